@@ -0,0 +1,265 @@
+use anyhow::{anyhow, bail, Result};
+use ethers::abi::token::{LenientTokenizer, Tokenizer};
+use ethers::abi::{Abi, AbiParser, Event, Function, Param, RawLog, Token};
+use ethers::types::{Bytes, Log};
+use ethers::utils::hex;
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Serialize)]
+pub struct DecodedCall {
+    pub function: String,
+    pub signature: String,
+    pub params: Vec<DecodedParam>,
+}
+
+#[derive(Serialize)]
+pub struct DecodedEvent {
+    pub event: String,
+    pub signature: String,
+    pub params: Vec<DecodedParam>,
+}
+
+#[derive(Serialize)]
+pub struct DecodedParam {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub value: String,
+}
+
+/// Loads an ABI from a JSON file holding either the bare ABI array or a
+/// compiler artifact with an `abi` field.
+pub fn load(path: &Path) -> Result<Abi> {
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let abi = match json.get("abi") {
+        Some(abi) => abi.clone(),
+        None => json,
+    };
+
+    serde_json::from_value(abi).map_err(|e| anyhow!("Invalid ABI in {}: {}", path.display(), e))
+}
+
+/// Decodes call data against the function whose selector matches its first
+/// four bytes. Returns None when no function in the ABI matches.
+pub fn decode_input(abi: &Abi, input: &[u8]) -> Result<Option<DecodedCall>> {
+    if input.len() < 4 {
+        return Ok(None);
+    }
+
+    let function = match abi.functions().find(|f| f.short_signature() == input[..4]) {
+        Some(function) => function,
+        None => return Ok(None),
+    };
+
+    let tokens = function.decode_input(&input[4..])?;
+
+    Ok(Some(DecodedCall {
+        function: function.name.clone(),
+        signature: signature(&function.name, &function.inputs),
+        params: decoded_params(&function.inputs, &tokens),
+    }))
+}
+
+/// Parses a function from `name(types)(outputs)` shorthand or a Solidity
+/// declaration such as `function balanceOf(address) view returns (uint256)`.
+pub fn parse_function(declaration: &str) -> Result<Function> {
+    AbiParser::default()
+        .parse_function(declaration)
+        .map_err(|e| anyhow!("Invalid function signature '{}': {}", declaration, e))
+}
+
+/// Parses an event such as `Transfer(address indexed from, address indexed to, uint256 value)`,
+/// with or without the leading `event` keyword.
+pub fn parse_event(declaration: &str) -> Result<Event> {
+    let declaration = declaration.trim();
+    let declaration = match declaration.starts_with("event ") {
+        true => declaration.to_string(),
+        false => format!("event {}", declaration),
+    };
+
+    AbiParser::default()
+        .parse_event(&declaration)
+        .map_err(|e| anyhow!("Invalid event signature '{}': {}", declaration, e))
+}
+
+/// Encodes call data from command-line arguments, one per function input.
+pub fn encode_call(function: &Function, args: &[String]) -> Result<Bytes> {
+    if args.len() != function.inputs.len() {
+        bail!(
+            "{} takes {} argument(s), got {}",
+            signature(&function.name, &function.inputs),
+            function.inputs.len(),
+            args.len()
+        );
+    }
+
+    let tokens = function.inputs.iter().zip(args).map(|(param, arg)| {
+        LenientTokenizer::tokenize(&param.kind, arg)
+            .map_err(|e| anyhow!("Invalid {} argument '{}': {}", param.kind, arg, e))
+    }).collect::<Result<Vec<_>>>()?;
+
+    Ok(function.encode_input(&tokens)?.into())
+}
+
+pub fn decode_output(function: &Function, data: &[u8]) -> Result<Vec<DecodedParam>> {
+    let tokens = function.decode_output(data)?;
+    Ok(decoded_params(&function.outputs, &tokens))
+}
+
+/// Decodes a log against the first non-anonymous event whose signature hash
+/// matches topic0. Returns None when none matches.
+pub fn decode_log(events: &[Event], log: &Log) -> Result<Option<DecodedEvent>> {
+    let topic0 = match log.topics.first() {
+        Some(topic0) => *topic0,
+        None => return Ok(None),
+    };
+
+    let event = match events.iter().find(|event| !event.anonymous && event.signature() == topic0) {
+        Some(event) => event,
+        None => return Ok(None),
+    };
+
+    let parsed = event.parse_log(RawLog {
+        topics: log.topics.clone(),
+        data: log.data.to_vec(),
+    })?;
+
+    // parse_log keeps the declaration order, so inputs line up with params
+    let params = event.inputs.iter().zip(parsed.params).map(|(input, param)| DecodedParam {
+        name: param.name,
+        kind: input.kind.to_string(),
+        value: format_token(&param.value),
+    }).collect();
+
+    Ok(Some(DecodedEvent {
+        event: event.name.clone(),
+        signature: format!(
+            "{}({})",
+            event.name,
+            event.inputs.iter().map(|input| input.kind.to_string()).collect::<Vec<_>>().join(",")
+        ),
+        params,
+    }))
+}
+
+pub fn signature(name: &str, params: &[Param]) -> String {
+    format!("{}({})", name, params.iter().map(|p| p.kind.to_string()).collect::<Vec<_>>().join(","))
+}
+
+fn decoded_params(params: &[Param], tokens: &[Token]) -> Vec<DecodedParam> {
+    params.iter().zip(tokens).map(|(param, token)| DecodedParam {
+        name: param.name.clone(),
+        kind: param.kind.to_string(),
+        value: format_token(token),
+    }).collect()
+}
+
+// Formats a token the way it would be written in Solidity
+pub fn format_token(token: &Token) -> String {
+    match token {
+        Token::Address(address) => format!("{:?}", address),
+        Token::FixedBytes(bytes) | Token::Bytes(bytes) => format!("0x{}", hex::encode(bytes)),
+        Token::Int(value) => ethers::types::I256::from_raw(*value).to_string(),
+        Token::Uint(value) => value.to_string(),
+        Token::Bool(value) => value.to_string(),
+        Token::String(value) => format!("{:?}", value),
+        Token::FixedArray(tokens) | Token::Array(tokens) => {
+            format!("[{}]", tokens.iter().map(format_token).collect::<Vec<_>>().join(", "))
+        }
+        Token::Tuple(tokens) => {
+            format!("({})", tokens.iter().map(format_token).collect::<Vec<_>>().join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::parse_abi;
+    use ethers::types::{Address, H256, U256};
+
+    const TRANSFER_TOPIC: &str = "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+
+    fn word(hex_value: &str) -> String {
+        format!("{:0>64}", hex_value)
+    }
+
+    #[test]
+    fn encodes_erc20_transfer() {
+        let function = parse_function("transfer(address,uint256)(bool)").unwrap();
+        let args = ["0x1111111111111111111111111111111111111111".to_string(), "1000".to_string()];
+        let data = encode_call(&function, &args).unwrap();
+
+        let expected = format!("a9059cbb{}{}", word(&"11".repeat(20)), word("3e8"));
+        assert_eq!(hex::encode(&data), expected);
+    }
+
+    #[test]
+    fn rejects_wrong_argument_counts_and_values() {
+        let function = parse_function("function balanceOf(address owner) view returns (uint256)").unwrap();
+        let error = encode_call(&function, &[]).unwrap_err();
+        assert_eq!(error.to_string(), "balanceOf(address) takes 1 argument(s), got 0");
+        assert!(encode_call(&function, &["not-an-address".to_string()]).is_err());
+    }
+
+    #[test]
+    fn decodes_input_by_selector() {
+        let abi = parse_abi(&[
+            "function transfer(address to, uint256 amount) returns (bool)",
+            "function approve(address spender, uint256 amount) returns (bool)",
+        ])
+        .unwrap();
+        let input = hex::decode(format!("095ea7b3{}{}", word(&"22".repeat(20)), word("ff"))).unwrap();
+
+        let call = decode_input(&abi, &input).unwrap().unwrap();
+        assert_eq!(call.function, "approve");
+        assert_eq!(call.signature, "approve(address,uint256)");
+        let values: Vec<(&str, &str)> = call.params.iter().map(|p| (p.name.as_str(), p.value.as_str())).collect();
+        assert_eq!(values, [("spender", "0x2222222222222222222222222222222222222222"), ("amount", "255")]);
+
+        // Unknown selector and truncated call data
+        assert!(decode_input(&abi, &hex::decode("deadbeef").unwrap()).unwrap().is_none());
+        assert!(decode_input(&abi, &[0xa9, 0x05]).unwrap().is_none());
+    }
+
+    #[test]
+    fn decodes_output() {
+        let function = parse_function("balanceOf(address)(uint256)").unwrap();
+        let data = hex::decode(word("2a")).unwrap();
+        let params = decode_output(&function, &data).unwrap();
+        assert_eq!(params[0].kind, "uint256");
+        assert_eq!(params[0].value, "42");
+    }
+
+    #[test]
+    fn decodes_transfer_log() {
+        let event = parse_event("Transfer(address indexed from, address indexed to, uint256 value)").unwrap();
+        assert_eq!(hex::encode(event.signature()), TRANSFER_TOPIC);
+
+        let from = Address::repeat_byte(0x11);
+        let to = Address::repeat_byte(0x22);
+        let log = Log {
+            topics: vec![event.signature(), H256::from(from), H256::from(to)],
+            data: hex::decode(word("3e8")).unwrap().into(),
+            ..Default::default()
+        };
+
+        let decoded = decode_log(std::slice::from_ref(&event), &log).unwrap().unwrap();
+        assert_eq!(decoded.signature, "Transfer(address,address,uint256)");
+        let values: Vec<String> = decoded.params.iter().map(|p| p.value.clone()).collect();
+        assert_eq!(values, [format!("{:?}", from), format!("{:?}", to), "1000".to_string()]);
+
+        let other = Log { topics: vec![H256::zero()], ..log };
+        assert!(decode_log(&[event], &other).unwrap().is_none());
+    }
+
+    #[test]
+    fn formats_tokens_like_solidity() {
+        assert_eq!(format_token(&Token::Int(U256::MAX)), "-1");
+        assert_eq!(format_token(&Token::String("a\"b".to_string())), r#""a\"b""#);
+        assert_eq!(format_token(&Token::FixedBytes(vec![0xab, 0xcd])), "0xabcd");
+        let tuple = Token::Tuple(vec![Token::Bool(true), Token::Array(vec![Token::Uint(1.into()), Token::Uint(2.into())])]);
+        assert_eq!(format_token(&tuple), "(true, [1, 2])");
+    }
+}
@@ -0,0 +1,356 @@
+use anyhow::{Context, Result};
+use colored::*;
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::types::{Address, BlockNumber, H256, U256};
+use ethers::utils::{format_ether, keccak256};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use tabled::{Table, Tabled};
+use tokio::time::{sleep, Duration};
+
+use crate::output::{emit, emit_record, OutputFormat};
+
+// Parallel block requests when scanning for recent transactions
+const SCAN_CONCURRENCY: usize = 8;
+
+pub struct AccountOptions {
+    pub address: Address,
+    pub slots: Vec<U256>,
+    pub blocks: u64,
+    pub limit: usize,
+    pub indexer: Option<String>,
+    pub watch: bool,
+    pub interval: u64,
+}
+
+#[derive(Serialize)]
+struct AccountReport {
+    address: String,
+    block: u64,
+    balance_wei: String,
+    balance_etc: String,
+    nonce: u64,
+    contract: bool,
+    code_size: usize,
+    code_hash: Option<String>,
+    storage: Vec<StorageSlot>,
+    // "indexer" or "scan"
+    transactions_source: String,
+    transactions: Vec<AccountTransaction>,
+}
+
+#[derive(Serialize)]
+struct StorageSlot {
+    slot: String,
+    value: String,
+}
+
+// Same shape as the indexer's /accounts/<address>/transactions entries
+#[derive(Deserialize, Serialize)]
+struct AccountTransaction {
+    hash: String,
+    block_number: u64,
+    timestamp: u64,
+    from: String,
+    to: Option<String>,
+    value: String,
+    nonce: u64,
+    status: Option<u64>,
+    gas_used: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct BalanceChange {
+    block: u64,
+    balance_wei: String,
+    balance_etc: String,
+    // Signed difference from the previous balance, in wei
+    delta_wei: String,
+    nonce: u64,
+}
+
+#[derive(Tabled)]
+struct AccountField {
+    field: String,
+    value: String,
+}
+
+#[derive(Tabled)]
+struct AccountTxRow {
+    block: u64,
+    hash: String,
+    direction: String,
+    counterparty: String,
+    value: String,
+    status: String,
+}
+
+/// Accepts decimal or 0x-prefixed hex storage slots.
+pub fn parse_slot(slot: &str) -> Result<U256, String> {
+    match slot.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16).map_err(|e| format!("invalid storage slot '{}': {}", slot, e)),
+        None => U256::from_dec_str(slot).map_err(|e| format!("invalid storage slot '{}': {:?}", slot, e)),
+    }
+}
+
+pub async fn run(rpc_url: &str, options: AccountOptions, output: OutputFormat) -> Result<()> {
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+    let address = options.address;
+
+    let block = provider.get_block_number().await?.as_u64();
+    let at = Some(BlockNumber::Number(block.into()).into());
+
+    let (balance, nonce, code) = tokio::try_join!(
+        provider.get_balance(address, at),
+        provider.get_transaction_count(address, at),
+        provider.get_code(address, at),
+    )?;
+
+    let mut storage = Vec::new();
+    for slot in &options.slots {
+        let mut key = [0u8; 32];
+        slot.to_big_endian(&mut key);
+        let value = provider.get_storage_at(address, H256(key), at).await?;
+        storage.push(StorageSlot {
+            slot: format!("{:#x}", slot),
+            value: format!("{:?}", value),
+        });
+    }
+
+    // Scanning is only a stand-in for an indexer that isn't there; one that
+    // answers with an error is reported instead
+    let indexed = match &options.indexer {
+        Some(indexer) => match indexed_transactions(indexer, address, options.limit).await {
+            Ok(transactions) => Some(transactions),
+            Err(e) if e.is_connect() || e.is_timeout() => None,
+            Err(e) => return Err(e).context(format!("Indexer {} failed", indexer)),
+        },
+        None => None,
+    };
+    let (transactions_source, transactions) = match indexed {
+        Some(transactions) => ("indexer", transactions),
+        None => ("scan", scan_transactions(&provider, address, block, options.blocks, options.limit).await?),
+    };
+
+    let report = AccountReport {
+        address: format!("{:?}", address),
+        block,
+        balance_wei: balance.to_string(),
+        balance_etc: format_ether(balance),
+        nonce: nonce.as_u64(),
+        contract: !code.is_empty(),
+        code_size: code.len(),
+        code_hash: (!code.is_empty()).then(|| format!("{:?}", H256(keccak256(&code)))),
+        storage,
+        transactions_source: transactions_source.to_string(),
+        transactions,
+    };
+
+    if options.watch && !output.is_table() {
+        emit_record(output, &report)?;
+    } else {
+        print_report(&report, &options, output)?;
+    }
+
+    if options.watch {
+        watch(&provider, address, block, balance, options.interval, output).await?;
+    }
+
+    Ok(())
+}
+
+async fn indexed_transactions(
+    indexer: &str,
+    address: Address,
+    limit: usize,
+) -> reqwest::Result<Vec<AccountTransaction>> {
+    let url = format!("{}/accounts/{:?}/transactions?limit={}", indexer, address, limit);
+    let client = reqwest::Client::new();
+
+    client
+        .get(&url)
+        .timeout(Duration::from_secs(2))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+}
+
+// Without an indexer, walk back over the last `blocks` blocks
+async fn scan_transactions(
+    provider: &Provider<Http>,
+    address: Address,
+    tip: u64,
+    blocks: u64,
+    limit: usize,
+) -> Result<Vec<AccountTransaction>> {
+    let from = tip.saturating_sub(blocks.saturating_sub(1));
+    let mut transactions = Vec::new();
+
+    let mut fetched = stream::iter((from..=tip).rev())
+        .map(|number| {
+            let provider = provider.clone();
+            async move { provider.get_block_with_txs(number).await }
+        })
+        .buffered(SCAN_CONCURRENCY);
+
+    while let Some(block) = fetched.next().await {
+        let block = match block? {
+            Some(block) => block,
+            None => continue,
+        };
+
+        for tx in block.transactions.iter().rev() {
+            if tx.from != address && tx.to != Some(address) {
+                continue;
+            }
+            transactions.push(AccountTransaction {
+                hash: format!("{:?}", tx.hash),
+                block_number: block.number.unwrap_or_default().as_u64(),
+                timestamp: block.timestamp.as_u64(),
+                from: format!("{:?}", tx.from),
+                to: tx.to.map(|to| format!("{:?}", to)),
+                value: tx.value.to_string(),
+                nonce: tx.nonce.as_u64(),
+                status: None,
+                gas_used: None,
+            });
+        }
+
+        if transactions.len() >= limit {
+            transactions.truncate(limit);
+            break;
+        }
+    }
+
+    Ok(transactions)
+}
+
+async fn watch(
+    provider: &Provider<Http>,
+    address: Address,
+    mut block: u64,
+    mut balance: U256,
+    interval: u64,
+    output: OutputFormat,
+) -> Result<()> {
+    if output.is_table() {
+        println!("\n{}", "Watching for balance changes (Ctrl+C to stop)".bright_blue().bold());
+    }
+
+    loop {
+        sleep(Duration::from_secs(interval)).await;
+
+        // Read at the latest block rather than each block in between, which
+        // non-archive nodes prune; changes between two polls are combined
+        let tip = provider.get_block_number().await?.as_u64();
+        if tip > block {
+            block = tip;
+            let current = provider.get_balance(address, None).await?;
+            if current == balance {
+                continue;
+            }
+
+            let nonce = provider.get_transaction_count(address, None).await?.as_u64();
+            let delta = if current > balance {
+                format!("+{}", current - balance)
+            } else {
+                format!("-{}", balance - current)
+            };
+            let change = BalanceChange {
+                block,
+                balance_wei: current.to_string(),
+                balance_etc: format_ether(current),
+                delta_wei: delta,
+                nonce,
+            };
+
+            if output.is_table() {
+                let delta_etc = if current > balance {
+                    format!("+{} ETC", format_ether(current - balance)).bright_green()
+                } else {
+                    format!("-{} ETC", format_ether(balance - current)).bright_red()
+                };
+                println!(
+                    "Block {} | Balance: {} ETC ({}) | Nonce: {}",
+                    block.to_string().bright_yellow(),
+                    change.balance_etc,
+                    delta_etc,
+                    nonce
+                );
+            } else {
+                emit_record(output, &change)?;
+            }
+
+            balance = current;
+        }
+    }
+}
+
+fn print_report(report: &AccountReport, options: &AccountOptions, output: OutputFormat) -> Result<()> {
+    if !output.is_table() {
+        return emit(output, report);
+    }
+
+    println!("{}", format!("Account {}", report.address).bright_blue().bold());
+    println!("{}", "=".repeat(50).bright_blue());
+
+    let mut rows = vec![
+        AccountField { field: "Block".to_string(), value: report.block.to_string() },
+        AccountField { field: "Balance".to_string(), value: format!("{} ETC", report.balance_etc) },
+        AccountField { field: "Balance (wei)".to_string(), value: report.balance_wei.clone() },
+        AccountField { field: "Nonce".to_string(), value: report.nonce.to_string() },
+        AccountField {
+            field: "Type".to_string(),
+            value: if report.contract { "contract".to_string() } else { "externally owned".to_string() },
+        },
+    ];
+    if let Some(code_hash) = &report.code_hash {
+        rows.push(AccountField { field: "Code Size".to_string(), value: format!("{} bytes", report.code_size) });
+        rows.push(AccountField { field: "Code Hash".to_string(), value: code_hash.clone() });
+    }
+    for slot in &report.storage {
+        rows.push(AccountField { field: format!("Slot {}", slot.slot), value: slot.value.clone() });
+    }
+
+    println!("\n{}", Table::new(rows));
+
+    let source = match report.transactions_source.as_str() {
+        "indexer" => "from the indexer".to_string(),
+        _ => format!("in the last {} blocks", options.blocks),
+    };
+    if report.transactions.is_empty() {
+        println!("\nNo transactions {}", source);
+        return Ok(());
+    }
+
+    println!("\n{}", format!("Recent transactions ({})", source).bright_yellow().bold());
+
+    let address = report.address.as_str();
+    let rows: Vec<AccountTxRow> = report.transactions.iter().map(|tx| {
+        let (direction, counterparty) = if tx.from == address && tx.to.as_deref() == Some(address) {
+            ("self", address.to_string())
+        } else if tx.from == address {
+            ("out", tx.to.clone().unwrap_or_else(|| "(contract creation)".to_string()))
+        } else {
+            ("in", tx.from.clone())
+        };
+        AccountTxRow {
+            block: tx.block_number,
+            hash: tx.hash.clone(),
+            direction: direction.to_string(),
+            counterparty,
+            value: format!("{} ETC", format_ether(U256::from_dec_str(&tx.value).unwrap_or_default())),
+            status: match tx.status {
+                Some(1) => "success".to_string(),
+                Some(_) => "failed".to_string(),
+                None => "-".to_string(),
+            },
+        }
+    }).collect();
+
+    println!("{}", Table::new(rows));
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use ethers::abi::Function;
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, BlockId, BlockNumber, TransactionRequest};
+use ethers::utils::hex;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::abi::{self, DecodedParam};
+use crate::output::{emit, OutputFormat};
+
+pub struct CallOptions {
+    pub to: Address,
+    pub signature: String,
+    pub args: Vec<String>,
+    pub abi: Option<PathBuf>,
+    pub from: Option<Address>,
+    pub block: String,
+}
+
+#[derive(Serialize)]
+struct CallReport {
+    to: String,
+    function: String,
+    block: String,
+    calldata: String,
+    raw: String,
+    outputs: Vec<DecodedParam>,
+}
+
+// A declaration is used as is; a bare name is looked up in the ABI, picking
+// the overload that takes as many arguments as were given
+fn resolve_function(signature: &str, args: &[String], abi_path: Option<&Path>) -> Result<Function> {
+    if signature.contains('(') {
+        return abi::parse_function(signature);
+    }
+
+    let abi_path = abi_path.ok_or_else(|| anyhow!("'{}' is not a signature, pass one like {}(uint256) or --abi", signature, signature))?;
+    let abi = abi::load(abi_path)?;
+    let overloads = abi
+        .functions_by_name(signature)
+        .map_err(|_| anyhow!("No function {} in {}", signature, abi_path.display()))?;
+
+    overloads
+        .iter()
+        .find(|function| function.inputs.len() == args.len())
+        .cloned()
+        .ok_or_else(|| anyhow!("No {} in the ABI takes {} argument(s)", signature, args.len()))
+}
+
+/// Runs eth_call against a contract and decodes the return data.
+pub async fn run(rpc_url: &str, options: CallOptions, output: OutputFormat) -> Result<()> {
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+    let function = resolve_function(&options.signature, &options.args, options.abi.as_deref())?;
+    let calldata = abi::encode_call(&function, &options.args)?;
+
+    let block_id: BlockId = match options.block.as_str() {
+        "latest" => BlockNumber::Latest.into(),
+        "pending" => BlockNumber::Pending.into(),
+        number => BlockNumber::Number(number.parse::<u64>()?.into()).into(),
+    };
+
+    let mut request = TransactionRequest::new().to(options.to).data(calldata.clone());
+    if let Some(from) = options.from {
+        request = request.from(from);
+    }
+    let typed: TypedTransaction = request.into();
+    let result = provider.call(&typed, Some(block_id)).await?;
+
+    let outputs = match function.outputs.is_empty() {
+        true => Vec::new(),
+        false => abi::decode_output(&function, &result)?,
+    };
+
+    let report = CallReport {
+        to: format!("{:?}", options.to),
+        function: abi::signature(&function.name, &function.inputs),
+        block: options.block,
+        calldata: format!("0x{}", hex::encode(&calldata)),
+        raw: format!("0x{}", hex::encode(&result)),
+        outputs,
+    };
+
+    if !output.is_table() {
+        return emit(output, &report);
+    }
+
+    println!(
+        "{} {} {}",
+        report.function.bright_blue().bold(),
+        "on".bright_black(),
+        report.to.bright_cyan()
+    );
+    println!("{}", "=".repeat(50).bright_blue());
+
+    if report.outputs.is_empty() {
+        // No declared outputs: show the raw return data
+        println!("{}", report.raw);
+        return Ok(());
+    }
+
+    for param in &report.outputs {
+        let name = if param.name.is_empty() { String::new() } else { format!(" {}", param.name) };
+        println!("{}{} = {}", param.kind.bright_black(), name, param.value.bright_green());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,282 @@
+use anyhow::{anyhow, bail, Result};
+use clap::Subcommand;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tabled::{Table, Tabled};
+
+use crate::output::{emit, OutputFormat};
+
+// Selects a profile when --profile is not given
+const PROFILE_ENV: &str = "MORDOR_PROFILE";
+
+const DEFAULT_PROFILE: &str = "local";
+
+// Written by `config init`; profiles not listed here fall back to the
+// built-in ones
+const EXAMPLE_CONFIG: &str = r#"# mordor-cli profiles. Select one with --profile <name> or MORDOR_PROFILE.
+# Fields left out of a profile take the local defaults; set a service to null
+# when the profile has none.
+default_profile: local
+
+profiles:
+  local:
+    rpc_url: http://localhost:8545
+    ws_url: ws://localhost:8546
+    fork_monitor: http://localhost:9090
+    gas_estimator: http://localhost:9091
+    indexer: http://localhost:9093
+    prometheus: http://localhost:9092
+    grafana: http://localhost:3000
+
+  staging:
+    rpc_url: https://mordor-rpc.staging.example.org
+    ws_url: wss://mordor-rpc.staging.example.org/ws
+    fork_monitor: https://fork-monitor.staging.example.org
+    gas_estimator: https://gas-estimator.staging.example.org
+    indexer: https://indexer.staging.example.org
+    prometheus: https://prometheus.staging.example.org
+    grafana: https://grafana.staging.example.org
+
+  public:
+    rpc_url: https://rpc.mordor.etccooperative.org
+    ws_url: null
+    fork_monitor: null
+    gas_estimator: null
+    indexer: null
+    prometheus: null
+    grafana: null
+"#;
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Show the endpoints of the selected profile
+    Show,
+
+    /// List the available profiles
+    Profiles,
+
+    /// Write an example config file with local, staging and public profiles
+    Init {
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+/// Endpoints used by the CLI, all overridable per command. Profiles that
+/// only point at a node leave the services unset.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Profile {
+    pub rpc_url: String,
+    pub ws_url: Option<String>,
+    pub fork_monitor: Option<String>,
+    pub gas_estimator: Option<String>,
+    pub indexer: Option<String>,
+    pub prometheus: Option<String>,
+    pub grafana: Option<String>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            rpc_url: "http://localhost:8545".to_string(),
+            ws_url: Some("ws://localhost:8546".to_string()),
+            fork_monitor: Some("http://localhost:9090".to_string()),
+            gas_estimator: Some("http://localhost:9091".to_string()),
+            indexer: Some("http://localhost:9093".to_string()),
+            prometheus: Some("http://localhost:9092".to_string()),
+            grafana: Some("http://localhost:3000".to_string()),
+        }
+    }
+}
+
+/// The endpoint given on the command line, else the profile's `field`.
+pub fn endpoint(given: Option<String>, profile: &Option<String>, field: &str) -> Result<String> {
+    given.or_else(|| profile.clone()).ok_or_else(|| {
+        anyhow!(
+            "The selected profile has no {} endpoint, pass one on the command line or set {} in the config file",
+            field,
+            field
+        )
+    })
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct ConfigFile {
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Serialize)]
+struct ProfileReport {
+    profile: String,
+    config: String,
+    #[serde(flatten)]
+    endpoints: Profile,
+}
+
+#[derive(Tabled)]
+struct EndpointRow {
+    endpoint: String,
+    url: String,
+}
+
+#[derive(Serialize, Tabled)]
+struct ProfileRow {
+    profile: String,
+    source: String,
+    rpc_url: String,
+}
+
+/// The config file lives in ~/.mordor unless --config says otherwise.
+pub fn default_config_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    Path::new(&home).join(".mordor").join("config.yaml")
+}
+
+fn builtin_profiles() -> BTreeMap<String, Profile> {
+    let mut profiles = BTreeMap::new();
+    profiles.insert("local".to_string(), Profile::default());
+    profiles.insert(
+        "public".to_string(),
+        // A public node only; none of the services run there
+        Profile {
+            rpc_url: "https://rpc.mordor.etccooperative.org".to_string(),
+            ws_url: None,
+            fork_monitor: None,
+            gas_estimator: None,
+            indexer: None,
+            prometheus: None,
+            grafana: None,
+        },
+    );
+    profiles
+}
+
+/// Reads the config file. A missing file is only an error when the path
+/// was given explicitly.
+pub fn load(path: &Path, explicit: bool) -> Result<ConfigFile> {
+    if !path.exists() && !explicit {
+        return Ok(ConfigFile::default());
+    }
+
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Could not read config {}: {}", path.display(), e))?;
+    serde_yaml::from_str(&contents).map_err(|e| anyhow!("Invalid config {}: {}", path.display(), e))
+}
+
+/// Picks the profile named by --profile, MORDOR_PROFILE or the config's
+/// default_profile, in that order. Config profiles shadow built-in ones.
+pub fn resolve(config: &ConfigFile, name: Option<&str>) -> Result<(String, Profile)> {
+    let name = name
+        .map(str::to_string)
+        .or_else(|| std::env::var(PROFILE_ENV).ok())
+        .or_else(|| config.default_profile.clone())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+
+    let profile = config
+        .profiles
+        .get(&name)
+        .cloned()
+        .or_else(|| builtin_profiles().remove(&name));
+
+    match profile {
+        Some(profile) => Ok((name, profile)),
+        None => {
+            let mut available: Vec<String> = builtin_profiles().into_keys().collect();
+            available.extend(config.profiles.keys().cloned());
+            available.sort();
+            available.dedup();
+            bail!("Unknown profile '{}', available: {}", name, available.join(", "))
+        }
+    }
+}
+
+pub fn run(
+    command: ConfigCommands,
+    path: &Path,
+    config: &ConfigFile,
+    name: &str,
+    profile: &Profile,
+    output: OutputFormat,
+) -> Result<()> {
+    match command {
+        ConfigCommands::Show => show(path, name, profile, output),
+        ConfigCommands::Profiles => profiles(config, name, output),
+        ConfigCommands::Init { force } => init(path, force),
+    }
+}
+
+fn show(path: &Path, name: &str, profile: &Profile, output: OutputFormat) -> Result<()> {
+    let report = ProfileReport {
+        profile: name.to_string(),
+        config: path.display().to_string(),
+        endpoints: profile.clone(),
+    };
+
+    if !output.is_table() {
+        return emit(output, &report);
+    }
+
+    println!("{}", format!("Profile {}", name).bright_blue().bold());
+    println!("{}", "=".repeat(50).bright_blue());
+
+    let optional = |url: &Option<String>| url.clone().unwrap_or_else(|| "-".to_string());
+    let rows = vec![
+        EndpointRow { endpoint: "RPC".to_string(), url: profile.rpc_url.clone() },
+        EndpointRow { endpoint: "WebSocket".to_string(), url: optional(&profile.ws_url) },
+        EndpointRow { endpoint: "Fork Monitor".to_string(), url: optional(&profile.fork_monitor) },
+        EndpointRow { endpoint: "Gas Estimator".to_string(), url: optional(&profile.gas_estimator) },
+        EndpointRow { endpoint: "Indexer".to_string(), url: optional(&profile.indexer) },
+        EndpointRow { endpoint: "Prometheus".to_string(), url: optional(&profile.prometheus) },
+        EndpointRow { endpoint: "Grafana".to_string(), url: optional(&profile.grafana) },
+    ];
+    println!("\n{}", Table::new(rows));
+    println!("\nConfig file: {}", report.config);
+
+    Ok(())
+}
+
+fn profiles(config: &ConfigFile, active: &str, output: OutputFormat) -> Result<()> {
+    let mut all: BTreeMap<String, (&str, Profile)> = builtin_profiles()
+        .into_iter()
+        .map(|(name, profile)| (name, ("built-in", profile)))
+        .collect();
+    for (name, profile) in &config.profiles {
+        all.insert(name.clone(), ("config", profile.clone()));
+    }
+
+    let rows: Vec<ProfileRow> = all
+        .into_iter()
+        .map(|(name, (source, profile))| ProfileRow {
+            profile: if name == active && output.is_table() { format!("{} *", name) } else { name },
+            source: source.to_string(),
+            rpc_url: profile.rpc_url,
+        })
+        .collect();
+
+    if !output.is_table() {
+        return emit(output, &rows);
+    }
+
+    println!("{}", Table::new(rows));
+
+    Ok(())
+}
+
+fn init(path: &Path, force: bool) -> Result<()> {
+    if path.exists() && !force {
+        bail!("{} already exists, use --force to overwrite it", path.display());
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, EXAMPLE_CONFIG)?;
+    println!("Wrote {}", path.display());
+
+    Ok(())
+}
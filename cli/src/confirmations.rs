@@ -0,0 +1,163 @@
+use anyhow::Result;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use tabled::{Table, Tabled};
+use tokio::time::{sleep, Duration};
+
+use crate::output::{emit, emit_record, OutputFormat};
+
+// Same shape as the fork monitor's /confirmations endpoint
+#[derive(Deserialize, Serialize)]
+pub struct ConfirmationReport {
+    pub block: u64,
+    pub target_risk: f64,
+    pub recommended_confirmations: Option<u64>,
+    pub attacker_share: f64,
+    pub unexplained_hashrate_share: f64,
+    pub hashrate_short: Option<f64>,
+    pub hashrate_long: Option<f64>,
+    pub mess_active: bool,
+    pub blocks_observed: u64,
+    pub reorgs_observed: usize,
+    pub max_reorg_depth: u64,
+    pub depths: Vec<DepthRisk>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct DepthRisk {
+    pub confirmations: u64,
+    pub natural: f64,
+    pub attack: f64,
+    pub probability: f64,
+    pub mess_factor: f64,
+}
+
+#[derive(Tabled)]
+struct DepthRow {
+    confirmations: u64,
+    natural: String,
+    attack: String,
+    probability: String,
+    mess_factor: String,
+}
+
+pub struct ConfirmationOptions {
+    pub risk: Option<f64>,
+    pub attacker_share: Option<f64>,
+    pub depths: u64,
+    pub watch: bool,
+    pub interval: u64,
+}
+
+async fn fetch_report(endpoint: &str, options: &ConfirmationOptions) -> Result<ConfirmationReport> {
+    let url = format!("{}/confirmations", endpoint.trim_end_matches('/'));
+    let mut query = Vec::new();
+    if let Some(risk) = options.risk {
+        query.push(("risk", risk));
+    }
+    if let Some(share) = options.attacker_share {
+        query.push(("attacker_share", share));
+    }
+
+    let response = reqwest::Client::new()
+        .get(&url)
+        .query(&query)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        anyhow::bail!("{}: {}", response.status(), response.text().await?);
+    }
+    Ok(response.json().await?)
+}
+
+pub async fn run(endpoint: &str, options: ConfirmationOptions, output: OutputFormat) -> Result<()> {
+    if !options.watch {
+        let mut report = fetch_report(endpoint, &options).await?;
+        report.depths.truncate(options.depths as usize);
+        if !output.is_table() {
+            return emit(output, &report);
+        }
+        print_report(&report);
+        return Ok(());
+    }
+
+    // One line (or record) per refresh, as the oracle moves with the chain
+    loop {
+        let mut report = fetch_report(endpoint, &options).await?;
+        report.depths.truncate(options.depths as usize);
+        if output.is_table() {
+            println!(
+                "Block {}: {} (attacker {:.0}%, {} reorgs, max depth {})",
+                report.block,
+                recommendation(&report),
+                report.attacker_share * 100.0,
+                report.reorgs_observed,
+                report.max_reorg_depth
+            );
+        } else {
+            emit_record(output, &report)?;
+        }
+        sleep(Duration::from_secs(options.interval)).await;
+    }
+}
+
+fn print_report(report: &ConfirmationReport) {
+    println!("{}", "Confirmation Safety".bright_blue().bold());
+    println!("{}", "=".repeat(70).bright_blue());
+
+    println!("\n  Block: {}", report.block);
+    println!("  Recommended: {}", recommendation(report));
+    println!(
+        "  Attacker share: {:.1}%{}",
+        report.attacker_share * 100.0,
+        if report.unexplained_hashrate_share > 0.0 {
+            format!(" ({:.1}% unexplained hashrate)", report.unexplained_hashrate_share * 100.0)
+        } else {
+            String::new()
+        }
+    );
+    if let (Some(short), Some(long)) = (report.hashrate_short, report.hashrate_long) {
+        println!("  Hashrate: {} now, {} over the last hour", format_hashrate(short), format_hashrate(long));
+    }
+    println!("  MESS (ECBP-1100): {}", if report.mess_active { "active".green() } else { "inactive".yellow() });
+    println!(
+        "  Reorgs observed: {} in {} blocks (deepest {})",
+        report.reorgs_observed, report.blocks_observed, report.max_reorg_depth
+    );
+
+    let rows: Vec<DepthRow> = report
+        .depths
+        .iter()
+        .map(|depth| DepthRow {
+            confirmations: depth.confirmations,
+            natural: format!("{:.2e}", depth.natural),
+            attack: format!("{:.2e}", depth.attack),
+            probability: format!("{:.2e}", depth.probability),
+            mess_factor: format!("{:.3}", depth.mess_factor),
+        })
+        .collect();
+
+    println!("\n{}", "Reorg probability by confirmations:".bright_yellow().bold());
+    println!("{}", Table::new(rows));
+}
+
+fn recommendation(report: &ConfirmationReport) -> ColoredString {
+    match report.recommended_confirmations {
+        Some(confirmations) => {
+            format!("{} confirmations for risk {:e}", confirmations, report.target_risk).bright_green()
+        }
+        None => format!("no depth the oracle covers reaches risk {:e}", report.target_risk).bright_red(),
+    }
+}
+
+fn format_hashrate(hashes_per_second: f64) -> String {
+    const UNITS: [&str; 6] = ["H/s", "kH/s", "MH/s", "GH/s", "TH/s", "PH/s"];
+    let mut value = hashes_per_second;
+    let mut unit = 0;
+    while value >= 1_000.0 && unit < UNITS.len() - 1 {
+        value /= 1_000.0;
+        unit += 1;
+    }
+    format!("{:.2} {}", value, UNITS[unit])
+}
@@ -0,0 +1,513 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::types::{Block, Transaction};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Block as Panel, Borders, Cell, Clear, Paragraph, Row, Sparkline, Table, TableState, Wrap,
+};
+use ratatui::Frame;
+use std::collections::VecDeque;
+use std::io::IsTerminal;
+use std::sync::{Arc, Mutex};
+use tokio::time::{sleep, Duration};
+
+use crate::config::Profile;
+use crate::{check_services, fetch_gas_recommendation, ForkEvent, GasRecommendation, HealthCheck};
+
+// Blocks kept for the sparklines and the recent blocks table
+const HISTORY_BLOCKS: u64 = 60;
+
+// How often the UI redraws and checks for key presses
+const TICK: Duration = Duration::from_millis(250);
+
+pub struct DashboardConfig {
+    pub profile: Profile,
+    pub interval: u64,
+}
+
+#[derive(Default)]
+struct DashboardState {
+    // Oldest first
+    blocks: VecDeque<Block<Transaction>>,
+    gas: Option<GasRecommendation>,
+    forks: Option<Vec<ForkEvent>>,
+    mempool: Option<(u64, u64)>,
+    health: Vec<HealthCheck>,
+    error: Option<String>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl DashboardState {
+    fn block_times(&self) -> Vec<u64> {
+        self.blocks
+            .iter()
+            .zip(self.blocks.iter().skip(1))
+            .map(|(parent, block)| block.timestamp.as_u64().saturating_sub(parent.timestamp.as_u64()))
+            .collect()
+    }
+
+    fn difficulties(&self) -> Vec<u64> {
+        self.blocks
+            .iter()
+            .map(|block| block.difficulty.min(u64::MAX.into()).as_u64())
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct View {
+    // Block number picked in the recent blocks table; None follows the head
+    selected: Option<u64>,
+    details: bool,
+}
+
+pub async fn run(config: DashboardConfig) -> Result<()> {
+    if !std::io::stdout().is_terminal() {
+        bail!("The dashboard needs an interactive terminal");
+    }
+
+    let provider = Provider::<Http>::try_from(config.profile.rpc_url.as_str())?;
+    let state = Arc::new(Mutex::new(DashboardState::default()));
+
+    let refresher = tokio::spawn(refresh_loop(provider, config, state.clone()));
+    let result = tokio::task::spawn_blocking(move || ui_loop(state)).await?;
+    refresher.abort();
+
+    result
+}
+
+async fn refresh_loop(provider: Provider<Http>, config: DashboardConfig, state: Arc<Mutex<DashboardState>>) {
+    loop {
+        let error = refresh(&provider, &config, &state).await.err().map(|e| e.to_string());
+
+        {
+            let mut state = state.lock().unwrap();
+            state.error = error;
+            state.updated_at = Some(Utc::now());
+        }
+
+        sleep(Duration::from_secs(config.interval)).await;
+    }
+}
+
+async fn refresh(provider: &Provider<Http>, config: &DashboardConfig, state: &Mutex<DashboardState>) -> Result<()> {
+    let client = reqwest::Client::new();
+
+    // Panels for services the profile has no endpoint for stay empty
+    let (gas, forks, mempool, health) = tokio::join!(
+        async { fetch_gas_recommendation(config.profile.gas_estimator.as_deref()?).await.ok() },
+        async {
+            let forks_url = format!("{}/forks", config.profile.fork_monitor.as_deref()?.trim_end_matches('/'));
+            let response = client.get(&forks_url).send().await.ok()?.error_for_status().ok()?;
+            response.json::<Vec<ForkEvent>>().await.ok()
+        },
+        provider.txpool_status(),
+        check_services(&config.profile),
+    );
+
+    {
+        let mut state = state.lock().unwrap();
+        state.gas = gas;
+        state.forks = forks;
+        state.mempool = mempool.ok().map(|status| (status.pending.as_u64(), status.queued.as_u64()));
+        state.health = health;
+    }
+
+    let tip = provider.get_block_number().await?.as_u64();
+    let next = {
+        let state = state.lock().unwrap();
+        match state.blocks.back().and_then(|block| block.number) {
+            Some(last) => (last.as_u64() + 1).max(tip.saturating_sub(HISTORY_BLOCKS - 1)),
+            None => tip.saturating_sub(HISTORY_BLOCKS - 1),
+        }
+    };
+
+    // Without a new head, refetch the tip so a same-height reorg replaces it
+    let next = next.min(tip);
+
+    for number in next..=tip {
+        let block = match provider.get_block_with_txs(number).await? {
+            Some(block) => block,
+            None => break,
+        };
+
+        let mut state = state.lock().unwrap();
+        while state
+            .blocks
+            .back()
+            .is_some_and(|last| last.number.unwrap_or_default().as_u64() >= number)
+        {
+            state.blocks.pop_back();
+        }
+        state.blocks.push_back(block);
+        while state.blocks.len() as u64 > HISTORY_BLOCKS {
+            state.blocks.pop_front();
+        }
+    }
+
+    Ok(())
+}
+
+fn ui_loop(state: Arc<Mutex<DashboardState>>) -> Result<()> {
+    let mut terminal = ratatui::init();
+    let mut view = View::default();
+
+    let result = (|| -> Result<()> {
+        loop {
+            terminal.draw(|frame| draw(frame, &state.lock().unwrap(), &view))?;
+
+            if !event::poll(TICK)? {
+                continue;
+            }
+            let key = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+
+            let numbers: Vec<u64> = state
+                .lock()
+                .unwrap()
+                .blocks
+                .iter()
+                .rev()
+                .filter_map(|block| block.number.map(|n| n.as_u64()))
+                .collect();
+
+            match key.code {
+                KeyCode::Char('q') => return Ok(()),
+                KeyCode::Esc | KeyCode::Backspace if view.details => view.details = false,
+                KeyCode::Esc => return Ok(()),
+                KeyCode::Enter => view.details = !numbers.is_empty(),
+                KeyCode::Up | KeyCode::Char('k') if !view.details => {
+                    view.selected = match selected_index(&numbers, view.selected) {
+                        0 | 1 => None,
+                        index => numbers.get(index - 1).copied(),
+                    };
+                }
+                KeyCode::Down | KeyCode::Char('j') if !view.details => {
+                    let index = selected_index(&numbers, view.selected) + 1;
+                    if let Some(&number) = numbers.get(index) {
+                        view.selected = Some(number);
+                    }
+                }
+                KeyCode::Home | KeyCode::Char('g') => view.selected = None,
+                _ => {}
+            }
+        }
+    })();
+
+    ratatui::restore();
+    result
+}
+
+// Position of the selection in the newest-first list; the head when it
+// isn't set or has scrolled out of the history
+fn selected_index(numbers: &[u64], selected: Option<u64>) -> usize {
+    selected
+        .and_then(|number| numbers.iter().position(|n| *n == number))
+        .unwrap_or(0)
+}
+
+fn draw(frame: &mut Frame, state: &DashboardState, view: &View) {
+    let [title, top, middle, bottom] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(10),
+        Constraint::Length(7),
+        Constraint::Min(8),
+    ])
+    .areas(frame.area());
+
+    let updated = state
+        .updated_at
+        .map(|at| at.format("%H:%M:%S").to_string())
+        .unwrap_or_else(|| "loading...".to_string());
+    let mut status = vec![
+        Span::from(" Mordor Dashboard ").bold().fg(Color::Cyan),
+        Span::from(format!("| updated {} | q quit, ↑↓ select, ⏎ details ", updated)).dark_gray(),
+    ];
+    if let Some(error) = &state.error {
+        status.push(Span::from(format!("| {}", error)).red());
+    }
+    frame.render_widget(Line::from(status), title);
+
+    let [head, gas, services] = Layout::horizontal([
+        Constraint::Percentage(40),
+        Constraint::Percentage(25),
+        Constraint::Percentage(35),
+    ])
+    .areas(top);
+    draw_head(frame, head, state);
+    draw_gas(frame, gas, state);
+    draw_services(frame, services, state);
+
+    let [block_times, difficulty] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(middle);
+    draw_block_times(frame, block_times, state);
+    draw_difficulty(frame, difficulty, state);
+
+    let [blocks, forks] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(bottom);
+    draw_blocks(frame, blocks, state, view);
+    draw_forks(frame, forks, state);
+
+    if view.details {
+        draw_details(frame, state, view);
+    }
+}
+
+fn panel(title: &str) -> Panel<'_> {
+    Panel::default()
+        .borders(Borders::ALL)
+        .title(Span::from(format!(" {} ", title)).bold())
+}
+
+fn field<'a>(name: &'a str, value: String) -> Line<'a> {
+    Line::from(vec![Span::from(format!("{:<12}", name)).dark_gray(), Span::from(value)])
+}
+
+fn gas_percent(block: &Block<Transaction>) -> f64 {
+    if block.gas_limit.is_zero() {
+        return 0.0;
+    }
+    block.gas_used.as_u64() as f64 / block.gas_limit.as_u64() as f64 * 100.0
+}
+
+fn draw_head(frame: &mut Frame, area: Rect, state: &DashboardState) {
+    let lines = match state.blocks.back() {
+        Some(block) => {
+            let timestamp = block.timestamp.as_u64();
+            let age = Utc::now().timestamp() - timestamp as i64;
+            vec![
+                field("Number", block.number.unwrap_or_default().to_string()),
+                field("Hash", block.hash.unwrap_or_default().to_string()),
+                field("Age", format!("{}s", age.max(0))),
+                field("Miner", format!("{:?}", block.author.unwrap_or_default())),
+                field("Txs", block.transactions.len().to_string()),
+                field(
+                    "Gas",
+                    format!("{} / {} ({:.1}%)", block.gas_used, block.gas_limit, gas_percent(block)),
+                ),
+                field("Difficulty", block.difficulty.to_string()),
+            ]
+        }
+        None => vec![Line::from("Waiting for blocks...").dark_gray()],
+    };
+
+    frame.render_widget(Paragraph::new(lines).block(panel("Head Block")), area);
+}
+
+fn draw_gas(frame: &mut Frame, area: Rect, state: &DashboardState) {
+    let gwei = |wei: u64| format!("{:.2} Gwei", wei as f64 / 1e9);
+    let lines = match &state.gas {
+        Some(gas) => vec![
+            field("Slow", gwei(gas.slow_wei)).yellow(),
+            field("Standard", gwei(gas.standard_wei)).cyan(),
+            field("Fast", gwei(gas.fast_wei)).green(),
+            field("Instant", gwei(gas.instant_wei)).magenta(),
+            Line::from(""),
+            field("Utilization", format!("{:.2}%", gas.utilization_percent)),
+        ],
+        None => vec![Line::from("Gas estimator unreachable").red()],
+    };
+
+    frame.render_widget(Paragraph::new(lines).block(panel("Gas Prices")), area);
+}
+
+fn draw_services(frame: &mut Frame, area: Rect, state: &DashboardState) {
+    let mut lines = vec![match state.mempool {
+        Some((pending, queued)) => field("Mempool", format!("{} pending, {} queued", pending, queued)),
+        None => field("Mempool", "n/a".to_string()),
+    }];
+    lines.push(Line::from(""));
+
+    for check in &state.health {
+        let status = if check.healthy {
+            Span::from("✓ OK").green()
+        } else {
+            Span::from("✗ DOWN").red()
+        };
+        lines.push(Line::from(vec![Span::from(format!("{:<17}", check.service)).dark_gray(), status]));
+    }
+
+    frame.render_widget(Paragraph::new(lines).block(panel("Mempool & Health")), area);
+}
+
+fn draw_block_times(frame: &mut Frame, area: Rect, state: &DashboardState) {
+    let times = state.block_times();
+    let title = match times.last() {
+        Some(last) => {
+            let avg = times.iter().sum::<u64>() as f64 / times.len() as f64;
+            format!("Block Time (last {}s, avg {:.1}s)", last, avg)
+        }
+        None => "Block Time".to_string(),
+    };
+
+    let sparkline = Sparkline::default()
+        .block(panel(&title))
+        .data(&times)
+        .style(Style::default().fg(Color::Yellow));
+    frame.render_widget(sparkline, area);
+}
+
+fn draw_difficulty(frame: &mut Frame, area: Rect, state: &DashboardState) {
+    let difficulties = state.difficulties();
+    let min = difficulties.iter().copied().min().unwrap_or(0);
+    let max = difficulties.iter().copied().max().unwrap_or(0);
+
+    // Plot the variation above the window minimum, otherwise small swings
+    // on a large difficulty all look the same height
+    let data: Vec<u64> = difficulties.iter().map(|d| d - min + 1).collect();
+    let title = format!("Difficulty ({} - {})", min, max);
+
+    let sparkline = Sparkline::default()
+        .block(panel(&title))
+        .data(&data)
+        .style(Style::default().fg(Color::Magenta));
+    frame.render_widget(sparkline, area);
+}
+
+fn draw_blocks(frame: &mut Frame, area: Rect, state: &DashboardState, view: &View) {
+    let rows: Vec<Row> = state
+        .blocks
+        .iter()
+        .rev()
+        .map(|block| {
+            let datetime = DateTime::<Utc>::from_timestamp(block.timestamp.as_u64() as i64, 0).unwrap_or_default();
+            Row::new(vec![
+                Cell::from(block.number.unwrap_or_default().to_string()),
+                Cell::from(datetime.format("%H:%M:%S").to_string()),
+                Cell::from(block.transactions.len().to_string()),
+                Cell::from(format!("{:.1}%", gas_percent(block))),
+                Cell::from(format!("{:?}", block.author.unwrap_or_default())),
+            ])
+        })
+        .collect();
+
+    let numbers: Vec<u64> = state
+        .blocks
+        .iter()
+        .rev()
+        .filter_map(|block| block.number.map(|n| n.as_u64()))
+        .collect();
+    let mut table_state = TableState::default().with_selected(Some(selected_index(&numbers, view.selected)));
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(10),
+            Constraint::Length(9),
+            Constraint::Length(5),
+            Constraint::Length(7),
+            Constraint::Min(10),
+        ],
+    )
+    .header(Row::new(vec!["Block", "Time", "Txs", "Gas", "Miner"]).bold().fg(Color::Cyan))
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    .block(panel("Recent Blocks"));
+
+    frame.render_stateful_widget(table, area, &mut table_state);
+}
+
+fn draw_forks(frame: &mut Frame, area: Rect, state: &DashboardState) {
+    let forks = match &state.forks {
+        Some(forks) => forks,
+        None => {
+            let message = Paragraph::new(Line::from("Fork monitor unreachable").red());
+            frame.render_widget(message.block(panel("Recent Forks")), area);
+            return;
+        }
+    };
+
+    if forks.is_empty() {
+        let message = Paragraph::new(Line::from("No forks detected").green());
+        frame.render_widget(message.block(panel("Recent Forks")), area);
+        return;
+    }
+
+    let rows: Vec<Row> = forks
+        .iter()
+        .rev()
+        .map(|fork| {
+            let datetime = DateTime::<Utc>::from_timestamp(fork.detected_at as i64, 0).unwrap_or_default();
+            Row::new(vec![
+                Cell::from(datetime.format("%m-%d %H:%M").to_string()),
+                Cell::from(fork.height.to_string()),
+                Cell::from(fork.depth.to_string()),
+                Cell::from(fork.competing_blocks.to_string()),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(12),
+            Constraint::Length(10),
+            Constraint::Length(6),
+            Constraint::Length(9),
+        ],
+    )
+    .header(Row::new(vec!["Detected", "Height", "Depth", "Competing"]).bold().fg(Color::Cyan))
+    .block(panel("Recent Forks"));
+
+    frame.render_widget(table, area);
+}
+
+fn draw_details(frame: &mut Frame, state: &DashboardState, view: &View) {
+    let numbers: Vec<u64> = state
+        .blocks
+        .iter()
+        .rev()
+        .filter_map(|block| block.number.map(|n| n.as_u64()))
+        .collect();
+    let block = match state.blocks.iter().rev().nth(selected_index(&numbers, view.selected)) {
+        Some(block) => block,
+        None => return,
+    };
+
+    let area = frame.area();
+    let popup = Rect {
+        x: area.width / 10,
+        y: area.height / 10,
+        width: area.width * 8 / 10,
+        height: area.height * 8 / 10,
+    };
+
+    let datetime = DateTime::<Utc>::from_timestamp(block.timestamp.as_u64() as i64, 0).unwrap_or_default();
+    let mut lines = vec![
+        field("Hash", format!("{:?}", block.hash.unwrap_or_default())),
+        field("Parent", format!("{:?}", block.parent_hash)),
+        field("Timestamp", datetime.format("%Y-%m-%d %H:%M:%S UTC").to_string()),
+        field("Miner", format!("{:?}", block.author.unwrap_or_default())),
+        field("Difficulty", block.difficulty.to_string()),
+        field(
+            "Gas",
+            format!("{} / {} ({:.2}%)", block.gas_used, block.gas_limit, gas_percent(block)),
+        ),
+        field("Size", format!("{} bytes", block.size.unwrap_or_default())),
+        field("Uncles", block.uncles.len().to_string()),
+        Line::from(""),
+        Line::from(format!("Transactions ({}):", block.transactions.len())).bold().yellow(),
+    ];
+
+    for tx in &block.transactions {
+        lines.push(Line::from(vec![
+            Span::from(format!("{} ", tx.hash)).dark_gray(),
+            Span::from(format!("{:?}", tx.from)).cyan(),
+            Span::from(" -> "),
+            match tx.to {
+                Some(to) => Span::from(format!("{:?}", to)).green(),
+                None => Span::from("contract creation").magenta(),
+            },
+            Span::from(format!(" {} gas @ {} wei", tx.gas, tx.gas_price.unwrap_or_default())),
+        ]));
+    }
+
+    let title = format!("Block #{} (Esc to close)", block.number.unwrap_or_default());
+    frame.render_widget(Clear, popup);
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }).block(panel(&title)), popup);
+}
@@ -0,0 +1,193 @@
+use anyhow::{bail, Result};
+use arrow_array::{ArrayRef, Int64Array, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use clap::ValueEnum;
+use parquet::arrow::ArrowWriter;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, IsTerminal, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+// Parquet rows are buffered and written as one row group per this many rows
+const PARQUET_BATCH_ROWS: usize = 1_024;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+    Parquet,
+}
+
+/// A flat record that can be written in every export format.
+pub trait ExportRow: Serialize + Sized {
+    fn schema() -> SchemaRef;
+    fn record_batch(rows: &[Self]) -> Result<RecordBatch>;
+}
+
+#[derive(Serialize)]
+pub struct BlockRow {
+    pub height: u64,
+    pub hash: String,
+    pub parent_hash: String,
+    pub timestamp: u64,
+    pub miner: String,
+    pub difficulty: u64,
+    pub gas_used: u64,
+    pub gas_limit: u64,
+    pub tx_count: u64,
+    pub uncle_count: u64,
+    // Seconds since the parent block; negative when timestamps go backwards
+    pub block_time: Option<i64>,
+}
+
+impl ExportRow for BlockRow {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("height", DataType::UInt64, false),
+            Field::new("hash", DataType::Utf8, false),
+            Field::new("parent_hash", DataType::Utf8, false),
+            Field::new("timestamp", DataType::UInt64, false),
+            Field::new("miner", DataType::Utf8, false),
+            Field::new("difficulty", DataType::UInt64, false),
+            Field::new("gas_used", DataType::UInt64, false),
+            Field::new("gas_limit", DataType::UInt64, false),
+            Field::new("tx_count", DataType::UInt64, false),
+            Field::new("uncle_count", DataType::UInt64, false),
+            Field::new("block_time", DataType::Int64, true),
+        ]))
+    }
+
+    fn record_batch(rows: &[Self]) -> Result<RecordBatch> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt64Array::from_iter_values(rows.iter().map(|r| r.height))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| &r.hash))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| &r.parent_hash))),
+            Arc::new(UInt64Array::from_iter_values(rows.iter().map(|r| r.timestamp))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| &r.miner))),
+            Arc::new(UInt64Array::from_iter_values(rows.iter().map(|r| r.difficulty))),
+            Arc::new(UInt64Array::from_iter_values(rows.iter().map(|r| r.gas_used))),
+            Arc::new(UInt64Array::from_iter_values(rows.iter().map(|r| r.gas_limit))),
+            Arc::new(UInt64Array::from_iter_values(rows.iter().map(|r| r.tx_count))),
+            Arc::new(UInt64Array::from_iter_values(rows.iter().map(|r| r.uncle_count))),
+            Arc::new(Int64Array::from_iter(rows.iter().map(|r| r.block_time))),
+        ];
+
+        Ok(RecordBatch::try_new(Self::schema(), columns)?)
+    }
+}
+
+#[derive(Serialize)]
+pub struct ForkRow {
+    pub detected_at: u64,
+    pub height: u64,
+    pub depth: u64,
+    pub competing_blocks: u64,
+    pub active_forks: u64,
+    // Competing block hashes, separated by ';'
+    pub hashes: String,
+}
+
+impl ExportRow for ForkRow {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("detected_at", DataType::UInt64, false),
+            Field::new("height", DataType::UInt64, false),
+            Field::new("depth", DataType::UInt64, false),
+            Field::new("competing_blocks", DataType::UInt64, false),
+            Field::new("active_forks", DataType::UInt64, false),
+            Field::new("hashes", DataType::Utf8, false),
+        ]))
+    }
+
+    fn record_batch(rows: &[Self]) -> Result<RecordBatch> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt64Array::from_iter_values(rows.iter().map(|r| r.detected_at))),
+            Arc::new(UInt64Array::from_iter_values(rows.iter().map(|r| r.height))),
+            Arc::new(UInt64Array::from_iter_values(rows.iter().map(|r| r.depth))),
+            Arc::new(UInt64Array::from_iter_values(rows.iter().map(|r| r.competing_blocks))),
+            Arc::new(UInt64Array::from_iter_values(rows.iter().map(|r| r.active_forks))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| &r.hashes))),
+        ];
+
+        Ok(RecordBatch::try_new(Self::schema(), columns)?)
+    }
+}
+
+type Output = Box<dyn Write + Send>;
+
+enum Sink<R> {
+    Csv(csv::Writer<Output>),
+    Jsonl(BufWriter<Output>),
+    Parquet {
+        writer: ArrowWriter<Output>,
+        pending: Vec<R>,
+    },
+}
+
+/// Streams rows to a file, or to stdout when no path is given.
+pub struct Exporter<R: ExportRow> {
+    sink: Sink<R>,
+    rows: usize,
+}
+
+impl<R: ExportRow> Exporter<R> {
+    pub fn new(format: ExportFormat, path: Option<&Path>) -> Result<Self> {
+        let output: Output = match path {
+            Some(path) => Box::new(File::create(path)?),
+            None => {
+                if matches!(format, ExportFormat::Parquet) && std::io::stdout().is_terminal() {
+                    bail!("Refusing to write Parquet to a terminal, use --output or a pipe");
+                }
+                Box::new(std::io::stdout())
+            }
+        };
+
+        let sink = match format {
+            ExportFormat::Csv => Sink::Csv(csv::Writer::from_writer(output)),
+            ExportFormat::Jsonl => Sink::Jsonl(BufWriter::new(output)),
+            ExportFormat::Parquet => Sink::Parquet {
+                writer: ArrowWriter::try_new(output, R::schema(), None)?,
+                pending: Vec::with_capacity(PARQUET_BATCH_ROWS),
+            },
+        };
+
+        Ok(Self { sink, rows: 0 })
+    }
+
+    pub fn write(&mut self, row: R) -> Result<()> {
+        match &mut self.sink {
+            Sink::Csv(writer) => writer.serialize(&row)?,
+            Sink::Jsonl(writer) => {
+                serde_json::to_writer(&mut *writer, &row)?;
+                writer.write_all(b"\n")?;
+            }
+            Sink::Parquet { writer, pending } => {
+                pending.push(row);
+                if pending.len() >= PARQUET_BATCH_ROWS {
+                    writer.write(&R::record_batch(pending)?)?;
+                    pending.clear();
+                }
+            }
+        }
+
+        self.rows += 1;
+        Ok(())
+    }
+
+    // Flushes everything buffered and returns the number of rows written
+    pub fn finish(self) -> Result<usize> {
+        match self.sink {
+            Sink::Csv(mut writer) => writer.flush()?,
+            Sink::Jsonl(mut writer) => writer.flush()?,
+            Sink::Parquet { mut writer, pending } => {
+                if !pending.is_empty() {
+                    writer.write(&R::record_batch(&pending)?)?;
+                }
+                writer.close()?;
+            }
+        }
+
+        Ok(self.rows)
+    }
+}
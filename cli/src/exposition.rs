@@ -0,0 +1,473 @@
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricType {
+    Counter,
+    Gauge,
+    Histogram,
+    Summary,
+    Untyped,
+}
+
+#[derive(Clone)]
+pub struct Sample {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub value: f64,
+}
+
+/// All samples of one metric, including a histogram's `_bucket`, `_sum`
+/// and `_count` series.
+pub struct Family {
+    pub name: String,
+    pub help: Option<String>,
+    pub kind: MetricType,
+    pub samples: Vec<Sample>,
+}
+
+/// One histogram or summary series: a label set without `le`/`quantile`.
+pub struct Distribution {
+    pub labels: BTreeMap<String, String>,
+    // Cumulative (upper bound, count) buckets for histograms, (quantile,
+    // value) pairs for summaries
+    pub points: Vec<(f64, f64)>,
+    pub sum: Option<f64>,
+    pub count: Option<f64>,
+}
+
+/// A parsed Prometheus text exposition (format 0.0.4).
+pub struct Exposition {
+    pub families: Vec<Family>,
+}
+
+impl Exposition {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut samples = Vec::new();
+        let mut types = HashMap::new();
+        let mut help = HashMap::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(comment) = line.strip_prefix('#') {
+                let mut parts = comment.trim_start().splitn(3, ' ');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some("HELP"), Some(name), text) => {
+                        help.insert(name.to_string(), unescape(text.unwrap_or_default()));
+                    }
+                    (Some("TYPE"), Some(name), Some(kind)) => {
+                        let kind = match kind.trim() {
+                            "counter" => MetricType::Counter,
+                            "gauge" => MetricType::Gauge,
+                            "histogram" => MetricType::Histogram,
+                            "summary" => MetricType::Summary,
+                            _ => MetricType::Untyped,
+                        };
+                        types.insert(name.to_string(), kind);
+                    }
+                    // Any other comment is free text
+                    _ => {}
+                }
+                continue;
+            }
+
+            let sample = parse_sample(line).map_err(|e| anyhow!("Line {}: {}", number + 1, e))?;
+            samples.push(sample);
+        }
+
+        Ok(Self::group(samples, types, help))
+    }
+
+    /// Groups samples that came without TYPE lines, e.g. from a PromQL
+    /// query; `_bucket` series with an `le` label are taken as histograms.
+    pub fn from_samples(samples: Vec<Sample>) -> Self {
+        Self::group(samples, HashMap::new(), HashMap::new())
+    }
+
+    fn group(samples: Vec<Sample>, mut types: HashMap<String, MetricType>, help: HashMap<String, String>) -> Self {
+        let inferred: HashSet<String> = samples
+            .iter()
+            .filter(|s| s.labels.contains_key("le"))
+            .filter_map(|s| s.name.strip_suffix("_bucket"))
+            .filter(|base| !types.contains_key(*base))
+            .map(str::to_string)
+            .collect();
+        for base in inferred {
+            types.insert(base, MetricType::Histogram);
+        }
+
+        let mut families: Vec<Family> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+
+        for sample in samples {
+            let (name, kind) = family_of(&sample.name, &types);
+            let position = *index.entry(name.clone()).or_insert_with(|| {
+                families.push(Family {
+                    help: help.get(&name).cloned(),
+                    name: name.clone(),
+                    kind,
+                    samples: Vec::new(),
+                });
+                families.len() - 1
+            });
+            families[position].samples.push(sample);
+        }
+
+        Self { families }
+    }
+
+    pub fn family(&self, name: &str) -> Option<&Family> {
+        self.families.iter().find(|f| f.name == name)
+    }
+
+    /// The value of a metric with a single series. A missing metric is an
+    /// error rather than zero.
+    pub fn value(&self, name: &str) -> Result<f64> {
+        let family = self.family(name).ok_or_else(|| anyhow!("Metric {} not found", name))?;
+        let series: Vec<&Sample> = family.samples.iter().filter(|s| s.name == name).collect();
+
+        match series.as_slice() {
+            [sample] => Ok(sample.value),
+            [] => bail!("Metric {} has no samples", name),
+            _ => series
+                .iter()
+                .find(|s| s.labels.is_empty())
+                .map(|s| s.value)
+                .ok_or_else(|| anyhow!("Metric {} has {} series, expected one", name, series.len())),
+        }
+    }
+}
+
+impl Family {
+    /// Histogram or summary series, one per label set.
+    pub fn distributions(&self) -> Vec<Distribution> {
+        let point_label = match self.kind {
+            MetricType::Histogram => "le",
+            MetricType::Summary => "quantile",
+            _ => return Vec::new(),
+        };
+
+        let mut series: Vec<Distribution> = Vec::new();
+        for sample in &self.samples {
+            let mut labels = sample.labels.clone();
+            let point = labels.remove(point_label);
+
+            let position = match series.iter().position(|d| d.labels == labels) {
+                Some(position) => position,
+                None => {
+                    series.push(Distribution { labels, points: Vec::new(), sum: None, count: None });
+                    series.len() - 1
+                }
+            };
+            let distribution = &mut series[position];
+
+            match sample.name.strip_prefix(&self.name) {
+                Some("_sum") => distribution.sum = Some(sample.value),
+                Some("_count") => distribution.count = Some(sample.value),
+                _ => {
+                    if let Some(at) = point.as_deref().and_then(|p| parse_value(p).ok()) {
+                        distribution.points.push((at, sample.value));
+                    }
+                }
+            }
+        }
+
+        for distribution in &mut series {
+            distribution.points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        }
+        series
+    }
+}
+
+impl Distribution {
+    pub fn mean(&self) -> Option<f64> {
+        match (self.sum, self.count) {
+            (Some(sum), Some(count)) if count > 0.0 => Some(sum / count),
+            _ => None,
+        }
+    }
+
+    /// Estimates a histogram quantile the way PromQL's histogram_quantile
+    /// does, interpolating linearly within the bucket.
+    pub fn histogram_quantile(&self, q: f64) -> Option<f64> {
+        let total = self.points.last()?.1;
+        if total <= 0.0 {
+            return None;
+        }
+        let rank = q * total;
+
+        let mut lower = (0.0, 0.0);
+        for &(upper, count) in &self.points {
+            if count >= rank {
+                // Above the last finite bucket only its bound is known
+                if upper.is_infinite() {
+                    return Some(lower.0);
+                }
+                if count == lower.1 {
+                    return Some(upper);
+                }
+                let start = if lower.0 == 0.0 && upper < 0.0 { upper } else { lower.0 };
+                return Some(start + (upper - start) * (rank - lower.1) / (count - lower.1));
+            }
+            lower = (upper, count);
+        }
+        None
+    }
+}
+
+/// `name{label="value",...}`, the way Prometheus prints a series.
+pub fn series_name(name: &str, labels: &BTreeMap<String, String>) -> String {
+    if labels.is_empty() {
+        return name.to_string();
+    }
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| format!("{}={:?}", key, value))
+        .collect();
+    format!("{}{{{}}}", name, labels.join(","))
+}
+
+// Histogram and summary samples belong to the family of their base name
+fn family_of(name: &str, types: &HashMap<String, MetricType>) -> (String, MetricType) {
+    if let Some(kind) = types.get(name) {
+        return (name.to_string(), *kind);
+    }
+    for suffix in ["_bucket", "_sum", "_count"] {
+        let Some(base) = name.strip_suffix(suffix) else {
+            continue;
+        };
+        match types.get(base) {
+            Some(MetricType::Histogram) => return (base.to_string(), MetricType::Histogram),
+            Some(MetricType::Summary) if suffix != "_bucket" => return (base.to_string(), MetricType::Summary),
+            _ => {}
+        }
+    }
+    (name.to_string(), MetricType::Untyped)
+}
+
+// metric_name [{label="value",...}] value [timestamp]
+fn parse_sample(line: &str) -> Result<Sample> {
+    let name_end = line
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == ':'))
+        .unwrap_or(line.len());
+    let name = &line[..name_end];
+    if name.is_empty() {
+        bail!("Missing metric name in '{}'", line);
+    }
+
+    let mut rest = &line[name_end..];
+    let mut labels = BTreeMap::new();
+    if let Some(body) = rest.strip_prefix('{') {
+        let (parsed, remainder) = parse_labels(body)?;
+        labels = parsed;
+        rest = remainder;
+    }
+
+    let mut fields = rest.split_whitespace();
+    let value = fields.next().ok_or_else(|| anyhow!("Missing value for {}", name))?;
+    // A trailing timestamp is allowed but not needed here
+    if fields.nth(1).is_some() {
+        bail!("Unexpected trailing fields after {}", name);
+    }
+
+    Ok(Sample { name: name.to_string(), labels, value: parse_value(value)? })
+}
+
+// Parses `key="value",...}` and returns what follows the closing brace
+fn parse_labels(body: &str) -> Result<(BTreeMap<String, String>, &str)> {
+    let mut labels = BTreeMap::new();
+    let mut rest = body.trim_start();
+
+    loop {
+        if let Some(after) = rest.strip_prefix('}') {
+            return Ok((labels, after));
+        }
+
+        let (key, after) = rest
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Malformed labels in '{{{}'", body))?;
+        let after = after
+            .trim_start()
+            .strip_prefix('"')
+            .ok_or_else(|| anyhow!("Label {} is not quoted", key.trim()))?;
+
+        let mut value = String::new();
+        let mut chars = after.char_indices();
+        let end = loop {
+            match chars.next() {
+                Some((i, '"')) => break i,
+                Some((_, '\\')) => match chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, c)) => value.push(c),
+                    None => bail!("Unterminated label {}", key.trim()),
+                },
+                Some((_, c)) => value.push(c),
+                None => bail!("Unterminated label {}", key.trim()),
+            }
+        };
+        labels.insert(key.trim().to_string(), value);
+
+        rest = after[end + 1..].trim_start();
+        rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+    }
+}
+
+fn parse_value(value: &str) -> Result<f64> {
+    match value {
+        "+Inf" | "Inf" => Ok(f64::INFINITY),
+        "-Inf" => Ok(f64::NEG_INFINITY),
+        "NaN" => Ok(f64::NAN),
+        _ => value.parse().map_err(|_| anyhow!("Invalid sample value '{}'", value)),
+    }
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some(other) => unescaped.push(other),
+                None => unescaped.push('\\'),
+            },
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = r#"# HELP etc_mordor_block_time_seconds Time between blocks\nin seconds
+# TYPE etc_mordor_block_time_seconds histogram
+etc_mordor_block_time_seconds_bucket{chain="mordor",le="0.1"} 10
+etc_mordor_block_time_seconds_bucket{chain="mordor",le="0.5"} 30
+etc_mordor_block_time_seconds_bucket{chain="mordor",le="1"} 40
+etc_mordor_block_time_seconds_bucket{chain="mordor",le="+Inf"} 50
+etc_mordor_block_time_seconds_sum{chain="mordor"} 20
+etc_mordor_block_time_seconds_count{chain="mordor"} 50
+etc_mordor_block_time_seconds_bucket{chain="other",le="0.1"} 0
+etc_mordor_block_time_seconds_bucket{chain="other",le="0.5"} 0
+etc_mordor_block_time_seconds_bucket{chain="other",le="1"} 4
+etc_mordor_block_time_seconds_bucket{chain="other",le="+Inf"} 4
+# TYPE etc_mordor_rpc_latency_seconds summary
+etc_mordor_rpc_latency_seconds{quantile="0.5"} 0.02
+etc_mordor_rpc_latency_seconds{quantile="0.99"} 0.3
+etc_mordor_rpc_latency_seconds_sum 1.5
+etc_mordor_rpc_latency_seconds_count 40
+# A free-text comment
+etc_mordor_block_number 123456 1700000000000
+etc_mordor_peer_label{client="core-geth \"v1\"\\x\ny"} 1
+"#;
+
+    fn histogram(exposition: &Exposition, chain: &str) -> Distribution {
+        exposition
+            .family("etc_mordor_block_time_seconds")
+            .unwrap()
+            .distributions()
+            .into_iter()
+            .find(|d| d.labels.get("chain").map(String::as_str) == Some(chain))
+            .unwrap()
+    }
+
+    #[test]
+    fn groups_histogram_and_summary_series_into_families() {
+        let exposition = Exposition::parse(TEXT).unwrap();
+        let names: Vec<&str> = exposition.families.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "etc_mordor_block_time_seconds",
+                "etc_mordor_rpc_latency_seconds",
+                "etc_mordor_block_number",
+                "etc_mordor_peer_label"
+            ]
+        );
+
+        let family = exposition.family("etc_mordor_block_time_seconds").unwrap();
+        assert!(family.kind == MetricType::Histogram);
+        assert_eq!(family.help.as_deref(), Some("Time between blocks\nin seconds"));
+        assert_eq!(family.samples.len(), 10);
+        assert_eq!(family.distributions().len(), 2);
+
+        let summary = &exposition.family("etc_mordor_rpc_latency_seconds").unwrap().distributions()[0];
+        assert_eq!(summary.points, [(0.5, 0.02), (0.99, 0.3)]);
+        assert_eq!(summary.mean(), Some(1.5 / 40.0));
+    }
+
+    #[test]
+    fn parses_values_timestamps_and_escaped_labels() {
+        let exposition = Exposition::parse(TEXT).unwrap();
+        assert_eq!(exposition.value("etc_mordor_block_number").unwrap(), 123456.0);
+
+        let sample = &exposition.family("etc_mordor_peer_label").unwrap().samples[0];
+        assert_eq!(sample.labels["client"], "core-geth \"v1\"\\x\ny");
+        assert_eq!(series_name("up", &sample.labels), r#"up{client="core-geth \"v1\"\\x\ny"}"#);
+
+        let buckets = histogram(&exposition, "mordor").points;
+        assert_eq!(buckets.last(), Some(&(f64::INFINITY, 50.0)));
+    }
+
+    #[test]
+    fn value_needs_exactly_one_series() {
+        let exposition = Exposition::parse(TEXT).unwrap();
+        assert!(exposition.value("etc_mordor_missing").is_err());
+        assert!(exposition.value("etc_mordor_block_time_seconds_sum").is_err());
+    }
+
+    #[test]
+    fn reports_the_line_of_a_malformed_sample() {
+        let error = Exposition::parse("etc_mordor_ok 1\netc_mordor_bad{le=\"1} 2\n").err().unwrap();
+        assert!(error.to_string().starts_with("Line 2:"), "{}", error);
+        assert!(Exposition::parse("etc_mordor_bad one").is_err());
+        assert!(Exposition::parse("etc_mordor_bad 1 2 3").is_err());
+    }
+
+    #[test]
+    fn infers_histograms_from_untyped_samples() {
+        let sample = |le: &str, value| Sample {
+            name: "etc_mordor_gas_bucket".to_string(),
+            labels: BTreeMap::from([("le".to_string(), le.to_string())]),
+            value,
+        };
+        let exposition = Exposition::from_samples(vec![sample("1", 2.0), sample("+Inf", 4.0)]);
+        let family = exposition.family("etc_mordor_gas").unwrap();
+        assert!(family.kind == MetricType::Histogram);
+        assert_eq!(family.distributions()[0].histogram_quantile(0.25), Some(0.5));
+    }
+
+    #[test]
+    fn histogram_quantile_matches_promql() {
+        let exposition = Exposition::parse(TEXT).unwrap();
+        let mordor = histogram(&exposition, "mordor");
+
+        // Interpolated within the first bucket, from zero
+        assert!((mordor.histogram_quantile(0.05).unwrap() - 0.025).abs() < 1e-12);
+        // rank 25 falls in (0.1, 0.5] holding ranks 10..30
+        assert!((mordor.histogram_quantile(0.5).unwrap() - 0.4).abs() < 1e-12);
+        assert_eq!(mordor.histogram_quantile(0.8), Some(1.0));
+        // In the +Inf bucket only the highest finite bound is known
+        assert_eq!(mordor.histogram_quantile(0.9), Some(1.0));
+        assert_eq!(mordor.mean(), Some(0.4));
+
+        // Empty lower buckets start the interpolation at their bound
+        let other = histogram(&exposition, "other");
+        assert!((other.histogram_quantile(0.5).unwrap() - 0.75).abs() < 1e-12);
+        assert_eq!(other.mean(), None);
+    }
+
+    #[test]
+    fn histogram_quantile_of_an_empty_histogram_is_none() {
+        let empty = Distribution { labels: BTreeMap::new(), points: vec![(1.0, 0.0), (f64::INFINITY, 0.0)], sum: None, count: None };
+        assert_eq!(empty.histogram_quantile(0.5), None);
+    }
+}
@@ -0,0 +1,765 @@
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use colored::*;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use tabled::{Table, Tabled};
+
+use crate::config::{self, Profile};
+use crate::exposition::{Exposition, MetricType};
+use crate::output::{emit, OutputFormat};
+use crate::registry::{self, MetricSpec, Service, METRICS};
+use crate::scrape_exposition;
+
+const DASHBOARD_UID: &str = "mordor-testnet";
+
+const GRID_WIDTH: u32 = 24;
+
+const PANEL_HEIGHT: u32 = 8;
+
+#[derive(Subcommand)]
+pub enum GenCommands {
+    /// Grafana dashboard JSON built from the metric registry
+    Dashboard {
+        /// Output file (defaults to stdout)
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+
+    /// Prometheus recording and alerting rules built from the metric registry
+    Rules {
+        /// Output file (defaults to stdout)
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+
+    /// Compare the metric registry with what the services export
+    Check {
+        /// Fork monitor endpoint (defaults to the profile's)
+        #[arg(long)]
+        fork_monitor: Option<String>,
+
+        /// Gas estimator endpoint (defaults to the profile's)
+        #[arg(long)]
+        gas_estimator: Option<String>,
+
+        /// Indexer endpoint (defaults to the profile's)
+        #[arg(long)]
+        indexer: Option<String>,
+    },
+}
+
+enum PanelKind {
+    Stat,
+    TimeSeries,
+}
+
+struct Panel {
+    title: &'static str,
+    description: &'static str,
+    kind: PanelKind,
+    unit: &'static str,
+    width: u32,
+    // (expression, legend)
+    targets: Vec<(String, String)>,
+}
+
+struct Row {
+    title: &'static str,
+    panels: Vec<Panel>,
+}
+
+#[derive(Serialize)]
+struct RuleFile {
+    groups: Vec<RuleGroup>,
+}
+
+#[derive(Serialize)]
+struct RuleGroup {
+    name: String,
+    interval: String,
+    rules: Vec<Rule>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Rule {
+    Recording {
+        record: String,
+        expr: String,
+    },
+    Alerting {
+        alert: String,
+        expr: String,
+        #[serde(rename = "for")]
+        duration: String,
+        labels: BTreeMap<String, String>,
+        annotations: BTreeMap<String, String>,
+    },
+}
+
+#[derive(Serialize, Tabled)]
+struct DriftRow {
+    service: String,
+    metric: String,
+    problem: String,
+}
+
+pub async fn run(
+    command: GenCommands,
+    profile: &Profile,
+    output: OutputFormat,
+) -> Result<()> {
+    match command {
+        GenCommands::Dashboard { file } => {
+            write(file.as_deref(), &serde_json::to_string_pretty(&dashboard())?)
+        }
+        GenCommands::Rules { file } => {
+            let yaml = serde_yaml::to_string(&rules())?;
+            write(file.as_deref(), &format!("# Generated by `mordor-cli gen rules`, do not edit\n{}", yaml))
+        }
+        GenCommands::Check { fork_monitor, gas_estimator, indexer } => {
+            let endpoints = [
+                (Service::ForkMonitor, config::endpoint(fork_monitor, &profile.fork_monitor, "fork_monitor")?),
+                (Service::GasEstimator, config::endpoint(gas_estimator, &profile.gas_estimator, "gas_estimator")?),
+                (Service::Indexer, config::endpoint(indexer, &profile.indexer, "indexer")?),
+            ];
+            check(&endpoints, output).await
+        }
+    }
+}
+
+fn write(file: Option<&Path>, contents: &str) -> Result<()> {
+    let Some(path) = file else {
+        println!("{}", contents.trim_end());
+        return Ok(());
+    };
+
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, format!("{}\n", contents.trim_end()))?;
+    println!("Wrote {}", path.display());
+
+    Ok(())
+}
+
+// PromQL building blocks; they take registry entries so a renamed metric
+// fails to compile instead of leaving a blank panel
+
+fn quantile(metric: &MetricSpec, q: f64, window: &str) -> String {
+    format!("histogram_quantile({}, sum by (le) (rate({}_bucket[{}])))", q, metric.name, window)
+}
+
+fn mean(metric: &MetricSpec, window: &str) -> String {
+    format!("sum(rate({0}_sum[{1}])) / sum(rate({0}_count[{1}]))", metric.name, window)
+}
+
+fn increase(metric: &MetricSpec, window: &str) -> String {
+    format!("sum(increase({}[{}]))", metric.name, window)
+}
+
+fn rate(metric: &MetricSpec, window: &str) -> String {
+    format!("sum(rate({}[{}]))", metric.name, window)
+}
+
+fn by(label: &str, expr: &str) -> String {
+    format!("sum by ({}) ({})", label, expr)
+}
+
+fn stat(title: &'static str, metric: &MetricSpec, unit: &'static str) -> Panel {
+    Panel {
+        title,
+        description: metric.help,
+        kind: PanelKind::Stat,
+        unit,
+        width: 6,
+        targets: vec![(metric.name.to_string(), String::new())],
+    }
+}
+
+fn series(title: &'static str, metric: &MetricSpec, unit: &'static str, targets: Vec<(String, &str)>) -> Panel {
+    Panel {
+        title,
+        description: metric.help,
+        kind: PanelKind::TimeSeries,
+        unit,
+        width: 12,
+        targets: targets.into_iter().map(|(expr, legend)| (expr, legend.to_string())).collect(),
+    }
+}
+
+fn rows() -> Vec<Row> {
+    use registry::*;
+
+    let gwei = |metric: &MetricSpec| format!("{} / 1e9", metric.name);
+
+    vec![
+        Row {
+            title: "Chain",
+            panels: vec![
+                stat("Block height", &BLOCK_HEIGHT, "none"),
+                stat("Active forks", &ACTIVE_FORKS, "none"),
+                stat("Peers", &PEER_COUNT, "none"),
+                stat("Indexer lag", &INDEXER_LAG, "none"),
+                series("Block time", &BLOCK_TIME, "s", vec![
+                    (quantile(&BLOCK_TIME, 0.5, "15m"), "p50"),
+                    (quantile(&BLOCK_TIME, 0.9, "15m"), "p90"),
+                    (quantile(&BLOCK_TIME, 0.99, "15m"), "p99"),
+                    (mean(&BLOCK_TIME, "15m"), "mean"),
+                ]),
+                series("Gas used and limit", &BLOCK_GAS_USED, "short", vec![
+                    (BLOCK_GAS_USED.name.to_string(), "used"),
+                    (BLOCK_GAS_LIMIT.name.to_string(), "limit"),
+                ]),
+                series("Transactions per block", &TRANSACTION_COUNT, "none", vec![
+                    (TRANSACTION_COUNT.name.to_string(), "latest block"),
+                    (AVG_TX_PER_BLOCK.name.to_string(), "average"),
+                ]),
+                series("Difficulty", &BLOCK_DIFFICULTY, "short", vec![
+                    (mean(&BLOCK_DIFFICULTY, "15m"), "mean"),
+                ]),
+            ],
+        },
+        Row {
+            title: "Forks",
+            panels: vec![
+                stat("Safe confirmations", &RECOMMENDED_CONFIRMATIONS, "none"),
+                series("Forks per hour", &FORKS, "none", vec![
+                    (increase(&FORKS, "1h"), "forks"),
+                    (increase(&MISSED_BLOCKS, "1h"), "missed blocks"),
+                ]),
+                series("Reorg depth", &FORK_DEPTH, "none", vec![
+                    (quantile(&FORK_DEPTH, 0.5, "1h"), "p50"),
+                    (quantile(&FORK_DEPTH, 0.99, "1h"), "p99"),
+                ]),
+                series("Reorged transactions per hour", &REORGED_TRANSACTIONS, "none", vec![
+                    (by("kind", &format!("increase({}[1h])", REORGED_TRANSACTIONS.name)), "{{kind}}"),
+                ]),
+            ],
+        },
+        Row {
+            title: "Anomalies",
+            panels: vec![
+                series("Anomaly score", &ANOMALY_SCORE, "none", vec![
+                    (ANOMALY_SCORE.name.to_string(), "{{kind}}"),
+                ]),
+                series("Anomalies per hour", &ANOMALIES, "none", vec![
+                    (by("kind", &format!("increase({}[1h])", ANOMALIES.name)), "{{kind}}"),
+                ]),
+                series("Estimated hashrate", &HASHRATE_ESTIMATE, "short", vec![
+                    (HASHRATE_ESTIMATE.name.to_string(), "{{window}}"),
+                ]),
+                series("Timestamp violations per hour", &TIMESTAMP_VIOLATIONS, "none", vec![
+                    (by("kind, miner", &format!("increase({}[1h])", TIMESTAMP_VIOLATIONS.name)), "{{kind}} {{miner}}"),
+                ]),
+            ],
+        },
+        Row {
+            title: "Gas",
+            panels: vec![
+                series("Gas price (gwei)", &GAS_PRICE_MEDIAN, "none", vec![
+                    (gwei(&GAS_PRICE_MIN), "min"),
+                    (gwei(&GAS_PRICE_P25), "p25"),
+                    (gwei(&GAS_PRICE_MEDIAN), "median"),
+                    (gwei(&GAS_PRICE_MEAN), "mean"),
+                    (gwei(&GAS_PRICE_P75), "p75"),
+                    (gwei(&GAS_PRICE_MAX), "max"),
+                ]),
+                series("Gas utilization", &GAS_UTILIZATION_AVG, "percent", vec![
+                    (GAS_UTILIZATION.name.to_string(), "recent blocks"),
+                    (GAS_UTILIZATION_AVG.name.to_string(), "{{window}} average"),
+                ]),
+                series("Full blocks", &FULL_BLOCKS, "none", vec![
+                    (FULL_BLOCKS.name.to_string(), "last {{window}}"),
+                ]),
+                series("Gas limit votes per hour", &GAS_LIMIT_VOTES, "none", vec![
+                    (by("direction", &format!("increase({}[1h])", GAS_LIMIT_VOTES.name)), "{{direction}}"),
+                ]),
+                series("Gas limit trend", &GAS_LIMIT_TREND, "short", vec![
+                    (GAS_LIMIT_TREND.name.to_string(), "gas/hour"),
+                    (GAS_LIMIT_DRIFT.name.to_string(), "direction"),
+                ]),
+                series("Gas used by kind (1h)", &GAS_USED_BY_KIND, "short", vec![
+                    (by("kind", &format!("{}{{window=\"1h\"}}", GAS_USED_BY_KIND.name)), "{{kind}}"),
+                ]),
+            ],
+        },
+        Row {
+            title: "Peers",
+            panels: vec![
+                series("Peers by direction", &PEERS_BY_DIRECTION, "none", vec![
+                    (PEERS_BY_DIRECTION.name.to_string(), "{{direction}}"),
+                    (PEER_COUNT.name.to_string(), "total"),
+                ]),
+                series("Peers by client", &PEERS_BY_CLIENT, "none", vec![
+                    (PEERS_BY_CLIENT.name.to_string(), "{{client}}"),
+                ]),
+                series("Fork ID", &PEERS_BY_FORK_ID, "none", vec![
+                    (PEERS_BY_FORK_ID.name.to_string(), "{{status}}"),
+                ]),
+                series("Fork ID match ratio", &FORK_ID_MATCH_RATIO, "percentunit", vec![
+                    (FORK_ID_MATCH_RATIO.name.to_string(), "match ratio"),
+                ]),
+                series("Peer churn per hour", &PEER_CONNECTS, "none", vec![
+                    (increase(&PEER_CONNECTS, "1h"), "connects"),
+                    (increase(&PEER_DISCONNECTS, "1h"), "disconnects"),
+                ]),
+            ],
+        },
+        Row {
+            title: "Sync",
+            panels: vec![
+                stat("Syncing", &SYNC_SYNCING, "bool_yes_no"),
+                stat("Sync progress", &SYNC_PROGRESS, "percent"),
+                stat("Sync rate", &SYNC_BLOCKS_PER_SECOND, "none"),
+                stat("Sync ETA", &SYNC_ETA, "s"),
+                series("Sync blocks", &SYNC_CURRENT_BLOCK, "none", vec![
+                    (SYNC_CURRENT_BLOCK.name.to_string(), "current"),
+                    (SYNC_HIGHEST_BLOCK.name.to_string(), "highest"),
+                ]),
+                series("State sync", &SYNC_PULLED_STATES, "short", vec![
+                    (SYNC_PULLED_STATES.name.to_string(), "pulled"),
+                    (SYNC_KNOWN_STATES.name.to_string(), "known"),
+                ]),
+            ],
+        },
+        Row {
+            title: "Indexer",
+            panels: vec![
+                series("Indexed height", &INDEXER_INDEXED_HEIGHT, "none", vec![
+                    (INDEXER_INDEXED_HEIGHT.name.to_string(), "indexed"),
+                    (INDEXER_CHAIN_HEIGHT.name.to_string(), "chain"),
+                ]),
+                series("Indexing rate", &INDEXER_BLOCKS, "ops", vec![
+                    (rate(&INDEXER_BLOCKS, "5m"), "blocks/s"),
+                    (rate(&INDEXER_TRANSACTIONS, "5m"), "transactions/s"),
+                ]),
+                series("Index rollbacks per hour", &INDEXER_REORG_ROLLBACKS, "none", vec![
+                    (increase(&INDEXER_REORG_ROLLBACKS, "1h"), "reorgs"),
+                    (increase(&INDEXER_ROLLED_BACK_BLOCKS, "1h"), "blocks"),
+                ]),
+            ],
+        },
+    ]
+}
+
+fn datasource() -> Value {
+    json!({ "type": "prometheus", "uid": "${datasource}" })
+}
+
+fn panel_json(panel: &Panel, id: u32, x: u32, y: u32) -> Value {
+    let targets: Vec<Value> = panel
+        .targets
+        .iter()
+        .enumerate()
+        .map(|(i, (expr, legend))| {
+            json!({
+                "refId": ((b'A' + i as u8) as char).to_string(),
+                "datasource": datasource(),
+                "expr": expr,
+                "legendFormat": legend,
+            })
+        })
+        .collect();
+
+    let (kind, options) = match panel.kind {
+        PanelKind::Stat => ("stat", json!({
+            "reduceOptions": { "calcs": ["lastNotNull"], "fields": "", "values": false },
+            "colorMode": "value",
+            "graphMode": "area",
+        })),
+        PanelKind::TimeSeries => ("timeseries", json!({
+            "legend": { "displayMode": "list", "placement": "bottom", "showLegend": true },
+            "tooltip": { "mode": "multi", "sort": "none" },
+        })),
+    };
+
+    json!({
+        "id": id,
+        "type": kind,
+        "title": panel.title,
+        "description": panel.description,
+        "datasource": datasource(),
+        "gridPos": { "x": x, "y": y, "w": panel.width, "h": PANEL_HEIGHT },
+        "fieldConfig": { "defaults": { "unit": panel.unit }, "overrides": [] },
+        "options": options,
+        "targets": targets,
+    })
+}
+
+fn dashboard() -> Value {
+    let mut panels = Vec::new();
+    let mut id = 1;
+    let mut y = 0;
+
+    for row in rows() {
+        panels.push(json!({
+            "id": id,
+            "type": "row",
+            "title": row.title,
+            "collapsed": false,
+            "gridPos": { "x": 0, "y": y, "w": GRID_WIDTH, "h": 1 },
+            "panels": [],
+        }));
+        id += 1;
+        y += 1;
+
+        let mut x = 0;
+        for panel in &row.panels {
+            if x + panel.width > GRID_WIDTH {
+                x = 0;
+                y += PANEL_HEIGHT;
+            }
+            panels.push(panel_json(panel, id, x, y));
+            id += 1;
+            x += panel.width;
+        }
+        y += PANEL_HEIGHT;
+    }
+
+    json!({
+        "uid": DASHBOARD_UID,
+        "title": "Mordor Testnet",
+        "description": "Generated by `mordor-cli gen dashboard`, do not edit",
+        "tags": ["mordor", "etc"],
+        "timezone": "browser",
+        "schemaVersion": 39,
+        "version": 1,
+        "editable": false,
+        "refresh": "30s",
+        "time": { "from": "now-6h", "to": "now" },
+        "templating": {
+            "list": [{
+                "name": "datasource",
+                "label": "Data source",
+                "type": "datasource",
+                "query": "prometheus",
+                "current": {},
+            }],
+        },
+        "annotations": {
+            "list": [{
+                "name": "Forks",
+                "datasource": datasource(),
+                "enable": true,
+                "iconColor": "red",
+                "expr": format!("{} > 0", increase(&registry::FORKS, "1m")),
+                "titleFormat": "Fork detected",
+            }],
+        },
+        "panels": panels,
+    })
+}
+
+fn record(name: &str, expr: String) -> Rule {
+    Rule::Recording { record: format!("etc_mordor:{}", name), expr }
+}
+
+fn alert(name: &str, expr: String, duration: &str, severity: &str, summary: &str, description: &str) -> Rule {
+    Rule::Alerting {
+        alert: format!("Mordor{}", name),
+        expr,
+        duration: duration.to_string(),
+        labels: BTreeMap::from([("severity".to_string(), severity.to_string())]),
+        annotations: BTreeMap::from([
+            ("summary".to_string(), summary.to_string()),
+            ("description".to_string(), description.to_string()),
+        ]),
+    }
+}
+
+fn rules() -> RuleFile {
+    use registry::*;
+
+    let recording = vec![
+        record("block_time_seconds:p50_15m", quantile(&BLOCK_TIME, 0.5, "15m")),
+        record("block_time_seconds:p90_15m", quantile(&BLOCK_TIME, 0.9, "15m")),
+        record("block_time_seconds:p99_15m", quantile(&BLOCK_TIME, 0.99, "15m")),
+        record("block_time_seconds:mean_15m", mean(&BLOCK_TIME, "15m")),
+        record("forks:increase_1h", increase(&FORKS, "1h")),
+        record("peer_churn:increase_1h", format!("{} + {}", increase(&PEER_CONNECTS, "1h"), increase(&PEER_DISCONNECTS, "1h"))),
+        record("indexer_blocks:rate_5m", rate(&INDEXER_BLOCKS, "5m")),
+    ];
+
+    let mut alerting = vec![
+        alert(
+            "NoNewBlocks",
+            format!("changes({}[10m]) == 0", BLOCK_HEIGHT.name),
+            "5m",
+            "critical",
+            "No new Mordor blocks",
+            "The block height has not changed for 15 minutes.",
+        ),
+        alert(
+            "SlowBlocks",
+            "etc_mordor:block_time_seconds:p90_15m > 30".to_string(),
+            "15m",
+            "warning",
+            "Mordor blocks are slow",
+            "p90 block time is {{ $value | humanize }}s over the last 15 minutes.",
+        ),
+        alert(
+            "DeepReorg",
+            format!(
+                "sum(increase({0}_count[15m])) - sum(increase({0}_bucket{{le=\"10\"}}[15m])) > 0",
+                FORK_DEPTH.name
+            ),
+            "0m",
+            "critical",
+            "Mordor reorg deeper than 10 blocks",
+            "{{ $value }} reorg(s) deeper than 10 blocks in the last 15 minutes.",
+        ),
+        alert(
+            "FrequentForks",
+            "etc_mordor:forks:increase_1h > 10".to_string(),
+            "0m",
+            "warning",
+            "Frequent forks on Mordor",
+            "{{ $value }} forks detected in the last hour.",
+        ),
+        alert(
+            "DoubleSpend",
+            format!("sum(increase({}{{kind=\"replaced\"}}[15m])) > 0", REORGED_TRANSACTIONS.name),
+            "0m",
+            "critical",
+            "Reorg replaced a transaction on Mordor",
+            "{{ $value }} transaction(s) from orphaned blocks were replaced by another with the same sender and nonce in the last 15 minutes. See /reorgs on the fork monitor.",
+        ),
+        alert(
+            "ReorgDroppedTransactions",
+            format!("sum(increase({}{{kind=\"dropped\"}}[15m])) > 0", REORGED_TRANSACTIONS.name),
+            "0m",
+            "warning",
+            "Reorg dropped transactions on Mordor",
+            "{{ $value }} transaction(s) from orphaned blocks are not in the canonical chain. See /reorgs on the fork monitor.",
+        ),
+        alert(
+            "NoSafeConfirmationDepth",
+            format!("{} == -1", RECOMMENDED_CONFIRMATIONS.name),
+            "15m",
+            "warning",
+            "No confirmation depth is safe on Mordor",
+            "No depth up to CONFIRMATION_MAX_DEPTH brings the reorg risk under the target. See /confirmations on the fork monitor.",
+        ),
+        alert(
+            "BlockDrought",
+            format!("{}{{kind=\"drought\"}} >= 1", ANOMALY_SCORE.name),
+            "0m",
+            "warning",
+            "Mordor block drought",
+            "The gap since the last block is {{ $value | humanize }}x past the drought threshold.",
+        ),
+        alert(
+            "HashrateAnomaly",
+            format!("{}{{kind=~\"hashrate_drop|hashrate_spike\"}} >= 1", ANOMALY_SCORE.name),
+            "10m",
+            "warning",
+            "Mordor hashrate changed sharply",
+            "{{ $labels.kind }}: the last 20 blocks' hashrate is at least 2x off the last hour's.",
+        ),
+        alert(
+            "TimestampPattern",
+            format!("{}{{kind=\"timestamp_pattern\"}} >= 1", ANOMALY_SCORE.name),
+            "10m",
+            "warning",
+            "Mordor block times look manipulated",
+            "Recent block times vary far less than honest mining would produce.",
+        ),
+        alert(
+            "InvalidBlockTimestamp",
+            format!(
+                "sum by (kind, miner) (increase({}{{kind=~\"future|non_monotonic\"}}[15m])) > 0",
+                TIMESTAMP_VIOLATIONS.name
+            ),
+            "0m",
+            "warning",
+            "Mordor block with an invalid timestamp",
+            "{{ $labels.miner }} mined {{ $value }} block(s) with {{ $labels.kind }} timestamps in the last 15 minutes.",
+        ),
+        alert(
+            "MinIntervalBlocks",
+            format!(
+                "sum by (miner) (increase({}{{kind=\"min_interval\"}}[1h])) > 100",
+                TIMESTAMP_VIOLATIONS.name
+            ),
+            "0m",
+            "warning",
+            "Miner is timestamping blocks at the minimum interval",
+            "{{ $labels.miner }} mined {{ $value }} minimum-interval blocks in the last hour, a timewarp pattern.",
+        ),
+        alert(
+            "LowPeerCount",
+            format!("{} == 1", PEER_COUNT_LOW.name),
+            "5m",
+            "warning",
+            "Node has too few peers",
+            "The node's peer count has been below the configured minimum for 5 minutes.",
+        ),
+        alert(
+            "ForkIdMismatch",
+            format!("{} < 0.5", FORK_ID_MATCH_RATIO.name),
+            "15m",
+            "warning",
+            "Most peers advertise a different fork ID",
+            "Only {{ $value | humanizePercentage }} of peers with a known fork ID advertise ours.",
+        ),
+        alert(
+            "SyncStalled",
+            format!("{} == 1 and {} == 0", SYNC_SYNCING.name, SYNC_BLOCKS_PER_SECOND.name),
+            "15m",
+            "warning",
+            "Node sync has stalled",
+            "The node is syncing but has imported no blocks for 15 minutes.",
+        ),
+        alert(
+            "IndexerLagging",
+            format!("{} > 100", INDEXER_LAG.name),
+            "10m",
+            "warning",
+            "Indexer is behind the chain",
+            "The indexer is {{ $value }} blocks behind the chain tip.",
+        ),
+        alert(
+            "HighGasUtilization",
+            format!("{} > 90", GAS_UTILIZATION.name),
+            "30m",
+            "info",
+            "Mordor blocks are nearly full",
+            "Gas utilization has been {{ $value | humanize }}% for 30 minutes.",
+        ),
+    ];
+
+    for service in Service::ALL {
+        alert_service_down(&mut alerting, service);
+    }
+
+    RuleFile {
+        groups: vec![
+            RuleGroup { name: "mordor-recording".to_string(), interval: "1m".to_string(), rules: recording },
+            RuleGroup { name: "mordor-alerts".to_string(), interval: "1m".to_string(), rules: alerting },
+        ],
+    }
+}
+
+fn alert_service_down(rules: &mut Vec<Rule>, service: Service) {
+    let job = service.job();
+    let name: String = job
+        .split('-')
+        .map(|part| part[..1].to_uppercase() + &part[1..])
+        .collect();
+
+    rules.push(alert(
+        &format!("{}Down", name),
+        format!("up{{job=\"{}\"}} == 0", job),
+        "2m",
+        "critical",
+        &format!("{} is down", job),
+        &format!("Prometheus cannot scrape {} on {{{{ $labels.instance }}}}.", job),
+    ));
+}
+
+// Registry entries missing from a service, exported with another type or
+// labels, and exported metrics the registry does not know
+async fn check(endpoints: &[(Service, String)], output: OutputFormat) -> Result<()> {
+    let mut drift = Vec::new();
+    let mut checked = 0;
+    let mut unverified = 0;
+
+    for (service, endpoint) in endpoints {
+        let exposition = match scrape_exposition(endpoint).await {
+            Ok(exposition) => exposition,
+            Err(e) => {
+                drift.push(DriftRow {
+                    service: service.job().to_string(),
+                    metric: "-".to_string(),
+                    problem: format!("unreachable: {}", e),
+                });
+                continue;
+            }
+        };
+
+        for spec in METRICS.iter().filter(|spec| spec.service == *service) {
+            // Labelled metrics are not exported at all until a series exists
+            if exposition.family(spec.name).is_none() && !spec.labels.is_empty() {
+                unverified += 1;
+                continue;
+            }
+            checked += 1;
+            if let Some(problem) = compare(spec, &exposition) {
+                drift.push(DriftRow {
+                    service: service.job().to_string(),
+                    metric: spec.name.to_string(),
+                    problem,
+                });
+            }
+        }
+
+        for family in exposition.families.iter().filter(|f| f.name.starts_with("etc_mordor_")) {
+            if !METRICS.iter().any(|spec| spec.name == family.name) {
+                drift.push(DriftRow {
+                    service: service.job().to_string(),
+                    metric: family.name.clone(),
+                    problem: "exported but not in the registry".to_string(),
+                });
+            }
+        }
+    }
+
+    if !output.is_table() {
+        emit(output, &drift)?;
+    } else {
+        match drift.is_empty() {
+            true => println!("{} {} metrics match the registry", "✓".green(), checked),
+            false => println!("{}", Table::new(&drift)),
+        }
+        if unverified > 0 {
+            println!("{} labelled metric(s) have no series yet and were not checked", unverified);
+        }
+    }
+
+    match drift.is_empty() {
+        true => Ok(()),
+        false => Err(anyhow!("{} difference(s) between the registry and the exported metrics", drift.len())),
+    }
+}
+
+fn compare(spec: &MetricSpec, exposition: &Exposition) -> Option<String> {
+    let Some(family) = exposition.family(spec.name) else {
+        return Some("not exported".to_string());
+    };
+
+    if family.kind != spec.kind {
+        return Some(format!("exported as {}, registry says {}", kind_name(family.kind), kind_name(spec.kind)));
+    }
+
+    let labels: BTreeSet<&str> = family
+        .samples
+        .iter()
+        .flat_map(|s| s.labels.keys())
+        .map(String::as_str)
+        .filter(|label| !matches!(*label, "le" | "quantile"))
+        .collect();
+    let expected: BTreeSet<&str> = spec.labels.iter().copied().collect();
+    if labels != expected {
+        return Some(format!(
+            "labels {{{}}}, registry says {{{}}}",
+            labels.into_iter().collect::<Vec<_>>().join(","),
+            expected.into_iter().collect::<Vec<_>>().join(",")
+        ));
+    }
+
+    None
+}
+
+fn kind_name(kind: MetricType) -> &'static str {
+    match kind {
+        MetricType::Counter => "counter",
+        MetricType::Gauge => "gauge",
+        MetricType::Histogram => "histogram",
+        MetricType::Summary => "summary",
+        MetricType::Untyped => "untyped",
+    }
+}
@@ -0,0 +1,609 @@
+use anyhow::{bail, Result};
+use clap::{Args, ValueEnum};
+use colored::*;
+use ethers::core::rand::{thread_rng, Rng};
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::signers::{coins_bip39::English, LocalWallet, MnemonicBuilder, Signer};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, BlockNumber, Bytes, TransactionRequest, H256, U256};
+use ethers::utils::{get_contract_address, hex};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tabled::{Table, Tabled};
+use tokio::time::{interval, sleep, Duration, Instant, MissedTickBehavior};
+
+use crate::config::Profile;
+use crate::output::{emit, OutputFormat};
+use crate::wallet::{self, WalletConfig};
+
+// Read when --mnemonic is not given, so the phrase stays out of `ps`
+const MNEMONIC_ENV: &str = "MORDOR_LOAD_MNEMONIC";
+
+// Fixed gas limits; estimating every transaction would halve the send rate
+const TRANSFER_GAS: u64 = 21_000;
+const CALL_GAS: u64 = 50_000;
+const DEPLOY_GAS: u64 = 120_000;
+
+// Deploys a contract whose only behaviour is storing the first calldata
+// word in slot 0, so calls cost a realistic SSTORE
+const STORE_CONTRACT: &str = "6007600c60003960076000f360003560005500";
+
+const PROGRESS_SECS: u64 = 5;
+// Distinct send errors kept for the report
+const MAX_ERRORS: usize = 10;
+
+#[derive(Args)]
+pub struct LoadArgs {
+    /// Mnemonic to derive the account pool from (or MORDOR_LOAD_MNEMONIC)
+    #[arg(long)]
+    mnemonic: Option<String>,
+
+    /// Accounts to derive from the mnemonic
+    #[arg(long, default_value = "10")]
+    accounts: u32,
+
+    /// Wallet keystore to add to the pool, repeatable
+    #[arg(long = "from")]
+    keystores: Vec<String>,
+
+    /// Keystore directory (defaults to ~/.mordor/keystore)
+    #[arg(long)]
+    keystore: Option<PathBuf>,
+
+    /// File holding the keystore password
+    #[arg(long)]
+    password_file: Option<PathBuf>,
+
+    /// Transactions per second
+    #[arg(long, default_value = "1")]
+    rate: f64,
+
+    /// Seconds to keep sending
+    #[arg(long, default_value = "60")]
+    duration: u64,
+
+    /// Workload mix as kind=weight pairs (transfer, call, deploy)
+    #[arg(long, default_value = "transfer=80,call=15,deploy=5", value_parser = parse_mix)]
+    mix: Mix,
+
+    /// How gas prices are drawn between the minimum and maximum
+    #[arg(long, value_enum, default_value = "uniform")]
+    gas_distribution: GasDistribution,
+
+    /// Lowest gas price in gwei (defaults to the node's gas price)
+    #[arg(long)]
+    gas_price_min: Option<f64>,
+
+    /// Highest gas price in gwei (defaults to twice the minimum)
+    #[arg(long)]
+    gas_price_max: Option<f64>,
+
+    /// Seconds to wait for pending transactions after sending stops
+    #[arg(long, default_value = "60")]
+    drain: u64,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum GasDistribution {
+    /// Always the minimum
+    Fixed,
+    /// Evenly spread between minimum and maximum
+    Uniform,
+    /// Bell curve centred between minimum and maximum
+    Normal,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Kind {
+    Transfer,
+    Call,
+    Deploy,
+}
+
+impl Kind {
+    fn name(&self) -> &'static str {
+        match self {
+            Kind::Transfer => "transfer",
+            Kind::Call => "call",
+            Kind::Deploy => "deploy",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Mix {
+    transfer: u32,
+    call: u32,
+    deploy: u32,
+}
+
+impl Mix {
+    fn pick(&self, rng: &mut impl Rng) -> Kind {
+        let roll = rng.gen_range(0..self.transfer + self.call + self.deploy);
+        if roll < self.transfer {
+            Kind::Transfer
+        } else if roll < self.transfer + self.call {
+            Kind::Call
+        } else {
+            Kind::Deploy
+        }
+    }
+}
+
+pub fn parse_mix(mix: &str) -> Result<Mix, String> {
+    let mut parsed = Mix { transfer: 0, call: 0, deploy: 0 };
+
+    for pair in mix.split(',') {
+        let (kind, weight) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("expected kind=weight, got '{}'", pair))?;
+        let weight = weight.trim().parse::<u32>().map_err(|e| format!("invalid weight '{}': {}", weight, e))?;
+        match kind.trim() {
+            "transfer" => parsed.transfer = weight,
+            "call" => parsed.call = weight,
+            "deploy" => parsed.deploy = weight,
+            other => return Err(format!("unknown transaction kind '{}'", other)),
+        }
+    }
+
+    if parsed.transfer + parsed.call + parsed.deploy == 0 {
+        return Err("mix needs at least one non-zero weight".to_string());
+    }
+
+    Ok(parsed)
+}
+
+struct GasPrices {
+    distribution: GasDistribution,
+    min: f64,
+    max: f64,
+}
+
+impl GasPrices {
+    fn sample(&self, rng: &mut impl Rng) -> U256 {
+        let wei = match self.distribution {
+            GasDistribution::Fixed => self.min,
+            GasDistribution::Uniform if self.max > self.min => rng.gen_range(self.min..=self.max),
+            GasDistribution::Uniform => self.min,
+            GasDistribution::Normal => {
+                // Box-Muller, with the range covering ±3 standard deviations
+                let (u1, u2): (f64, f64) = (rng.gen_range(f64::EPSILON..1.0), rng.gen());
+                let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                let mean = (self.min + self.max) / 2.0;
+                (mean + z * (self.max - self.min) / 6.0).clamp(self.min, self.max)
+            }
+        };
+
+        U256::from(wei as u128)
+    }
+}
+
+struct Account {
+    wallet: LocalWallet,
+    // Held across the send so one account's nonces go out in order
+    nonce: tokio::sync::Mutex<u64>,
+}
+
+struct Sent {
+    kind: Kind,
+    at: Instant,
+    // Address the contract will get, for deployments
+    contract: Option<Address>,
+}
+
+#[derive(Default)]
+struct KindStats {
+    sent: u64,
+    included: u64,
+    reverted: u64,
+}
+
+#[derive(Default)]
+struct Stats {
+    pending: HashMap<H256, Sent>,
+    by_kind: BTreeMap<Kind, KindStats>,
+    rejected: u64,
+    latencies: Vec<f64>,
+    contracts: Vec<Address>,
+    errors: Vec<String>,
+}
+
+struct LoadContext {
+    provider: Provider<Http>,
+    accounts: Vec<Account>,
+    mix: Mix,
+    gas: GasPrices,
+    stats: Mutex<Stats>,
+}
+
+#[derive(Serialize)]
+struct LoadReport {
+    duration_secs: f64,
+    target_rate: f64,
+    achieved_rate: f64,
+    accounts: usize,
+    sent: u64,
+    included: u64,
+    pending: u64,
+    rejected: u64,
+    reverted: u64,
+    failure_rate_percent: f64,
+    latency: Option<LatencyReport>,
+    by_kind: BTreeMap<String, KindReport>,
+    errors: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct LatencyReport {
+    mean_secs: f64,
+    p50_secs: f64,
+    p90_secs: f64,
+    p99_secs: f64,
+    max_secs: f64,
+}
+
+#[derive(Serialize, Tabled)]
+struct KindReport {
+    sent: u64,
+    included: u64,
+    reverted: u64,
+}
+
+#[derive(Tabled)]
+struct LoadRow {
+    metric: String,
+    value: String,
+}
+
+#[derive(Tabled)]
+struct KindRow {
+    kind: String,
+    #[tabled(inline)]
+    report: KindReport,
+}
+
+/// Sends a transaction mix at a fixed rate from a pool of accounts and
+/// follows new blocks to measure how long each one takes to be included.
+pub async fn run(profile: &Profile, args: LoadArgs, output: OutputFormat) -> Result<()> {
+    let rpc_url = profile.rpc_url.as_str();
+    if args.rate <= 0.0 {
+        bail!("--rate must be positive");
+    }
+
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+    let chain_id = provider.get_chainid().await?.as_u64();
+
+    let mut wallets = Vec::new();
+    let mnemonic = args.mnemonic.clone().or_else(|| std::env::var(MNEMONIC_ENV).ok());
+    if let Some(phrase) = mnemonic {
+        for index in 0..args.accounts {
+            wallets.push(MnemonicBuilder::<English>::default().phrase(phrase.as_str()).index(index)?.build()?);
+        }
+    }
+    if !args.keystores.is_empty() {
+        let config = WalletConfig {
+            rpc_url: rpc_url.to_string(),
+            gas_estimator: profile.gas_estimator.clone(),
+            keystore: args.keystore.clone().unwrap_or_else(wallet::default_keystore_dir),
+            password_file: args.password_file.clone(),
+        };
+        for name in &args.keystores {
+            wallets.push(wallet::load(&config, name)?);
+        }
+    }
+    if wallets.is_empty() {
+        bail!("No accounts: pass --mnemonic (or set {}) and/or --from <keystore>", MNEMONIC_ENV);
+    }
+
+    let mut accounts = Vec::new();
+    for wallet in wallets {
+        let address = wallet.address();
+        let balance = provider.get_balance(address, None).await?;
+        if balance.is_zero() {
+            if output.is_table() {
+                println!("{} {:?} has no balance, skipping", "⚠".bright_yellow(), address);
+            }
+            continue;
+        }
+        let nonce = provider.get_transaction_count(address, Some(BlockNumber::Pending.into())).await?.as_u64();
+        accounts.push(Account {
+            wallet: wallet.with_chain_id(chain_id),
+            nonce: tokio::sync::Mutex::new(nonce),
+        });
+    }
+    if accounts.is_empty() {
+        bail!("None of the accounts are funded, top them up from a Mordor faucet or the devnet's genesis allocation");
+    }
+
+    let node_price = provider.get_gas_price().await?.as_u128() as f64;
+    let min = args.gas_price_min.map_or(node_price, |gwei| gwei * 1e9);
+    let max = args.gas_price_max.map_or(min * 2.0, |gwei| gwei * 1e9).max(min);
+
+    if output.is_table() {
+        println!("{}", "Load generator (Ctrl+C to stop)".bright_blue().bold());
+        println!("{}", "=".repeat(70).bright_blue());
+        println!(
+            "Chain {} | {} accounts | {} tx/s for {}s | gas {:.2}-{:.2} gwei",
+            chain_id,
+            accounts.len(),
+            args.rate,
+            args.duration,
+            min / 1e9,
+            max / 1e9
+        );
+    }
+
+    let context = Arc::new(LoadContext {
+        provider: provider.clone(),
+        accounts,
+        mix: args.mix,
+        gas: GasPrices { distribution: args.gas_distribution, min, max },
+        stats: Mutex::new(Stats::default()),
+    });
+
+    let start_block = provider.get_block_number().await?.as_u64();
+    let tracker = tokio::spawn(track_inclusion(context.clone(), start_block));
+
+    let started = Instant::now();
+    let send_until = started + Duration::from_secs(args.duration);
+    let mut ticks = interval(Duration::from_secs_f64(1.0 / args.rate));
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_progress = started;
+    let mut index = 0usize;
+
+    while Instant::now() < send_until {
+        ticks.tick().await;
+        tokio::spawn(send_one(context.clone(), index));
+        index += 1;
+
+        if output.is_table() && last_progress.elapsed() >= Duration::from_secs(PROGRESS_SECS) {
+            print_progress(&context, started);
+            last_progress = Instant::now();
+        }
+    }
+    let send_secs = started.elapsed().as_secs_f64();
+
+    // Let in-flight sends land, then give the chain time to include them
+    sleep(Duration::from_secs(1)).await;
+    let drain_until = Instant::now() + Duration::from_secs(args.drain);
+    while Instant::now() < drain_until && !context.stats.lock().unwrap().pending.is_empty() {
+        if output.is_table() && last_progress.elapsed() >= Duration::from_secs(PROGRESS_SECS) {
+            print_progress(&context, started);
+            last_progress = Instant::now();
+        }
+        sleep(Duration::from_secs(1)).await;
+    }
+    tracker.abort();
+
+    let report = build_report(&context, send_secs, args.rate);
+    print_report(&report, output)
+}
+
+async fn send_one(context: Arc<LoadContext>, index: usize) {
+    let account = &context.accounts[index % context.accounts.len()];
+    let from = account.wallet.address();
+
+    // ThreadRng cannot be held across an await
+    let (mut kind, gas_price, word) = {
+        let mut rng = thread_rng();
+        (context.mix.pick(&mut rng), context.gas.sample(&mut rng), rng.gen::<[u8; 32]>())
+    };
+
+    let contract = {
+        let stats = context.stats.lock().unwrap();
+        match stats.contracts.len() {
+            0 => None,
+            n => Some(stats.contracts[index % n]),
+        }
+    };
+    // Nothing to call until the first deployment is mined
+    if kind == Kind::Call && contract.is_none() {
+        kind = Kind::Transfer;
+    }
+
+    let mut request = TransactionRequest::new().from(from).gas_price(gas_price).chain_id(account.wallet.chain_id());
+    request = match kind {
+        Kind::Transfer => {
+            let to = context.accounts[(index + 1) % context.accounts.len()].wallet.address();
+            request.to(to).value(U256::exp10(9)).gas(TRANSFER_GAS)
+        }
+        Kind::Call => request.to(contract.unwrap()).data(Bytes::from(word.to_vec())).gas(CALL_GAS),
+        Kind::Deploy => request.data(Bytes::from(hex::decode(STORE_CONTRACT).unwrap())).gas(DEPLOY_GAS),
+    };
+
+    let mut nonce = account.nonce.lock().await;
+    request = request.nonce(*nonce);
+
+    let typed: TypedTransaction = request.into();
+    let result = match account.wallet.sign_transaction(&typed).await {
+        Ok(signature) => context.provider.send_raw_transaction(typed.rlp_signed(&signature)).await.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+
+    match result {
+        Ok(pending) => {
+            let mut stats = context.stats.lock().unwrap();
+            stats.by_kind.entry(kind).or_default().sent += 1;
+            stats.pending.insert(pending.tx_hash(), Sent {
+                kind,
+                at: Instant::now(),
+                contract: (kind == Kind::Deploy).then(|| get_contract_address(from, *nonce)),
+            });
+            *nonce += 1;
+        }
+        Err(e) => {
+            {
+                let mut stats = context.stats.lock().unwrap();
+                stats.rejected += 1;
+                if stats.errors.len() < MAX_ERRORS && !stats.errors.contains(&e) {
+                    stats.errors.push(e);
+                }
+            }
+            // The node's view of the nonce wins after a rejection
+            if let Ok(count) = context.provider.get_transaction_count(from, Some(BlockNumber::Pending.into())).await {
+                *nonce = count.as_u64();
+            }
+        }
+    }
+}
+
+// Matches every new block's transactions against what we sent
+async fn track_inclusion(context: Arc<LoadContext>, mut last_block: u64) {
+    loop {
+        sleep(Duration::from_secs(1)).await;
+
+        let tip = match context.provider.get_block_number().await {
+            Ok(tip) => tip.as_u64(),
+            Err(_) => continue,
+        };
+
+        while last_block < tip {
+            let block = match context.provider.get_block(last_block + 1).await {
+                Ok(Some(block)) => block,
+                _ => break,
+            };
+            last_block += 1;
+
+            for hash in block.transactions {
+                let sent = match context.stats.lock().unwrap().pending.remove(&hash) {
+                    Some(sent) => sent,
+                    None => continue,
+                };
+                let latency = sent.at.elapsed().as_secs_f64();
+
+                let succeeded = match context.provider.get_transaction_receipt(hash).await {
+                    Ok(Some(receipt)) => receipt.status.is_none_or(|status| status.as_u64() == 1),
+                    _ => true,
+                };
+
+                let mut stats = context.stats.lock().unwrap();
+                stats.latencies.push(latency);
+                let kind_stats = stats.by_kind.entry(sent.kind).or_default();
+                kind_stats.included += 1;
+                if !succeeded {
+                    kind_stats.reverted += 1;
+                } else if let Some(contract) = sent.contract {
+                    stats.contracts.push(contract);
+                }
+            }
+        }
+    }
+}
+
+fn print_progress(context: &LoadContext, started: Instant) {
+    let stats = context.stats.lock().unwrap();
+    let sent: u64 = stats.by_kind.values().map(|k| k.sent).sum();
+    let included: u64 = stats.by_kind.values().map(|k| k.included).sum();
+
+    println!(
+        "[{:>5.0}s] sent {} | included {} | pending {} | rejected {} | p50 latency {}",
+        started.elapsed().as_secs_f64(),
+        sent.to_string().bright_cyan(),
+        included.to_string().bright_green(),
+        stats.pending.len().to_string().bright_yellow(),
+        stats.rejected.to_string().bright_red(),
+        percentile(&stats.latencies, 50.0).map_or("-".to_string(), |p| format!("{:.1}s", p))
+    );
+}
+
+fn percentile(values: &[f64], percent: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let rank = ((percent / 100.0) * (sorted.len() - 1) as f64).round() as usize;
+    Some(sorted[rank])
+}
+
+fn build_report(context: &LoadContext, send_secs: f64, target_rate: f64) -> LoadReport {
+    let stats = context.stats.lock().unwrap();
+    let sent: u64 = stats.by_kind.values().map(|k| k.sent).sum();
+    let included: u64 = stats.by_kind.values().map(|k| k.included).sum();
+    let reverted: u64 = stats.by_kind.values().map(|k| k.reverted).sum();
+    let pending = stats.pending.len() as u64;
+    let attempted = sent + stats.rejected;
+
+    let latency = (!stats.latencies.is_empty()).then(|| LatencyReport {
+        mean_secs: stats.latencies.iter().sum::<f64>() / stats.latencies.len() as f64,
+        p50_secs: percentile(&stats.latencies, 50.0).unwrap_or_default(),
+        p90_secs: percentile(&stats.latencies, 90.0).unwrap_or_default(),
+        p99_secs: percentile(&stats.latencies, 99.0).unwrap_or_default(),
+        max_secs: stats.latencies.iter().cloned().fold(0.0, f64::max),
+    });
+
+    LoadReport {
+        duration_secs: send_secs,
+        target_rate,
+        achieved_rate: sent as f64 / send_secs.max(f64::EPSILON),
+        accounts: context.accounts.len(),
+        sent,
+        included,
+        pending,
+        rejected: stats.rejected,
+        reverted,
+        // Rejected, reverted and never-included transactions all count as failures
+        failure_rate_percent: match attempted {
+            0 => 0.0,
+            n => (stats.rejected + reverted + pending) as f64 / n as f64 * 100.0,
+        },
+        latency,
+        by_kind: stats.by_kind.iter().map(|(kind, k)| {
+            (kind.name().to_string(), KindReport { sent: k.sent, included: k.included, reverted: k.reverted })
+        }).collect(),
+        errors: stats.errors.clone(),
+    }
+}
+
+fn print_report(report: &LoadReport, output: OutputFormat) -> Result<()> {
+    if !output.is_table() {
+        return emit(output, report);
+    }
+
+    println!("\n{}", "Load Test Summary".bright_blue().bold());
+    println!("{}", "=".repeat(50).bright_blue());
+
+    let mut rows = vec![
+        LoadRow { metric: "Duration".to_string(), value: format!("{:.1}s", report.duration_secs) },
+        LoadRow {
+            metric: "Rate".to_string(),
+            value: format!("{:.2} tx/s (target {})", report.achieved_rate, report.target_rate),
+        },
+        LoadRow { metric: "Accounts".to_string(), value: report.accounts.to_string() },
+        LoadRow { metric: "Sent".to_string(), value: report.sent.to_string() },
+        LoadRow { metric: "Included".to_string(), value: report.included.to_string() },
+        LoadRow { metric: "Still Pending".to_string(), value: report.pending.to_string() },
+        LoadRow { metric: "Rejected".to_string(), value: report.rejected.to_string() },
+        LoadRow { metric: "Reverted".to_string(), value: report.reverted.to_string() },
+        LoadRow { metric: "Failure Rate".to_string(), value: format!("{:.2}%", report.failure_rate_percent) },
+    ];
+    if let Some(latency) = &report.latency {
+        rows.push(LoadRow {
+            metric: "Inclusion Latency".to_string(),
+            value: format!(
+                "mean {:.1}s | p50 {:.1}s | p90 {:.1}s | p99 {:.1}s | max {:.1}s",
+                latency.mean_secs, latency.p50_secs, latency.p90_secs, latency.p99_secs, latency.max_secs
+            ),
+        });
+    }
+    println!("{}", Table::new(rows));
+
+    let kinds: Vec<KindRow> = report.by_kind.iter().map(|(kind, k)| KindRow {
+        kind: kind.clone(),
+        report: KindReport { sent: k.sent, included: k.included, reverted: k.reverted },
+    }).collect();
+    if !kinds.is_empty() {
+        println!("\n{}", Table::new(kinds));
+    }
+
+    if !report.errors.is_empty() {
+        println!("\n{}", "Send errors:".bright_red().bold());
+        for error in &report.errors {
+            println!("  {}", error);
+        }
+    }
+
+    Ok(())
+}
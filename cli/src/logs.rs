@@ -0,0 +1,181 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use ethers::abi::Event;
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::types::{Address, Filter, Log, ValueOrArray, H256};
+use ethers::utils::hex;
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::abi::{self, DecodedEvent};
+use crate::output::{emit_record, OutputFormat};
+
+// Blocks searched by default when --from is not given
+const DEFAULT_RANGE: u64 = 1_000;
+
+pub struct LogsQuery {
+    pub addresses: Vec<Address>,
+    pub topics: Vec<String>,
+    pub event: Option<String>,
+    pub abi: Option<PathBuf>,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub chunk: u64,
+}
+
+#[derive(Serialize)]
+struct LogRecord {
+    block_number: Option<u64>,
+    transaction_hash: Option<String>,
+    log_index: Option<u64>,
+    address: String,
+    topics: Vec<String>,
+    data: String,
+    decoded: Option<DecodedEvent>,
+}
+
+// One --topic per position: `_` matches anything, commas separate
+// alternatives, and 20-byte addresses are left-padded to a topic
+fn parse_topic(topic: &str) -> Result<Option<ValueOrArray<Option<H256>>>> {
+    if topic == "_" || topic.is_empty() {
+        return Ok(None);
+    }
+
+    let values = topic.split(',').map(|value| {
+        let bytes = hex::decode(value.trim().trim_start_matches("0x"))
+            .map_err(|e| anyhow!("Invalid topic '{}': {}", value, e))?;
+        match bytes.len() {
+            32 => Ok(Some(H256::from_slice(&bytes))),
+            20 => Ok(Some(H256::from(Address::from_slice(&bytes)))),
+            n => Err(anyhow!("Invalid topic '{}': expected 32 or 20 bytes, got {}", value, n)),
+        }
+    }).collect::<Result<Vec<_>>>()?;
+
+    Ok(Some(match values.len() {
+        1 => ValueOrArray::Value(values[0]),
+        _ => ValueOrArray::Array(values),
+    }))
+}
+
+/// Streams eth_getLogs results in block order, fetching the range in chunks
+/// and halving the chunk whenever the node refuses a request as too large.
+pub async fn run(rpc_url: &str, query: LogsQuery, output: OutputFormat) -> Result<()> {
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+
+    let mut events: Vec<Event> = Vec::new();
+    if let Some(path) = &query.abi {
+        events.extend(abi::load(path)?.events().cloned());
+    }
+    let event = query.event.as_deref().map(abi::parse_event).transpose()?;
+    if let Some(event) = &event {
+        events.push(event.clone());
+    }
+
+    let mut filter = Filter::new();
+    if !query.addresses.is_empty() {
+        filter = filter.address(query.addresses.clone());
+    }
+    if query.topics.len() > 4 {
+        return Err(anyhow!("At most 4 topics can be given, got {}", query.topics.len()));
+    }
+    for (position, topic) in query.topics.iter().enumerate() {
+        filter.topics[position] = parse_topic(topic)?;
+    }
+    // --event narrows topic0 to its signature unless a topic0 was given
+    if let (Some(event), None) = (&event, &filter.topics[0]) {
+        filter.topics[0] = Some(ValueOrArray::Value(Some(event.signature())));
+    }
+
+    let to = match query.to {
+        Some(to) => to,
+        None => provider.get_block_number().await?.as_u64(),
+    };
+    let from = query.from.unwrap_or_else(|| to.saturating_sub(DEFAULT_RANGE - 1));
+
+    if output.is_table() {
+        println!("{}", format!("Logs in blocks {}-{}", from, to).bright_blue().bold());
+        println!("{}", "=".repeat(70).bright_blue());
+    }
+
+    let mut chunk = query.chunk.max(1);
+    let mut start = from;
+    let mut total = 0usize;
+
+    while start <= to {
+        let end = start.saturating_add(chunk - 1).min(to);
+        let range = filter.clone().from_block(start).to_block(end);
+
+        let logs = match provider.get_logs(&range).await {
+            Ok(logs) => logs,
+            // Nodes cap results or block ranges with differing messages, so
+            // any failure is retried on a smaller range before giving up
+            Err(_) if chunk > 1 => {
+                chunk /= 2;
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        for log in &logs {
+            print_log(log, &events, output)?;
+        }
+        total += logs.len();
+        start = end + 1;
+    }
+
+    if output.is_table() {
+        println!("\n{} log(s)", total);
+    }
+
+    Ok(())
+}
+
+fn print_log(log: &Log, events: &[Event], output: OutputFormat) -> Result<()> {
+    // Logs that do not match the ABI are still shown, just undecoded
+    let decoded = abi::decode_log(events, log).unwrap_or(None);
+
+    let record = LogRecord {
+        block_number: log.block_number.map(|n| n.as_u64()),
+        transaction_hash: log.transaction_hash.map(|h| format!("{:?}", h)),
+        log_index: log.log_index.map(|i| i.as_u64()),
+        address: format!("{:?}", log.address),
+        topics: log.topics.iter().map(|t| format!("{:?}", t)).collect(),
+        data: format!("0x{}", hex::encode(&log.data)),
+        decoded,
+    };
+
+    if !output.is_table() {
+        return emit_record(output, &record);
+    }
+
+    let location = format!(
+        "#{} {}",
+        record.block_number.map_or("-".to_string(), |n| n.to_string()),
+        record.transaction_hash.as_deref().unwrap_or("-")
+    );
+
+    match &record.decoded {
+        Some(event) => {
+            let params = event.params.iter()
+                .map(|p| format!("{}={}", if p.name.is_empty() { &p.kind } else { &p.name }, p.value))
+                .collect::<Vec<_>>()
+                .join(", ");
+            println!(
+                "{} {} {}({})",
+                location.bright_black(),
+                record.address.bright_cyan(),
+                event.event.bright_yellow(),
+                params
+            );
+        }
+        None => {
+            println!("{} {}", location.bright_black(), record.address.bright_cyan());
+            for (i, topic) in record.topics.iter().enumerate() {
+                println!("    topic{} {}", i, topic);
+            }
+            println!("    data   {}", record.data);
+        }
+    }
+
+    Ok(())
+}
@@ -2,10 +2,46 @@ use anyhow::Result;
 use clap::{Parser, Subcommand};
 use colored::*;
 use ethers::providers::{Provider, Http, Middleware};
-use ethers::types::BlockNumber;
+use ethers::types::{Address, BlockNumber, SyncingStatus, H256, U256, U64};
 use serde::{Deserialize, Serialize};
 use tabled::{Table, Tabled};
-use chrono::{DateTime, Utc, NaiveDateTime};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+mod abi;
+mod account;
+mod call;
+mod config;
+mod confirmations;
+mod dashboard;
+mod export;
+mod exposition;
+mod gen;
+mod load;
+mod logs;
+mod monitor;
+mod output;
+mod query;
+mod registry;
+mod sync;
+mod tx;
+mod wallet;
+
+use account::AccountOptions;
+use call::CallOptions;
+use config::{ConfigCommands, Profile};
+use confirmations::ConfirmationOptions;
+use dashboard::DashboardConfig;
+use export::{BlockRow, ExportFormat, Exporter, ForkRow};
+use exposition::{Exposition, MetricType};
+use gen::GenCommands;
+use logs::LogsQuery;
+use output::{emit, OutputFormat};
+use query::{Prometheus, ReportCommands};
+use sync::SyncStatus;
+use wallet::{WalletCommands, WalletConfig};
 
 #[derive(Parser)]
 #[command(name = "mordor-cli")]
@@ -14,15 +50,78 @@ struct Cli {
     #[command(subcommand)]
     command: Commands,
 
-    #[arg(short, long, default_value = "http://localhost:8545")]
-    rpc_url: String,
+    /// RPC endpoint (defaults to the profile's)
+    #[arg(short, long, global = true)]
+    rpc_url: Option<String>,
+
+    /// Endpoint profile (local, public or one from the config file)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Config file with endpoint profiles (defaults to ~/.mordor/config.yaml)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Output format
+    #[arg(short, long, value_enum, global = true, default_value = "table")]
+    output: OutputFormat,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Get current blockchain status
-    Status,
+    Status {
+        /// Fork monitor endpoint, for the peer breakdown (defaults to the profile's)
+        #[arg(long)]
+        fork_monitor: Option<String>,
+    },
     
+    /// Show sync progress and the estimated time to sync
+    Sync {
+        /// Keep running with a progress bar until the node is synced
+        #[arg(short, long)]
+        watch: bool,
+
+        /// Poll interval in seconds
+        #[arg(short, long, default_value = "5")]
+        interval: u64,
+
+        /// Moving window for the sync rate, in seconds
+        #[arg(long, default_value = "60")]
+        window: u64,
+
+        /// Fork monitor endpoint, for the rate without --watch (defaults to the profile's)
+        #[arg(long)]
+        fork_monitor: Option<String>,
+    },
+
+    /// Estimate reorg risk by confirmation depth and the confirmations needed
+    Confirmations {
+        /// Acceptable chance of a confirmed block being reorged out (defaults to the monitor's)
+        #[arg(long)]
+        risk: Option<f64>,
+
+        /// Hashrate share assumed for an attacker (defaults to the monitor's)
+        #[arg(short, long)]
+        attacker_share: Option<f64>,
+
+        /// Confirmation depths to show
+        #[arg(short, long, default_value = "30")]
+        depths: u64,
+
+        /// Keep printing the recommendation as it changes
+        #[arg(short, long)]
+        watch: bool,
+
+        /// Refresh interval in seconds for --watch
+        #[arg(short, long, default_value = "15")]
+        interval: u64,
+
+        /// Fork monitor endpoint (defaults to the profile's)
+        #[arg(short, long)]
+        endpoint: Option<String>,
+    },
+
     /// Get detailed block information
     Block {
         /// Block number (or 'latest')
@@ -35,24 +134,312 @@ enum Commands {
         /// Refresh interval in seconds
         #[arg(short, long, default_value = "5")]
         interval: u64,
+
+        /// Follow newHeads over WebSocket instead of polling; without a URL
+        /// the profile's WebSocket endpoint is used
+        #[arg(long, num_args = 0..=1)]
+        ws: Option<Option<String>>,
     },
     
     /// Get Prometheus metrics
     Metrics {
-        /// Service to query (fork-monitor or gas-estimator)
+        /// Service to query (fork-monitor, gas-estimator, indexer or prometheus)
         #[arg(short, long, default_value = "fork-monitor")]
         service: String,
         
-        /// Prometheus endpoint
-        #[arg(short, long, default_value = "http://localhost:9090")]
-        endpoint: String,
+        /// Service endpoint (defaults to the profile's)
+        #[arg(short, long)]
+        endpoint: Option<String>,
     },
     
     /// Check all containers health
     Health,
     
     /// Get gas price recommendations
-    Gas,
+    Gas {
+        #[command(subcommand)]
+        command: Option<GasCommands>,
+    },
+
+    /// Full-screen live dashboard
+    Dashboard {
+        /// Refresh interval in seconds
+        #[arg(short, long, default_value = "5")]
+        interval: u64,
+
+        /// Fork monitor endpoint (defaults to the profile's)
+        #[arg(long)]
+        fork_monitor: Option<String>,
+
+        /// Gas estimator endpoint (defaults to the profile's)
+        #[arg(long)]
+        gas_estimator: Option<String>,
+    },
+
+    /// Export block and fork data for offline analysis
+    Export {
+        #[command(subcommand)]
+        command: ExportCommands,
+    },
+
+    /// Inspect a transaction and its receipt
+    Tx {
+        /// Transaction hash
+        hash: H256,
+
+        /// Contract ABI JSON used to decode the input data
+        #[arg(long)]
+        abi: Option<PathBuf>,
+
+        /// Poll until the transaction is confirmed, dropped or replaced
+        #[arg(short, long)]
+        wait: bool,
+
+        /// Confirmations to wait for
+        #[arg(short, long, default_value = "12")]
+        confirmations: u64,
+
+        /// Poll interval in seconds
+        #[arg(short, long, default_value = "5")]
+        interval: u64,
+    },
+
+    /// Inspect an account's balance, nonce, code and recent transactions
+    Account {
+        /// Account address
+        address: Address,
+
+        /// Storage slot to read (decimal or 0x hex), repeatable
+        #[arg(long = "slot", value_parser = account::parse_slot)]
+        slots: Vec<U256>,
+
+        /// Blocks to scan for transactions when the indexer is unavailable
+        #[arg(short, long, default_value = "100")]
+        blocks: u64,
+
+        /// Number of recent transactions to show
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+
+        /// Indexer endpoint (defaults to the profile's)
+        #[arg(long)]
+        indexer: Option<String>,
+
+        /// Keep running and print balance changes as new blocks arrive
+        #[arg(short, long)]
+        watch: bool,
+
+        /// Poll interval in seconds
+        #[arg(short, long, default_value = "5")]
+        interval: u64,
+    },
+
+    /// Manage test keystores and send transactions
+    Wallet {
+        /// Keystore directory (defaults to ~/.mordor/keystore)
+        #[arg(long, global = true)]
+        keystore: Option<PathBuf>,
+
+        /// File holding the keystore password
+        #[arg(long, global = true)]
+        password_file: Option<PathBuf>,
+
+        #[command(subcommand)]
+        command: WalletCommands,
+    },
+
+    /// Generate transaction load against Mordor or a local devnet
+    Load {
+        #[command(flatten)]
+        args: load::LoadArgs,
+    },
+
+    /// Call a contract function with eth_call and decode the result
+    Call {
+        /// Contract address
+        address: Address,
+
+        /// Function signature, e.g. "balanceOf(address)(uint256)", or a name with --abi
+        signature: String,
+
+        /// Function arguments
+        args: Vec<String>,
+
+        /// Contract ABI JSON used to look up the function by name
+        #[arg(long)]
+        abi: Option<PathBuf>,
+
+        /// Sender address for the call
+        #[arg(long)]
+        from: Option<Address>,
+
+        /// Block to call at (latest, pending or a number)
+        #[arg(short, long, default_value = "latest")]
+        block: String,
+    },
+
+    /// Stream contract logs, decoded with an ABI or event signature
+    Logs {
+        /// Contract address, repeatable
+        #[arg(short, long = "address")]
+        addresses: Vec<Address>,
+
+        /// Topic filter by position, repeatable (`_` for any, commas for alternatives)
+        #[arg(short, long = "topic")]
+        topics: Vec<String>,
+
+        /// Event signature, e.g. "Transfer(address indexed,address indexed,uint256)"
+        #[arg(short, long)]
+        event: Option<String>,
+
+        /// Contract ABI JSON used to decode events
+        #[arg(long)]
+        abi: Option<PathBuf>,
+
+        /// First block (defaults to the last 1000 blocks)
+        #[arg(long)]
+        from: Option<u64>,
+
+        /// Last block (defaults to latest)
+        #[arg(long)]
+        to: Option<u64>,
+
+        /// Blocks per eth_getLogs request, halved when the node refuses
+        #[arg(long, default_value = "2000")]
+        chunk: u64,
+    },
+
+    /// Show and manage endpoint profiles
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
+    /// Run a PromQL instant query against Prometheus
+    Query {
+        /// PromQL expression, e.g. 'etc_mordor_block_height'
+        query: String,
+
+        /// Evaluation time, RFC 3339 or unix seconds (defaults to now)
+        #[arg(long)]
+        time: Option<String>,
+
+        /// Prometheus endpoint (defaults to the profile's)
+        #[arg(short, long)]
+        endpoint: Option<String>,
+    },
+
+    /// Run a PromQL range query and plot each series
+    QueryRange {
+        /// PromQL expression, e.g. 'rate(etc_mordor_fork_total[5m])'
+        query: String,
+
+        /// How far back to query, e.g. 30m, 24h or 7d
+        #[arg(long, default_value = "1h")]
+        since: String,
+
+        /// Resolution (defaults to one point per column)
+        #[arg(long)]
+        step: Option<String>,
+
+        /// Columns per sparkline or chart
+        #[arg(long, default_value = "60")]
+        width: usize,
+
+        /// Draw a chart per series instead of a sparkline table
+        #[arg(long)]
+        chart: bool,
+
+        /// Prometheus endpoint (defaults to the profile's)
+        #[arg(short, long)]
+        endpoint: Option<String>,
+    },
+
+    /// Generate Grafana dashboards and Prometheus rules from the metric registry
+    Gen {
+        #[command(subcommand)]
+        command: GenCommands,
+    },
+
+    /// Canned reports built on the etc_mordor_* series in Prometheus
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+
+        /// Columns of the trend sparkline
+        #[arg(long, global = true, default_value = "60")]
+        width: usize,
+
+        /// Prometheus endpoint (defaults to the profile's)
+        #[arg(short, long, global = true)]
+        endpoint: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum GasCommands {
+    /// Show the top contract/method gas consumers
+    Top {
+        /// Rolling window (1h or 24h)
+        #[arg(short, long, default_value = "1h")]
+        window: String,
+
+        /// Number of consumers to show
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+
+        /// Gas estimator endpoint (defaults to the profile's)
+        #[arg(short, long)]
+        endpoint: Option<String>,
+    },
+
+    /// Show gas limit voting and utilization trends
+    Limit {
+        /// Fork monitor endpoint (defaults to the profile's)
+        #[arg(short, long)]
+        endpoint: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportCommands {
+    /// Export per-block rows fetched from the RPC
+    Blocks {
+        /// First block to export
+        #[arg(long)]
+        from: u64,
+
+        /// Last block to export (defaults to the latest block)
+        #[arg(long)]
+        to: Option<u64>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "csv")]
+        format: ExportFormat,
+
+        /// Output file (defaults to stdout)
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// Parallel block requests
+        #[arg(short, long, default_value = "8")]
+        concurrency: usize,
+    },
+
+    /// Export fork events recorded by the fork monitor
+    Forks {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "csv")]
+        format: ExportFormat,
+
+        /// Output file (defaults to stdout)
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// Fork monitor endpoint (defaults to the profile's)
+        #[arg(short, long)]
+        endpoint: Option<String>,
+    },
 }
 
 #[derive(Tabled)]
@@ -67,57 +454,352 @@ struct BlockInfo {
     value: String,
 }
 
-#[derive(Deserialize)]
-struct PrometheusResponse {
-    status: String,
-    data: PrometheusData,
+#[derive(Tabled)]
+struct GasConsumerRow {
+    rank: usize,
+    kind: String,
+    contract: String,
+    method: String,
+    gas_used: u64,
+    txs: u64,
+    share: String,
 }
 
-#[derive(Deserialize)]
-struct PrometheusData {
-    #[serde(rename = "resultType")]
-    result_type: String,
-    result: Vec<PrometheusResult>,
+#[derive(Deserialize, Serialize)]
+struct GasWindowReport {
+    window: String,
+    blocks: usize,
+    total_gas_used: u64,
+    gas_by_kind: std::collections::BTreeMap<String, u64>,
+    top_consumers: Vec<GasConsumer>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct GasConsumer {
+    kind: String,
+    contract: String,
+    selector: Option<String>,
+    gas_used: u64,
+    tx_count: u64,
+    share_percent: f64,
+}
+
+#[derive(Tabled)]
+struct UtilizationRow {
+    window: String,
+    blocks: usize,
+    avg_utilization: String,
+    full_blocks: usize,
+}
+
+#[derive(Tabled)]
+struct MinerVoteRow {
+    miner: String,
+    up: u64,
+    down: u64,
+    hold: u64,
+    last_gas_limit: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+struct GasLimitReport {
+    current_gas_limit: u64,
+    utilization: Vec<UtilizationWindow>,
+    miners: Vec<MinerVotes>,
+    trend_per_hour: f64,
+    drift: String,
+    predicted_gas_limit_1h: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+struct UtilizationWindow {
+    window: String,
+    blocks: usize,
+    avg_utilization_percent: f64,
+    full_blocks: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+struct MinerVotes {
+    miner: String,
+    up: u64,
+    down: u64,
+    hold: u64,
+    last_gas_limit: u64,
+}
+
+#[derive(Serialize)]
+struct StatusReport {
+    chain_id: u64,
+    current_block: u64,
+    syncing: bool,
+    gas_price_wei: u128,
+    // Only while syncing
+    sync: Option<SyncStatus>,
+    // None when the node has no net_ API and the fork monitor is unreachable
+    peer_count: Option<u64>,
+    // From the fork monitor's /peers endpoint, when it is reachable
+    peers: Option<PeerStatus>,
+    latest_block: Option<LatestBlock>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct PeerStatus {
+    peer_count: u64,
+    min_peers: u64,
+    below_threshold: bool,
+    admin_api: bool,
+    inbound: usize,
+    outbound: usize,
+    clients: Vec<PeerClient>,
+    fork_id: Option<String>,
+    fork_id_match: usize,
+    fork_id_mismatch: usize,
+    fork_id_unknown: usize,
+    fork_id_match_ratio: Option<f64>,
+    connects_1h: usize,
+    disconnects_1h: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+struct PeerClient {
+    client: String,
+    peers: usize,
+}
+
+#[derive(Serialize)]
+struct LatestBlock {
+    timestamp: u64,
+    time: String,
+    transactions: usize,
+    gas_used: u64,
+    gas_limit: u64,
+    gas_used_percent: f64,
+}
+
+#[derive(Serialize)]
+struct BlockDetails {
+    number: u64,
+    hash: String,
+    parent_hash: String,
+    timestamp: u64,
+    time: String,
+    miner: String,
+    difficulty: String,
+    gas_limit: u64,
+    gas_used: u64,
+    gas_used_percent: f64,
+    size: u64,
+    transactions: Vec<TransactionSummary>,
+}
+
+#[derive(Serialize)]
+struct TransactionSummary {
+    hash: String,
+    from: String,
+    to: Option<String>,
+    gas: u64,
+    gas_price_wei: u128,
+}
+
+#[derive(Serialize)]
+struct MetricSample {
+    name: String,
+    value: f64,
+    help: Option<String>,
+}
+
+#[derive(Serialize)]
+struct DistributionSample {
+    name: String,
+    #[serde(rename = "type")]
+    kind: MetricType,
+    help: Option<String>,
+    count: Option<f64>,
+    sum: Option<f64>,
+    mean: Option<f64>,
+    // Estimated from the buckets for histograms, as exported for summaries
+    quantiles: BTreeMap<String, f64>,
+}
+
+#[derive(Serialize)]
+struct MetricsReport {
+    samples: Vec<MetricSample>,
+    distributions: Vec<DistributionSample>,
+}
+
+#[derive(Serialize)]
+struct HealthCheck {
+    service: String,
+    url: String,
+    healthy: bool,
+    status: Option<u16>,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct GasRecommendation {
+    slow_wei: u64,
+    standard_wei: u64,
+    fast_wei: u64,
+    instant_wei: u64,
+    utilization_percent: f64,
 }
 
 #[derive(Deserialize)]
-struct PrometheusResult {
-    metric: serde_json::Value,
-    value: Vec<serde_json::Value>,
+struct ForkEvent {
+    detected_at: u64,
+    height: u64,
+    depth: u64,
+    competing_blocks: u64,
+    active_forks: u64,
+    hashes: Vec<String>,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    output::configure_colour();
+    let output = cli.output;
+
+    let config_path = cli.config.clone().unwrap_or_else(config::default_config_path);
+    let config_file = config::load(&config_path, cli.config.is_some())?;
+    let (profile_name, mut profile) = config::resolve(&config_file, cli.profile.as_deref())?;
+    if let Some(rpc_url) = cli.rpc_url {
+        profile.rpc_url = rpc_url;
+    }
+    let rpc_url = profile.rpc_url.clone();
 
     match cli.command {
-        Commands::Status => {
-            status_command(&cli.rpc_url).await?;
+        Commands::Status { fork_monitor } => {
+            let fork_monitor = config::endpoint(fork_monitor, &profile.fork_monitor, "fork_monitor")?;
+            status_command(&rpc_url, &fork_monitor, output).await?;
+        }
+        Commands::Sync { watch, interval, window, fork_monitor } => {
+            let fork_monitor = config::endpoint(fork_monitor, &profile.fork_monitor, "fork_monitor")?;
+            sync::run(&rpc_url, &fork_monitor, watch, interval, window, output).await?;
+        }
+        Commands::Confirmations { risk, attacker_share, depths, watch, interval, endpoint } => {
+            let endpoint = config::endpoint(endpoint, &profile.fork_monitor, "fork_monitor")?;
+            let options = ConfirmationOptions { risk, attacker_share, depths, watch, interval };
+            confirmations::run(&endpoint, options, output).await?;
         }
         Commands::Block { number } => {
-            block_command(&cli.rpc_url, &number).await?;
+            block_command(&rpc_url, &number, output).await?;
         }
-        Commands::Monitor { interval } => {
-            monitor_command(&cli.rpc_url, interval).await?;
+        Commands::Monitor { interval, ws } => {
+            let ws = match ws {
+                Some(Some(url)) => Some(url),
+                Some(None) => Some(profile.ws_url.clone().ok_or_else(|| {
+                    anyhow::anyhow!("Profile '{}' has no WebSocket endpoint, pass --ws <URL>", profile_name)
+                })?),
+                None => None,
+            };
+            monitor::run(&rpc_url, ws.as_deref(), interval, output).await?;
         }
         Commands::Metrics { service, endpoint } => {
-            metrics_command(&service, &endpoint).await?;
+            metrics_command(&service, endpoint.as_deref(), &profile, output).await?;
         }
         Commands::Health => {
-            health_command().await?;
+            health_command(&profile, output).await?;
+        }
+        Commands::Gas { command: None } => {
+            gas_command(&config::endpoint(None, &profile.gas_estimator, "gas_estimator")?, output).await?;
+        }
+        Commands::Gas { command: Some(GasCommands::Top { window, limit, endpoint }) } => {
+            let endpoint = config::endpoint(endpoint, &profile.gas_estimator, "gas_estimator")?;
+            gas_top_command(&endpoint, &window, limit, output).await?;
+        }
+        Commands::Gas { command: Some(GasCommands::Limit { endpoint }) } => {
+            let endpoint = config::endpoint(endpoint, &profile.fork_monitor, "fork_monitor")?;
+            gas_limit_command(&endpoint, output).await?;
+        }
+        Commands::Dashboard { interval, fork_monitor, gas_estimator } => {
+            if fork_monitor.is_some() {
+                profile.fork_monitor = fork_monitor;
+            }
+            if gas_estimator.is_some() {
+                profile.gas_estimator = gas_estimator;
+            }
+            dashboard::run(DashboardConfig { profile, interval }).await?;
         }
-        Commands::Gas => {
-            gas_command(&cli.rpc_url).await?;
+        Commands::Export { command: ExportCommands::Blocks { from, to, format, file, concurrency } } => {
+            export_blocks_command(&rpc_url, from, to, format, file.as_deref(), concurrency).await?;
+        }
+        Commands::Export { command: ExportCommands::Forks { format, file, endpoint } } => {
+            let endpoint = config::endpoint(endpoint, &profile.fork_monitor, "fork_monitor")?;
+            export_forks_command(&endpoint, format, file.as_deref()).await?;
+        }
+        Commands::Tx { hash, abi, wait, confirmations, interval } => {
+            tx::run(&rpc_url, hash, abi.as_deref(), wait, confirmations, interval, output).await?;
+        }
+        Commands::Account { address, slots, blocks, limit, indexer, watch, interval } => {
+            account::run(&rpc_url, AccountOptions {
+                address,
+                slots,
+                blocks,
+                limit,
+                indexer: indexer.or_else(|| profile.indexer.clone()),
+                watch,
+                interval,
+            }, output).await?;
+        }
+        Commands::Wallet { keystore, password_file, command } => {
+            wallet::run(WalletConfig {
+                rpc_url: rpc_url.clone(),
+                keystore: keystore.unwrap_or_else(wallet::default_keystore_dir),
+                password_file,
+                gas_estimator: profile.gas_estimator.clone(),
+            }, command, output).await?;
+        }
+        Commands::Load { args } => {
+            load::run(&profile, args, output).await?;
+        }
+        Commands::Call { address, signature, args, abi, from, block } => {
+            call::run(&rpc_url, CallOptions {
+                to: address,
+                signature,
+                args,
+                abi,
+                from,
+                block,
+            }, output).await?;
+        }
+        Commands::Logs { addresses, topics, event, abi, from, to, chunk } => {
+            logs::run(&rpc_url, LogsQuery {
+                addresses,
+                topics,
+                event,
+                abi,
+                from,
+                to,
+                chunk,
+            }, output).await?;
+        }
+        Commands::Config { command } => {
+            config::run(command, &config_path, &config_file, &profile_name, &profile, output)?;
+        }
+        Commands::Query { query, time, endpoint } => {
+            let endpoint = config::endpoint(endpoint, &profile.prometheus, "prometheus")?;
+            query::query(&endpoint, &query, time.as_deref(), output).await?;
+        }
+        Commands::QueryRange { query, since, step, width, chart, endpoint } => {
+            let endpoint = config::endpoint(endpoint, &profile.prometheus, "prometheus")?;
+            query::query_range(&endpoint, &query, &since, step.as_deref(), width, chart, output).await?;
+        }
+        Commands::Gen { command } => {
+            gen::run(command, &profile, output).await?;
+        }
+        Commands::Report { command, width, endpoint } => {
+            let endpoint = config::endpoint(endpoint, &profile.prometheus, "prometheus")?;
+            query::report(&endpoint, command, width, output).await?;
         }
     }
 
     Ok(())
 }
 
-async fn status_command(rpc_url: &str) -> Result<()> {
-    println!("{}", "Mordor Testnet Status".bright_blue().bold());
-    println!("{}", "=".repeat(50).bright_blue());
-    
+async fn status_command(rpc_url: &str, fork_monitor: &str, output: OutputFormat) -> Result<()> {
     let provider = Provider::<Http>::try_from(rpc_url)?;
     
     // Get basic info
@@ -125,37 +807,91 @@ async fn status_command(rpc_url: &str) -> Result<()> {
     let syncing = provider.syncing().await?;
     let gas_price = provider.get_gas_price().await?;
     let chain_id = provider.get_chainid().await?;
+    let (peer_count, peers, monitored_sync) = tokio::join!(
+        provider.request::<_, U64>("net_peerCount", ()),
+        fetch_peer_status(fork_monitor),
+        sync::fetch_sync_status(fork_monitor)
+    );
+    let peers = peers.ok();
+    // Not every node enables the net_ namespace; the fork monitor may still know
+    let peer_count = peer_count
+        .ok()
+        .map(|count| count.as_u64())
+        .or_else(|| peers.as_ref().map(|peers| peers.peer_count));
+
+    // The fork monitor measures the sync rate over time; a single
+    // eth_syncing call only gives the position
+    let sync = match (SyncStatus::from_rpc(&syncing, block_number.as_u64()), monitored_sync) {
+        (sync, _) if !sync.syncing => None,
+        (sync, Ok(monitored)) if monitored.syncing => Some(sync.with_rate(monitored.blocks_per_second)),
+        (sync, _) => Some(sync),
+    };
+    
+    // Get latest block
+    let latest_block = provider.get_block(block_number).await?.map(|block| {
+        let timestamp = block.timestamp.as_u64();
+        let datetime = DateTime::<Utc>::from_timestamp(timestamp as i64, 0).unwrap();
+        
+        LatestBlock {
+            timestamp,
+            time: datetime.to_rfc3339(),
+            transactions: block.transactions.len(),
+            gas_used: block.gas_used.as_u64(),
+            gas_limit: block.gas_limit.as_u64(),
+            gas_used_percent: (block.gas_used.as_u64() as f64 / block.gas_limit.as_u64() as f64) * 100.0,
+        }
+    });
+    
+    let report = StatusReport {
+        chain_id: chain_id.as_u64(),
+        current_block: block_number.as_u64(),
+        syncing: !matches!(syncing, SyncingStatus::IsFalse),
+        sync,
+        gas_price_wei: gas_price.as_u128(),
+        peer_count,
+        peers,
+        latest_block,
+    };
+    
+    if !output.is_table() {
+        return emit(output, &report);
+    }
+    
+    println!("{}", "Mordor Testnet Status".bright_blue().bold());
+    println!("{}", "=".repeat(50).bright_blue());
     
     let mut rows = vec![
         StatusRow {
             metric: "Chain ID".to_string(),
-            value: chain_id.to_string(),
+            value: report.chain_id.to_string(),
         },
         StatusRow {
             metric: "Current Block".to_string(),
-            value: block_number.to_string(),
+            value: report.current_block.to_string(),
         },
         StatusRow {
             metric: "Syncing".to_string(),
-            value: if syncing.is_syncing() { 
+            value: if report.syncing { 
                 "Yes".red().to_string() 
             } else { 
                 "No".green().to_string() 
             },
         },
-        StatusRow {
-            metric: "Gas Price".to_string(),
-            value: format!("{} wei ({:.2} Gwei)", gas_price, gas_price.as_u128() as f64 / 1e9),
-        },
     ];
+
+    if let Some(sync) = &report.sync {
+        rows.extend(sync.rows().into_iter().map(|(metric, value)| StatusRow { metric, value }));
+    }
+
+    rows.push(StatusRow {
+        metric: "Gas Price".to_string(),
+        value: format!("{} wei ({:.2} Gwei)", report.gas_price_wei, report.gas_price_wei as f64 / 1e9),
+    });
+
+    push_peer_rows(&mut rows, &report);
     
-    // Get latest block
-    if let Some(block) = provider.get_block(block_number).await? {
-        let timestamp = block.timestamp.as_u64();
-        let datetime = DateTime::<Utc>::from_utc(
-            NaiveDateTime::from_timestamp_opt(timestamp as i64, 0).unwrap(),
-            Utc
-        );
+    if let Some(block) = &report.latest_block {
+        let datetime = DateTime::<Utc>::from_timestamp(block.timestamp as i64, 0).unwrap();
         
         rows.push(StatusRow {
             metric: "Latest Block Time".to_string(),
@@ -164,7 +900,7 @@ async fn status_command(rpc_url: &str) -> Result<()> {
         
         rows.push(StatusRow {
             metric: "Transactions".to_string(),
-            value: block.transactions.len().to_string(),
+            value: block.transactions.to_string(),
         });
         
         rows.push(StatusRow {
@@ -173,18 +909,97 @@ async fn status_command(rpc_url: &str) -> Result<()> {
                 "{} / {} ({:.2}%)",
                 block.gas_used,
                 block.gas_limit,
-                (block.gas_used.as_u64() as f64 / block.gas_limit.as_u64() as f64) * 100.0
+                block.gas_used_percent
             ),
         });
     }
     
     let table = Table::new(rows).to_string();
     println!("\n{}", table);
+
+    if let Some(peers) = report.peers.as_ref().filter(|p| p.below_threshold) {
+        println!(
+            "\n{} peer count {} is below the minimum of {}",
+            "⚠".bright_red(),
+            peers.peer_count,
+            peers.min_peers
+        );
+    }
     
     Ok(())
 }
 
-async fn block_command(rpc_url: &str, number: &str) -> Result<()> {
+async fn fetch_peer_status(endpoint: &str) -> Result<PeerStatus> {
+    let url = format!("{}/peers", endpoint.trim_end_matches('/'));
+    let client = reqwest::Client::new();
+
+    Ok(client
+        .get(&url)
+        .timeout(std::time::Duration::from_secs(2))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+fn push_peer_rows(rows: &mut Vec<StatusRow>, report: &StatusReport) {
+    let peer_count = report.peer_count.map(|count| count.to_string()).unwrap_or_else(|| "-".to_string());
+    let peers = match &report.peers {
+        Some(peers) => peers,
+        None => {
+            rows.push(StatusRow {
+                metric: "Peers".to_string(),
+                value: peer_count,
+            });
+            return;
+        }
+    };
+
+    let count = if peers.admin_api {
+        format!("{} ({} in / {} out)", peer_count, peers.inbound, peers.outbound)
+    } else {
+        peer_count
+    };
+    rows.push(StatusRow {
+        metric: "Peers".to_string(),
+        value: if peers.below_threshold { count.red().to_string() } else { count.green().to_string() },
+    });
+
+    // The rest needs the node's admin API
+    if !peers.admin_api {
+        return;
+    }
+
+    if let Some(ratio) = peers.fork_id_match_ratio {
+        let value = format!(
+            "{}/{} peers ({:.0}%), {} unknown",
+            peers.fork_id_match,
+            peers.fork_id_match + peers.fork_id_mismatch,
+            ratio * 100.0,
+            peers.fork_id_unknown
+        );
+        rows.push(StatusRow {
+            metric: format!("Fork ID {}", peers.fork_id.as_deref().unwrap_or("")),
+            value: if peers.fork_id_mismatch > 0 { value.yellow().to_string() } else { value },
+        });
+    }
+
+    rows.push(StatusRow {
+        metric: "Peer Churn (1h)".to_string(),
+        value: format!("+{} / -{}", peers.connects_1h, peers.disconnects_1h),
+    });
+
+    rows.push(StatusRow {
+        metric: "Peer Clients".to_string(),
+        value: peers.clients.iter()
+            .map(|c| format!("{} ({})", c.client, c.peers))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    });
+}
+
+async fn block_command(rpc_url: &str, number: &str, output: OutputFormat) -> Result<()> {
     let provider = Provider::<Http>::try_from(rpc_url)?;
     
     let block_id = if number == "latest" {
@@ -196,23 +1011,45 @@ async fn block_command(rpc_url: &str, number: &str) -> Result<()> {
     let block = provider.get_block_with_txs(block_id).await?
         .ok_or_else(|| anyhow::anyhow!("Block not found"))?;
     
-    println!("{}", format!("Block #{}", block.number.unwrap()).bright_blue().bold());
-    println!("{}", "=".repeat(50).bright_blue());
-    
     let timestamp = block.timestamp.as_u64();
-    let datetime = DateTime::<Utc>::from_utc(
-        NaiveDateTime::from_timestamp_opt(timestamp as i64, 0).unwrap(),
-        Utc
-    );
+    let datetime = DateTime::<Utc>::from_timestamp(timestamp as i64, 0).unwrap();
+    
+    let details = BlockDetails {
+        number: block.number.unwrap().as_u64(),
+        hash: format!("{:?}", block.hash.unwrap()),
+        parent_hash: format!("{:?}", block.parent_hash),
+        timestamp,
+        time: datetime.to_rfc3339(),
+        miner: format!("{:?}", block.author.unwrap_or_default()),
+        difficulty: block.difficulty.to_string(),
+        gas_limit: block.gas_limit.as_u64(),
+        gas_used: block.gas_used.as_u64(),
+        gas_used_percent: (block.gas_used.as_u64() as f64 / block.gas_limit.as_u64() as f64) * 100.0,
+        size: block.size.unwrap_or_default().as_u64(),
+        transactions: block.transactions.iter().map(|tx| TransactionSummary {
+            hash: format!("{:?}", tx.hash),
+            from: format!("{:?}", tx.from),
+            to: tx.to.map(|to| format!("{:?}", to)),
+            gas: tx.gas.as_u64(),
+            gas_price_wei: tx.gas_price.unwrap_or_default().as_u128(),
+        }).collect(),
+    };
+    
+    if !output.is_table() {
+        return emit(output, &details);
+    }
+    
+    println!("{}", format!("Block #{}", details.number).bright_blue().bold());
+    println!("{}", "=".repeat(50).bright_blue());
     
     let rows = vec![
         BlockInfo {
             field: "Hash".to_string(),
-            value: format!("{:?}", block.hash.unwrap()),
+            value: details.hash.clone(),
         },
         BlockInfo {
             field: "Parent Hash".to_string(),
-            value: format!("{:?}", block.parent_hash),
+            value: details.parent_hash.clone(),
         },
         BlockInfo {
             field: "Timestamp".to_string(),
@@ -220,557 +1057,500 @@ async fn block_command(rpc_url: &str, number: &str) -> Result<()> {
         },
         BlockInfo {
             field: "Miner".to_string(),
-            value: format!("{:?}", block.author.unwrap_or_default()),
+            value: details.miner.clone(),
         },
         BlockInfo {
             field: "Difficulty".to_string(),
-            value: block.difficulty.to_string(),
+            value: details.difficulty.clone(),
         },
         BlockInfo {
             field: "Gas Limit".to_string(),
-            value: block.gas_limit.to_string(),
+            value: details.gas_limit.to_string(),
         },
         BlockInfo {
             field: "Gas Used".to_string(),
-            value: format!(
-                "{} ({:.2}%)",
-                block.gas_used,
-                (block.gas_used.as_u64() as f64 / block.gas_limit.as_u64() as f64) * 100.0
-            ),
+            value: format!("{} ({:.2}%)", details.gas_used, details.gas_used_percent),
         },
         BlockInfo {
             field: "Transactions".to_string(),
-            value: block.transactions.len().to_string(),
+            value: details.transactions.len().to_string(),
         },
         BlockInfo {
             field: "Size".to_string(),
-            value: format!("{} bytes", block.size.unwrap_or_default()),
+            value: format!("{} bytes", details.size),
         },
     ];
     
     let table = Table::new(rows).to_string();
     println!("\n{}", table);
     
-    if !block.transactions.is_empty() {
+    if !details.transactions.is_empty() {
         println!("\n{}", "Transactions:".bright_yellow().bold());
-        for (i, tx) in block.transactions.iter().take(10).enumerate() {
+        for (i, tx) in details.transactions.iter().take(10).enumerate() {
             println!(
                 "  {}. {} -> {} ({} gas @ {} wei)",
                 i + 1,
-                format!("{:?}", tx.from).bright_cyan(),
-                format!("{:?}", tx.to.unwrap_or_default()).bright_green(),
+                tx.from.bright_cyan(),
+                tx.to.clone().unwrap_or_else(|| format!("{:?}", ethers::types::Address::zero())).bright_green(),
                 tx.gas,
-                tx.gas_price.unwrap_or_default()
+                tx.gas_price_wei
             );
         }
-        if block.transactions.len() > 10 {
-            println!("  ... and {} more", block.transactions.len() - 10);
+        if details.transactions.len() > 10 {
+            println!("  ... and {} more", details.transactions.len() - 10);
         }
     }
     
     Ok(())
 }
 
-async fn monitor_command(rpc_url: &str, interval: u64) -> Result<()> {
-    use tokio::time::{sleep, Duration};
-    
-    println!("{}", "Monitoring Mordor Testnet (Ctrl+C to stop)".bright_blue().bold());
-    println!("{}", "=".repeat(70).bright_blue());
-    
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let mut last_block = 0u64;
-    
-    loop {
-        let block_number = provider.get_block_number().await?;
-        
-        if block_number.as_u64() != last_block {
-            if let Some(block) = provider.get_block(block_number).await? {
-                let timestamp = block.timestamp.as_u64();
-                let datetime = DateTime::<Utc>::from_utc(
-                    NaiveDateTime::from_timestamp_opt(timestamp as i64, 0).unwrap(),
-                    Utc
-                );
-                
-                let block_time = if last_block > 0 {
-                    format!("(+{:.1}s)", (timestamp as i64 - last_block as i64).abs())
-                } else {
-                    String::new()
-                };
-                
-                println!(
-                    "{} Block {} {} | Txs: {} | Gas: {}/{} ({:.1}%) | Difficulty: {}",
-                    datetime.format("%H:%M:%S").to_string().bright_black(),
-                    block_number.to_string().bright_yellow(),
-                    block_time.bright_black(),
-                    block.transactions.len().to_string().bright_cyan(),
-                    block.gas_used.to_string().bright_green(),
-                    block.gas_limit,
-                    (block.gas_used.as_u64() as f64 / block.gas_limit.as_u64() as f64) * 100.0,
-                    block.difficulty
-                );
-                
-                last_block = timestamp;
-            }
-        }
-        
-        sleep(Duration::from_secs(interval)).await;
-    }
-}
+async fn metrics_command(service: &str, endpoint: Option<&str>, profile: &Profile, output: OutputFormat) -> Result<()> {
+    let (default_endpoint, field) = match service {
+        "fork-monitor" => (&profile.fork_monitor, "fork_monitor"),
+        "gas-estimator" => (&profile.gas_estimator, "gas_estimator"),
+        "indexer" => (&profile.indexer, "indexer"),
+        "prometheus" => (&profile.prometheus, "prometheus"),
+        _ => return Err(anyhow::anyhow!("Unknown service. Use 'fork-monitor', 'gas-estimator', 'indexer' or 'prometheus'")),
+    };
+    let endpoint = config::endpoint(endpoint.map(str::to_string), default_endpoint, field)?;
+    let endpoint = endpoint.trim_end_matches('/');
 
-async fn metrics_command(service: &str, endpoint: &str) -> Result<()> {
-    let port = match service {
-        "fork-monitor" => 9090,
-        "gas-estimator" => 9091,
-        _ => return Err(anyhow::anyhow!("Unknown service. Use 'fork-monitor' or 'gas-estimator'")),
+    let exposition = match service {
+        "prometheus" => prometheus_exposition(endpoint).await?,
+        _ => scrape_exposition(endpoint).await?,
     };
+    let report = metrics_report(&exposition);
     
-    let url = format!("http://localhost:{}/metrics", port);
-    let client = reqwest::Client::new();
-    let response = client.get(&url).send().await?;
-    let text = response.text().await?;
+    if !output.is_table() {
+        return emit(output, &report);
+    }
     
     println!("{}", format!("Metrics from {}", service).bright_blue().bold());
     println!("{}", "=".repeat(70).bright_blue());
     
-    // Parse and display key metrics
-    for line in text.lines() {
-        if line.starts_with("etc_mordor_") && !line.starts_with("#") {
-            if let Some((metric, value)) = line.split_once(' ') {
-                let metric_name = metric
-                    .strip_prefix("etc_mordor_")
-                    .unwrap_or(metric)
-                    .replace('_', " ");
-                println!("  {}: {}", metric_name.bright_cyan(), value.bright_yellow());
-            }
+    for sample in &report.samples {
+        let (name, labels) = display_name(&sample.name);
+        println!("  {}{}: {}", name.bright_cyan(), labels.bright_black(), sample.value.to_string().bright_yellow());
+    }
+
+    for distribution in &report.distributions {
+        let (name, labels) = display_name(&distribution.name);
+        // Histogram quantiles are bucket estimates
+        let approx = if distribution.kind == MetricType::Histogram { "~" } else { "" };
+        let mut parts = vec![format!("count {}", distribution.count.map_or("-".to_string(), |c| c.to_string()))];
+        if let Some(mean) = distribution.mean {
+            parts.push(format!("mean {:.2}", mean));
+        }
+        for (quantile, value) in &distribution.quantiles {
+            parts.push(format!("{} {}{:.2}", quantile, approx, value));
         }
+        println!("  {}{}: {}", name.bright_cyan(), labels.bright_black(), parts.join(", ").bright_yellow());
     }
     
     Ok(())
 }
 
-async fn health_command() -> Result<()> {
-    println!("{}", "Checking Container Health".bright_blue().bold());
-    println!("{}", "=".repeat(50).bright_blue());
-    
-    let services = vec![
-        ("Mordor Node RPC", "http://localhost:8545", "eth_blockNumber"),
-        ("Fork Monitor", "http://localhost:9090/health", ""),
-        ("Gas Estimator", "http://localhost:9091/health", ""),
-        ("Prometheus", "http://localhost:9092/-/healthy", ""),
-        ("Grafana", "http://localhost:3000/api/health", ""),
-    ];
-    
-    let client = reqwest::Client::new();
-    
-    for (name, url, _method) in services {
-        print!("  {} ... ", name);
-        match client.get(url).timeout(std::time::Duration::from_secs(5)).send().await {
-            Ok(response) if response.status().is_success() => {
-                println!("{}", "✓ OK".bright_green().bold());
-            }
-            Ok(response) => {
-                println!("{}", format!("✗ ERROR ({})", response.status()).bright_red().bold());
+// Splits off the labels, which are shown as they are, and makes the name readable
+fn display_name(series: &str) -> (String, &str) {
+    let (name, labels) = series.split_at(series.find('{').unwrap_or(series.len()));
+    let name = name
+        .strip_prefix("etc_mordor_")
+        .unwrap_or(name)
+        .replace('_', " ");
+    (name, labels)
+}
+
+fn metrics_report(exposition: &Exposition) -> MetricsReport {
+    let mut report = MetricsReport { samples: Vec::new(), distributions: Vec::new() };
+
+    for family in exposition.families.iter().filter(|f| f.name.starts_with("etc_mordor_")) {
+        match family.kind {
+            MetricType::Histogram | MetricType::Summary => {
+                for distribution in family.distributions() {
+                    let quantiles = match family.kind {
+                        MetricType::Histogram => [0.5, 0.9, 0.99]
+                            .into_iter()
+                            .filter_map(|q| Some((quantile_name(q), distribution.histogram_quantile(q)?)))
+                            .collect(),
+                        _ => distribution.points.iter().map(|(q, v)| (quantile_name(*q), *v)).collect(),
+                    };
+                    report.distributions.push(DistributionSample {
+                        name: exposition::series_name(&family.name, &distribution.labels),
+                        kind: family.kind,
+                        help: family.help.clone(),
+                        count: distribution.count,
+                        sum: distribution.sum,
+                        mean: distribution.mean(),
+                        quantiles,
+                    });
+                }
             }
-            Err(e) => {
-                println!("{}", format!("✗ UNREACHABLE ({})", e).bright_red().bold());
+            _ => {
+                report.samples.extend(family.samples.iter().map(|sample| MetricSample {
+                    name: exposition::series_name(&sample.name, &sample.labels),
+                    value: sample.value,
+                    help: family.help.clone(),
+                }));
             }
         }
     }
-    
-    Ok(())
+
+    report
 }
 
-async fn gas_command(rpc_url: &str) -> Result<()> {
-    println!("{}", "Gas Price Recommendations".bright_blue().bold());
-    println!("{}", "=".repeat(50).bright_blue());
-    
-    // Query gas estimator metrics
+// 0.5 -> "p50", 0.999 -> "p99.9"
+fn quantile_name(q: f64) -> String {
+    format!("p{}", (q * 1000.0).round() / 10.0)
+}
+
+async fn scrape_exposition(endpoint: &str) -> Result<Exposition> {
+    let url = format!("{}/metrics", endpoint.trim_end_matches('/'));
     let client = reqwest::Client::new();
-    let response = client.get("http://localhost:9091/metrics").send().await?;
-    let text = response.text().await?;
-    
-    let mut metrics = std::collections::HashMap::new();
+    let text = client.get(&url).send().await?.error_for_status()?.text().await?;
+
+    Exposition::parse(&text).map_err(|e| anyhow::anyhow!("Could not parse metrics from {}: {}", url, e))
+}
+
+// Current values of every etc_mordor_* series Prometheus has scraped, from
+// all services at once
+async fn prometheus_exposition(endpoint: &str) -> Result<Exposition> {
+    let mut samples: Vec<exposition::Sample> = Prometheus::new(endpoint)
+        .query("{__name__=~\"etc_mordor_.+\"}", None)
+        .await?
+        .into_iter()
+        .filter_map(|sample| {
+            let mut labels = sample.metric;
+            let name = labels.remove("__name__")?;
+            Some(exposition::Sample { name, labels, value: sample.value })
+        })
+        .collect();
+    samples.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.labels.cmp(&b.labels)));
+
+    Ok(Exposition::from_samples(samples))
+}
+
+async fn health_command(profile: &Profile, output: OutputFormat) -> Result<()> {
+    if output.is_table() {
+        println!("{}", "Checking Container Health".bright_blue().bold());
+        println!("{}", "=".repeat(50).bright_blue());
+    }
     
-    for line in text.lines() {
-        if let Some((metric, value)) = line.split_once(' ') {
-            if let Ok(val) = value.parse::<f64>() {
-                metrics.insert(metric.to_string(), val);
+    let checks = check_services(profile).await;
+    
+    if output.is_table() {
+        for check in &checks {
+            print!("  {} ... ", check.service);
+            match (&check.error, check.healthy) {
+                (None, true) => println!("{}", "✓ OK".bright_green().bold()),
+                (None, false) => println!(
+                    "{}",
+                    format!("✗ ERROR ({})", check.status.unwrap_or_default()).bright_red().bold()
+                ),
+                (Some(e), _) => println!("{}", format!("✗ UNREACHABLE ({})", e).bright_red().bold()),
             }
         }
     }
     
-    let slow = metrics.get("etc_mordor_gas_price_min_wei").copied().unwrap_or(0.0);
-    let standard = metrics.get("etc_mordor_gas_price_median_wei").copied().unwrap_or(0.0);
-    let fast = metrics.get("etc_mordor_gas_price_p75_wei").copied().unwrap_or(0.0);
-    let instant = metrics.get("etc_mordor_gas_price_max_wei").copied().unwrap_or(0.0);
-    
-    let gwei = |wei: f64| wei / 1e9;
-    
-    println!("\n  {}: {} wei ({:.2} Gwei)", "Slow".bright_yellow(), slow as u64, gwei(slow));
-    println!("  {}: {} wei ({:.2} Gwei)", "Standard".bright_cyan(), standard as u64, gwei(standard));
-    println!("  {}: {} wei ({:.2} Gwei)", "Fast".bright_green(), fast as u64, gwei(fast));
-    println!("  {}: {} wei ({:.2} Gwei)", "Instant".bright_magenta(), instant as u64, gwei(instant));
-    
-    let utilization = metrics.get("etc_mordor_gas_utilization_percent").copied().unwrap_or(0.0);
-    println!("\n  Network Utilization: {:.2}%", utilization);
-    
-    Ok(())
+    emit(output, &checks)
 }
-use anyhow::Result;
-use clap::{Parser, Subcommand};
-use colored::*;
-use ethers::providers::{Provider, Http, Middleware};
-use ethers::types::BlockNumber;
-use serde::{Deserialize, Serialize};
-use tabled::{Table, Tabled};
-use chrono::{DateTime, Utc, NaiveDateTime};
 
-#[derive(Parser)]
-#[command(name = "mordor-cli")]
-#[command(about = "CLI tool for monitoring Mordor testnet", long_about = None)]
-struct Cli {
-    #[command(subcommand)]
-    command: Commands,
-
-    #[arg(short, long, default_value = "http://localhost:8545")]
-    rpc_url: String,
+async fn check_services(profile: &Profile) -> Vec<HealthCheck> {
+    // Services the profile has no endpoint for are left out
+    let endpoint = |base: &Option<String>, path: &str| {
+        base.as_ref().map(|base| format!("{}{}", base.trim_end_matches('/'), path))
+    };
+    let services: Vec<(&str, String, &str)> = [
+        ("Mordor Node RPC", Some(profile.rpc_url.clone()), "eth_blockNumber"),
+        ("Fork Monitor", endpoint(&profile.fork_monitor, "/health"), ""),
+        ("Gas Estimator", endpoint(&profile.gas_estimator, "/health"), ""),
+        ("Indexer", endpoint(&profile.indexer, "/health"), ""),
+        ("Prometheus", endpoint(&profile.prometheus, "/-/healthy"), ""),
+        ("Grafana", endpoint(&profile.grafana, "/api/health"), ""),
+    ]
+    .into_iter()
+    .filter_map(|(name, url, method)| Some((name, url?, method)))
+    .collect();
+    
+    let client = reqwest::Client::new();
+    let mut checks = Vec::new();
+    
+    for (name, url, method) in services {
+        // JSON-RPC endpoints are checked with a call, the services with a GET
+        let request = match method {
+            "" => client.get(&url),
+            method => client.post(&url).json(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": [],
+            })),
+        };
+        let check = match request.timeout(std::time::Duration::from_secs(5)).send().await {
+            Ok(response) => HealthCheck {
+                service: name.to_string(),
+                url: url.clone(),
+                healthy: response.status().is_success(),
+                status: Some(response.status().as_u16()),
+                error: None,
+            },
+            Err(e) => HealthCheck {
+                service: name.to_string(),
+                url: url.clone(),
+                healthy: false,
+                status: None,
+                error: Some(e.to_string()),
+            },
+        };
+        checks.push(check);
+    }
+    
+    checks
 }
 
-#[derive(Subcommand)]
-enum Commands {
-    /// Get current blockchain status
-    Status,
+async fn gas_command(endpoint: &str, output: OutputFormat) -> Result<()> {
+    let recommendation = fetch_gas_recommendation(endpoint).await?;
     
-    /// Get detailed block information
-    Block {
-        /// Block number (or 'latest')
-        #[arg(default_value = "latest")]
-        number: String,
-    },
+    if !output.is_table() {
+        return emit(output, &recommendation);
+    }
     
-    /// Monitor blockchain in real-time
-    Monitor {
-        /// Refresh interval in seconds
-        #[arg(short, long, default_value = "5")]
-        interval: u64,
-    },
+    println!("{}", "Gas Price Recommendations".bright_blue().bold());
+    println!("{}", "=".repeat(50).bright_blue());
     
-    /// Get Prometheus metrics
-    Metrics {
-        /// Service to query (fork-monitor or gas-estimator)
-        #[arg(short, long, default_value = "fork-monitor")]
-        service: String,
-        
-        /// Prometheus endpoint
-        #[arg(short, long, default_value = "http://localhost:9090")]
-        endpoint: String,
-    },
+    let gwei = |wei: u64| wei as f64 / 1e9;
     
-    /// Check all containers health
-    Health,
+    println!("\n  {}: {} wei ({:.2} Gwei)", "Slow".bright_yellow(), recommendation.slow_wei, gwei(recommendation.slow_wei));
+    println!("  {}: {} wei ({:.2} Gwei)", "Standard".bright_cyan(), recommendation.standard_wei, gwei(recommendation.standard_wei));
+    println!("  {}: {} wei ({:.2} Gwei)", "Fast".bright_green(), recommendation.fast_wei, gwei(recommendation.fast_wei));
+    println!("  {}: {} wei ({:.2} Gwei)", "Instant".bright_magenta(), recommendation.instant_wei, gwei(recommendation.instant_wei));
     
-    /// Get gas price recommendations
-    Gas,
+    println!("\n  Network Utilization: {:.2}%", recommendation.utilization_percent);
+    
+    Ok(())
 }
 
-#[derive(Tabled)]
-struct StatusRow {
-    metric: String,
-    value: String,
-}
+async fn fetch_gas_recommendation(endpoint: &str) -> Result<GasRecommendation> {
+    let exposition = scrape_exposition(endpoint).await?;
+    let metric = |name: &str| {
+        exposition
+            .value(name)
+            .map_err(|e| anyhow::anyhow!("{} at {}", e, endpoint))
+    };
 
-#[derive(Tabled)]
-struct BlockInfo {
-    field: String,
-    value: String,
+    let recommendation = GasRecommendation {
+        slow_wei: metric("etc_mordor_gas_price_min_wei")? as u64,
+        standard_wei: metric("etc_mordor_gas_price_median_wei")? as u64,
+        fast_wei: metric("etc_mordor_gas_price_p75_wei")? as u64,
+        instant_wei: metric("etc_mordor_gas_price_max_wei")? as u64,
+        utilization_percent: metric("etc_mordor_gas_utilization_percent")?,
+    };
+    
+    Ok(recommendation)
 }
 
-#[derive(Deserialize)]
-struct PrometheusResponse {
-    status: String,
-    data: PrometheusData,
-}
+async fn gas_top_command(endpoint: &str, window: &str, limit: usize, output: OutputFormat) -> Result<()> {
+    let url = format!("{}/gas/top", endpoint.trim_end_matches('/'));
+    let client = reqwest::Client::new();
+    let reports: Vec<GasWindowReport> = client.get(&url).send().await?.error_for_status()?.json().await?;
 
-#[derive(Deserialize)]
-struct PrometheusData {
-    #[serde(rename = "resultType")]
-    result_type: String,
-    result: Vec<PrometheusResult>,
-}
+    let mut report = reports
+        .into_iter()
+        .find(|report| report.window == window)
+        .ok_or_else(|| anyhow::anyhow!("No analytics for window '{}' yet", window))?;
+    report.top_consumers.truncate(limit);
 
-#[derive(Deserialize)]
-struct PrometheusResult {
-    metric: serde_json::Value,
-    value: Vec<serde_json::Value>,
-}
+    if !output.is_table() {
+        return emit(output, &report);
+    }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    println!("{}", format!("Top Gas Consumers ({})", report.window).bright_blue().bold());
+    println!("{}", "=".repeat(70).bright_blue());
 
-    match cli.command {
-        Commands::Status => {
-            status_command(&cli.rpc_url).await?;
-        }
-        Commands::Block { number } => {
-            block_command(&cli.rpc_url, &number).await?;
-        }
-        Commands::Monitor { interval } => {
-            monitor_command(&cli.rpc_url, interval).await?;
-        }
-        Commands::Metrics { service, endpoint } => {
-            metrics_command(&service, &endpoint).await?;
-        }
-        Commands::Health => {
-            health_command().await?;
-        }
-        Commands::Gas => {
-            gas_command(&cli.rpc_url).await?;
-        }
+    let kind_gas = |kind: &str| report.gas_by_kind.get(kind).copied().unwrap_or(0);
+    println!(
+        "\n  Blocks: {} | Total Gas: {} | Calls: {} | Creations: {} | Transfers: {}",
+        report.blocks,
+        report.total_gas_used,
+        kind_gas("call").to_string().bright_cyan(),
+        kind_gas("create").to_string().bright_green(),
+        kind_gas("transfer").to_string().bright_yellow()
+    );
+
+    if report.top_consumers.is_empty() {
+        println!("\n  No contract activity in this window");
+        return Ok(());
     }
 
+    let rows: Vec<GasConsumerRow> = report
+        .top_consumers
+        .into_iter()
+        .enumerate()
+        .map(|(i, consumer)| GasConsumerRow {
+            rank: i + 1,
+            kind: consumer.kind,
+            contract: consumer.contract,
+            method: consumer.selector.unwrap_or_else(|| "-".to_string()),
+            gas_used: consumer.gas_used,
+            txs: consumer.tx_count,
+            share: format!("{:.2}%", consumer.share_percent),
+        })
+        .collect();
+
+    let table = Table::new(rows).to_string();
+    println!("\n{}", table);
+
     Ok(())
 }
 
-async fn status_command(rpc_url: &str) -> Result<()> {
-    println!("{}", "Mordor Testnet Status".bright_blue().bold());
-    println!("{}", "=".repeat(50).bright_blue());
-    
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    
-    // Get basic info
-    let block_number = provider.get_block_number().await?;
-    let syncing = provider.syncing().await?;
-    let gas_price = provider.get_gas_price().await?;
-block_command(rpc_url: &str, number: &str) -> Result<()> {
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    
-    let block_id = if number == "latest" {
-        BlockNumber::Latest
-    } else {
-        BlockNumber::Number(number.parse::<u64>()?.into())
+async fn gas_limit_command(endpoint: &str, output: OutputFormat) -> Result<()> {
+    let url = format!("{}/gas-limit", endpoint.trim_end_matches('/'));
+    let client = reqwest::Client::new();
+    let report: GasLimitReport = client.get(&url).send().await?.error_for_status()?.json().await?;
+
+    if !output.is_table() {
+        return emit(output, &report);
+    }
+
+    println!("{}", "Gas Limit Report".bright_blue().bold());
+    println!("{}", "=".repeat(70).bright_blue());
+
+    let drift = match report.drift.as_str() {
+        "up" => "▲ Up".bright_green(),
+        "down" => "▼ Down".bright_red(),
+        _ => "● Stable".bright_yellow(),
     };
-    
-    let block = provider.get_block_with_txs(block_id).await?
-        .ok_or_else(|| anyhow::anyhow!("Block not found"))?;
-    
-    println!("{}", format!("Block #{}", block.number.unwrap()).bright_blue().bold());
-    println!("{}", "=".repeat(50).bright_blue());
-    
-    let timestamp = block.timestamp.as_u64();
-    let datetime = DateTime::<Utc>::from_utc(
-        NaiveDateTime::from_timestamp_opt(timestamp as i64, 0).unwrap(),
-        Utc
-    );
-    
-    let rows = vec![
-        BlockInfo {
-            field: "Hash".to_string(),
-            value: format!("{:?}", block.hash.unwrap()),
-        },
-        BlockInfo {
-            field: "Parent Hash".to_string(),
-            value: format!("{:?}", block.parent_hash),
-        },
-        BlockInfo {
-            field: "Timestamp".to_string(),
-            value: datetime.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
-        },
-        BlockInfo {
-            field: "Miner".to_string(),
-            value: format!("{:?}", block.author.unwrap_or_default()),
-        },
-        BlockInfo {
-            field: "Difficulty".to_string(),
-            value: block.difficulty.to_string(),
-        },
-        BlockInfo {
-            field: "Gas Limit".to_string(),
-            value: block.gas_limit.to_string(),
-        },
-        BlockInfo {
-            field: "Gas Used".to_string(),
-            value: format!(
-                "{} ({:.2}%)",
-                block.gas_used,
-                (block.gas_used.as_u64() as f64 / block.gas_limit.as_u64() as f64) * 100.0
-            ),
-        },
-        BlockInfo {
-            field: "Transactions".to_string(),
-            value: block.transactions.len().to_string(),
-        },
-        BlockInfo {
-            field: "Size".to_string(),
-            value: format!("{} bytes", block.size.unwrap_or_default()),
-        },
-    ];
-    
-    let table = Table::new(rows).to_string();
-    println!("\n{}", table);
-    
-    if !block.transactions.is_empty() {
-        println!("\n{}", "Transactions:".bright_yellow().bold());
-        for (i, tx) in block.transactions.iter().take(10).enumerate() {
-            println!(
-                "  {}. {} -> {} ({} gas @ {} wei)",
-                i + 1,
-                format!("{:?}", tx.from).bright_cyan(),
-                format!("{:?}", tx.to.unwrap_or_default()).bright_green(),
-                tx.gas,
-                tx.gas_price.unwrap_or_default()
-            );
-        }
-        if block.transactions.len() > 10 {
-            println!("  ... and {} more", block.transactions.len() - 10);
-        }
+
+    println!("\n  Current Gas Limit: {}", report.current_gas_limit.to_string().bright_cyan());
+    println!("  Trend: {:+.0} gas/hour ({})", report.trend_per_hour, drift);
+    println!("  Predicted in 1h: {}", report.predicted_gas_limit_1h);
+
+    let rows: Vec<UtilizationRow> = report
+        .utilization
+        .into_iter()
+        .map(|window| UtilizationRow {
+            window: window.window,
+            blocks: window.blocks,
+            avg_utilization: format!("{:.2}%", window.avg_utilization_percent),
+            full_blocks: window.full_blocks,
+        })
+        .collect();
+
+    println!("\n{}", "Utilization:".bright_yellow().bold());
+    println!("{}", Table::new(rows));
+
+    if !report.miners.is_empty() {
+        let rows: Vec<MinerVoteRow> = report
+            .miners
+            .into_iter()
+            .map(|votes| MinerVoteRow {
+                miner: votes.miner,
+                up: votes.up,
+                down: votes.down,
+                hold: votes.hold,
+                last_gas_limit: votes.last_gas_limit,
+            })
+            .collect();
+
+        println!("\n{}", "Gas Limit Votes by Miner (24h):".bright_yellow().bold());
+        println!("{}", Table::new(rows));
     }
-    
+
     Ok(())
 }
 
-async fn monitor_command(rpc_url: &str, interval: u64) -> Result<()> {
-    use tokio::time::{sleep, Duration};
-    
-    println!("{}", "Monitoring Mordor Testnet (Ctrl+C to stop)".bright_blue().bold());
-    println!("{}", "=".repeat(70).bright_blue());
-    
+async fn export_blocks_command(
+    rpc_url: &str,
+    from: u64,
+    to: Option<u64>,
+    format: ExportFormat,
+    output: Option<&std::path::Path>,
+    concurrency: usize,
+) -> Result<()> {
     let provider = Provider::<Http>::try_from(rpc_url)?;
-    let mut last_block = 0u64;
-    
-    loop {
-        let block_number = provider.get_block_number().await?;
-        
-        if block_number.as_u64() != last_block {
-            if let Some(block) = provider.get_block(block_number).await? {
-                let timestamp = block.timestamp.as_u64();
-                let datetime = DateTime::<Utc>::from_utc(
-                    NaiveDateTime::from_timestamp_opt(timestamp as i64, 0).unwrap(),
-                    Utc
-                );
-                
-                let block_time = if last_block > 0 {
-                    format!("(+{:.1}s)", (timestamp as i64 - last_block as i64).abs())
-                } else {
-                    String::new()
-                };
-                
-                println!(
-                    "{} Block {} {} | Txs: {} | Gas: {}/{} ({:.1}%) | Difficulty: {}",
-                    datetime.format("%H:%M:%S").to_string().bright_black(),
-                    block_number.to_string().bright_yellow(),
-                    block_time.bright_black(),
-                    block.transactions.len().to_string().bright_cyan(),
-                    block.gas_used.to_string().bright_green(),
-                    block.gas_limit,
-                    (block.gas_used.as_u64() as f64 / block.gas_limit.as_u64() as f64) * 100.0,
-                    block.difficulty
-                );
-                
-                last_block = timestamp;
-            }
-        }
-        
-        sleep(Duration::from_secs(interval)).await;
+
+    let to = match to {
+        Some(to) => to,
+        None => provider.get_block_number().await?.as_u64(),
+    };
+    if from > to {
+        return Err(anyhow::anyhow!("--from {} is after --to {}", from, to));
     }
-}
 
-async fn metrics_command(service: &str, endpoint: &str) -> Result<()> {
-    let port = match service {
-        "fork-monitor" => 9090,
-        "gas-estimator" => 9091,
-        _ => return Err(anyhow::anyhow!("Unknown service. Use 'fork-monitor' or 'gas-estimator'")),
+    // The first block's time needs its parent
+    let mut parent_timestamp = if from > 0 {
+        provider.get_block(from - 1).await?.map(|block| block.timestamp.as_u64())
+    } else {
+        None
     };
-    
-    let url = format!("http://localhost:{}/metrics", port);
-    let client = reqwest::Client::new();
-    let response = client.get(&url).send().await?;
-    let text = response.text().await?;
-    
-    println!("{}", format!("Metrics from {}", service).bright_blue().bold());
-    println!("{}", "=".repeat(70).bright_blue());
-    
-    // Parse and display key metrics
-    for line in text.lines() {
-        if line.starts_with("etc_mordor_") && !line.starts_with("#") {
-            if let Some((metric, value)) = line.split_once(' ') {
-                let metric_name = metric
-                    .strip_prefix("etc_mordor_")
-                    .unwrap_or(metric)
-                    .replace('_', " ");
-                println!("  {}: {}", metric_name.bright_cyan(), value.bright_yellow());
-            }
-        }
+
+    let mut exporter = Exporter::new(format, output)?;
+
+    // Requests run in parallel but rows come out in block order
+    let mut blocks = stream::iter(from..=to)
+        .map(|number| {
+            let provider = provider.clone();
+            async move { (number, provider.get_block(number).await) }
+        })
+        .buffered(concurrency.max(1));
+
+    while let Some((number, block)) = blocks.next().await {
+        let block = block?.ok_or_else(|| anyhow::anyhow!("Block {} not found", number))?;
+        let timestamp = block.timestamp.as_u64();
+
+        exporter.write(BlockRow {
+            height: number,
+            hash: format!("{:?}", block.hash.unwrap_or_default()),
+            parent_hash: format!("{:?}", block.parent_hash),
+            timestamp,
+            miner: format!("{:?}", block.author.unwrap_or_default()),
+            difficulty: block.difficulty.min(u64::MAX.into()).as_u64(),
+            gas_used: block.gas_used.as_u64(),
+            gas_limit: block.gas_limit.as_u64(),
+            tx_count: block.transactions.len() as u64,
+            uncle_count: block.uncles.len() as u64,
+            block_time: parent_timestamp.map(|parent| timestamp as i64 - parent as i64),
+        })?;
+
+        parent_timestamp = Some(timestamp);
     }
-    
+
+    let rows = exporter.finish()?;
+    eprintln!("{}", format!("Exported {} blocks ({}-{})", rows, from, to).bright_green());
+
     Ok(())
 }
 
-async fn health_command() -> Result<()> {
-    println!("{}", "Checking Container Health".bright_blue().bold());
-    println!("{}", "=".repeat(50).bright_blue());
-    
-    let services = vec![
-        ("Mordor Node RPC", "http://localhost:8545", "eth_blockNumber"),
-        ("Fork Monitor", "http://localhost:9090/health", ""),
-        ("Gas Estimator", "http://localhost:9091/health", ""),
-        ("Prometheus", "http://localhost:9092/-/healthy", ""),
-        ("Grafana", "http://localhost:3000/api/health", ""),
-    ];
-    
+async fn export_forks_command(endpoint: &str, format: ExportFormat, output: Option<&std::path::Path>) -> Result<()> {
+    let url = format!("{}/forks", endpoint.trim_end_matches('/'));
     let client = reqwest::Client::new();
-    
-    for (name, url, _method) in services {
-        print!("  {} ... ", name);
-        match client.get(url).timeout(std::time::Duration::from_secs(5)).send().await {
-            Ok(response) if response.status().is_success() => {
-                println!("{}", "✓ OK".bright_green().bold());
-            }
-            Ok(response) => {
-                println!("{}", format!("✗ ERROR ({})", response.status()).bright_red().bold());
-            }
-            Err(e) => {
-                println!("{}", format!("✗ UNREACHABLE ({})", e).bright_red().bold());
-            }
-        }
+    let events: Vec<ForkEvent> = client.get(&url).send().await?.error_for_status()?.json().await?;
+
+    let mut exporter = Exporter::new(format, output)?;
+    for event in events {
+        exporter.write(ForkRow {
+            detected_at: event.detected_at,
+            height: event.height,
+            depth: event.depth,
+            competing_blocks: event.competing_blocks,
+            active_forks: event.active_forks,
+            hashes: event.hashes.join(";"),
+        })?;
     }
-    
+
+    let rows = exporter.finish()?;
+    eprintln!("{}", format!("Exported {} fork events", rows).bright_green());
+
     Ok(())
 }
 
-async fn gas_command(rpc_url: &str) -> Result<()> {
-    println!("{}", "Gas Price Recommendations".bright_blue().bold());
-    println!("{}", "=".repeat(50).bright_blue());
-    
-    // Query gas estimator metrics
-    let client = reqwest::Client::new();
-    let response = client.get("http://localhost:9091/metrics").send().await?;
-    let text = response.text().await?;
-    
-    let mut metrics = std::collections::HashMap::new();
-    
-    for line in text.lines() {
-        if let Some((metric, value)) = line.split_once(' ') {
-            if let Ok(val) = value.parse::<f64>() {
-                metrics.insert(metric.to_string(), val);
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn arguments_are_consistent() {
+        // Catches clashing flags, e.g. a global short option reused by a subcommand
+        Cli::command().debug_assert();
     }
-    
-    let slow = metrics.get("etc_mordor_gas_price_min_wei").copied().unwrap_or(0.0);
-    let standard = metrics.get("etc_mordor_gas_price_median_wei").copied().unwrap_or(0.0);
-    let fast = metrics.get("etc_mordor_gas_price_p75_wei").copied().unwrap_or(0.0);
-    let instant = metrics.get("etc_mordor_gas_price_max_wei").copied().unwrap_or(0.0);
-    
-    let gwei = |wei: f64| wei / 1e9;
-    
-    println!("\n  {}: {} wei ({:.2} Gwei)", "Slow".bright_yellow(), slow as u64, gwei(slow));
-    println!("  {}: {} wei ({:.2} Gwei)", "Standard".bright_cyan(), standard as u64, gwei(standard));
-    println!("  {}: {} wei ({:.2} Gwei)", "Fast".bright_green(), fast as u64, gwei(fast));
-    println!("  {}: {} wei ({:.2} Gwei)", "Instant".bright_magenta(), instant as u64, gwei(instant));
-    
-    let utilization = metrics.get("etc_mordor_gas_utilization_percent").copied().unwrap_or(0.0);
-    println!("\n  Network Utilization: {:.2}%", utilization);
-    
-    Ok(())
 }
@@ -0,0 +1,226 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use colored::*;
+use ethers::providers::{Http, Middleware, Provider, StreamExt, Ws};
+use ethers::types::{Block, H256};
+use serde::Serialize;
+use std::collections::VecDeque;
+use tokio::time::{sleep, Duration};
+
+use crate::output::{emit_record, OutputFormat};
+
+// Heads remembered for walking back to the common ancestor of a reorg
+const HISTORY_BLOCKS: usize = 128;
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+enum MonitorRecord {
+    Block(BlockEvent),
+    Reorg(ReorgEvent),
+}
+
+#[derive(Serialize)]
+struct BlockEvent {
+    number: u64,
+    hash: String,
+    timestamp: u64,
+    time: String,
+    block_time: Option<i64>,
+    transactions: usize,
+    gas_used: u64,
+    gas_limit: u64,
+    gas_used_percent: f64,
+    difficulty: String,
+}
+
+#[derive(Serialize)]
+struct ReorgEvent {
+    // First height whose block was replaced
+    height: u64,
+    depth: u64,
+    old_head: String,
+    old_head_number: u64,
+}
+
+struct Head {
+    number: u64,
+    hash: H256,
+    timestamp: u64,
+}
+
+/// Follows the chain head and prints every block once, in order. Gaps
+/// between polls are filled in, and a head that no longer links to what was
+/// printed is reported as a reorg before the new branch is printed.
+struct HeadFollower {
+    provider: Provider<Http>,
+    output: OutputFormat,
+    history: VecDeque<Head>,
+}
+
+impl HeadFollower {
+    async fn advance(&mut self, tip: u64) -> Result<()> {
+        let mut number = match self.history.back() {
+            Some(last) => last.number + 1,
+            None => tip,
+        };
+
+        // No new height: our head must still be the canonical block
+        if number > tip {
+            let last = self.history.back().unwrap();
+            let canonical = self.provider.get_block(last.number).await?.and_then(|block| block.hash);
+            if canonical == Some(last.hash) {
+                return Ok(());
+            }
+            number = self.rollback().await? + 1;
+        }
+
+        while number <= tip {
+            let block = match self.provider.get_block(number).await? {
+                Some(block) => block,
+                None => break,
+            };
+
+            if self.history.back().is_some_and(|last| block.parent_hash != last.hash) {
+                number = self.rollback().await? + 1;
+                continue;
+            }
+
+            self.print_block(&block)?;
+            self.history.push_back(Head {
+                number,
+                hash: block.hash.unwrap_or_default(),
+                timestamp: block.timestamp.as_u64(),
+            });
+            if self.history.len() > HISTORY_BLOCKS {
+                self.history.pop_front();
+            }
+
+            number += 1;
+        }
+
+        Ok(())
+    }
+
+    // Drops the printed heads that are no longer canonical and reports the
+    // reorg. Returns the height of the common ancestor.
+    async fn rollback(&mut self) -> Result<u64> {
+        let (old_head_number, old_head) = match self.history.back() {
+            Some(last) => (last.number, last.hash),
+            None => return Err(anyhow!("Reorg with no known head")),
+        };
+
+        let mut depth = 0;
+        while let Some(last) = self.history.back() {
+            let canonical = self.provider.get_block(last.number).await?.and_then(|block| block.hash);
+            if canonical == Some(last.hash) {
+                break;
+            }
+            self.history.pop_back();
+            depth += 1;
+        }
+
+        let ancestor = match self.history.back() {
+            Some(last) => last.number,
+            // Deeper than the history; restart from the first dropped height
+            None => old_head_number - depth,
+        };
+
+        let reorg = ReorgEvent {
+            height: ancestor + 1,
+            depth,
+            old_head: format!("{:?}", old_head),
+            old_head_number,
+        };
+
+        if self.output.is_table() {
+            println!(
+                "{}",
+                format!(
+                    "⚠ REORG at height {}: {} block(s) replaced, old head #{} {}",
+                    reorg.height, reorg.depth, reorg.old_head_number, reorg.old_head
+                )
+                .bright_red()
+                .bold()
+            );
+        } else {
+            emit_record(self.output, &MonitorRecord::Reorg(reorg))?;
+        }
+
+        Ok(ancestor)
+    }
+
+    fn print_block(&self, block: &Block<H256>) -> Result<()> {
+        let timestamp = block.timestamp.as_u64();
+        let datetime = DateTime::<Utc>::from_timestamp(timestamp as i64, 0).unwrap();
+
+        let event = BlockEvent {
+            number: block.number.unwrap_or_default().as_u64(),
+            hash: format!("{:?}", block.hash.unwrap_or_default()),
+            timestamp,
+            time: datetime.to_rfc3339(),
+            block_time: self.history.back().map(|parent| timestamp as i64 - parent.timestamp as i64),
+            transactions: block.transactions.len(),
+            gas_used: block.gas_used.as_u64(),
+            gas_limit: block.gas_limit.as_u64(),
+            gas_used_percent: (block.gas_used.as_u64() as f64 / block.gas_limit.as_u64() as f64) * 100.0,
+            difficulty: block.difficulty.to_string(),
+        };
+
+        if !self.output.is_table() {
+            return emit_record(self.output, &MonitorRecord::Block(event));
+        }
+
+        let block_time = match event.block_time {
+            Some(seconds) => format!("(+{}s)", seconds),
+            None => String::new(),
+        };
+
+        println!(
+            "{} Block {} {} {} | Txs: {} | Gas: {}/{} ({:.1}%) | Difficulty: {}",
+            datetime.format("%H:%M:%S").to_string().bright_black(),
+            event.number.to_string().bright_yellow(),
+            block.hash.unwrap_or_default().to_string().bright_black(),
+            block_time.bright_black(),
+            event.transactions.to_string().bright_cyan(),
+            event.gas_used.to_string().bright_green(),
+            event.gas_limit,
+            event.gas_used_percent,
+            event.difficulty
+        );
+
+        Ok(())
+    }
+}
+
+pub async fn run(rpc_url: &str, ws_url: Option<&str>, interval: u64, output: OutputFormat) -> Result<()> {
+    if output.is_table() {
+        println!("{}", "Monitoring Mordor Testnet (Ctrl+C to stop)".bright_blue().bold());
+        println!("{}", "=".repeat(70).bright_blue());
+    }
+
+    let mut follower = HeadFollower {
+        provider: Provider::<Http>::try_from(rpc_url)?,
+        output,
+        history: VecDeque::new(),
+    };
+
+    match ws_url {
+        // newHeads only says that the head moved; blocks are still fetched
+        // over HTTP so gaps and reorgs are handled the same way as polling
+        Some(ws_url) => {
+            let ws = Provider::<Ws>::connect(ws_url).await?;
+            let mut heads = ws.subscribe_blocks().await?;
+            while let Some(head) = heads.next().await {
+                if let Some(number) = head.number {
+                    follower.advance(number.as_u64()).await?;
+                }
+            }
+            Err(anyhow!("newHeads subscription closed"))
+        }
+        None => loop {
+            let tip = follower.provider.get_block_number().await?.as_u64();
+            follower.advance(tip).await?;
+            sleep(Duration::from_secs(interval)).await;
+        },
+    }
+}
@@ -0,0 +1,46 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io::IsTerminal;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Yaml,
+}
+
+impl OutputFormat {
+    pub fn is_table(&self) -> bool {
+        *self == OutputFormat::Table
+    }
+}
+
+// Colour only makes sense on a terminal; piped output stays plain
+pub fn configure_colour() {
+    if !std::io::stdout().is_terminal() {
+        colored::control::set_override(false);
+    }
+}
+
+/// Prints a whole command result as one JSON or YAML document.
+pub fn emit<T: Serialize>(format: OutputFormat, value: &T) -> Result<()> {
+    match format {
+        OutputFormat::Table => {}
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(value)?),
+    }
+
+    Ok(())
+}
+
+/// Prints one record of a streaming command: a JSON line or a YAML document.
+pub fn emit_record<T: Serialize>(format: OutputFormat, value: &T) -> Result<()> {
+    match format {
+        OutputFormat::Table => {}
+        OutputFormat::Json => println!("{}", serde_json::to_string(value)?),
+        OutputFormat::Yaml => print!("---\n{}", serde_yaml::to_string(value)?),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,659 @@
+use anyhow::{anyhow, bail, Result};
+use clap::Subcommand;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tabled::{Table, Tabled};
+use tokio::time::Duration;
+
+use crate::exposition;
+use crate::output::{emit, OutputFormat};
+
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+const CHART_HEIGHT: usize = 8;
+
+#[derive(Subcommand)]
+pub enum ReportCommands {
+    /// Block time mean, percentiles and slow blocks
+    Blocktime {
+        /// Period to report on, e.g. 30m, 24h or 7d
+        #[arg(long, default_value = "24h")]
+        since: String,
+    },
+
+    /// Gas prices, utilization and full blocks
+    Gas {
+        /// Period to report on, e.g. 30m, 24h or 7d
+        #[arg(long, default_value = "24h")]
+        since: String,
+    },
+
+    /// Forks, reorg depth and missed blocks
+    Forks {
+        /// Period to report on, e.g. 30m, 24h or 7d
+        #[arg(long, default_value = "24h")]
+        since: String,
+    },
+}
+
+#[derive(Deserialize)]
+struct PrometheusResponse {
+    status: String,
+    data: Option<PrometheusData>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PrometheusData {
+    #[serde(rename = "resultType")]
+    result_type: String,
+    result: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct PrometheusResult {
+    metric: BTreeMap<String, String>,
+    value: Option<(f64, String)>,
+    values: Option<Vec<(f64, String)>>,
+}
+
+/// One value of an instant query.
+#[derive(Serialize)]
+pub struct Sample {
+    pub metric: BTreeMap<String, String>,
+    pub timestamp: f64,
+    pub value: f64,
+}
+
+/// The points of one series of a range query.
+#[derive(Serialize)]
+pub struct Series {
+    pub metric: BTreeMap<String, String>,
+    pub values: Vec<(f64, f64)>,
+}
+
+#[derive(Tabled)]
+struct SampleRow {
+    series: String,
+    value: String,
+}
+
+#[derive(Tabled)]
+struct SeriesRow {
+    series: String,
+    min: String,
+    max: String,
+    last: String,
+    trend: String,
+}
+
+#[derive(Tabled)]
+struct StatRow {
+    metric: String,
+    value: String,
+}
+
+#[derive(Serialize)]
+struct RangeReport<'a> {
+    query: &'a str,
+    start: f64,
+    end: f64,
+    step: u64,
+    series: &'a [Series],
+}
+
+#[derive(Serialize)]
+struct ReportOutput {
+    report: String,
+    since_seconds: u64,
+    stats: BTreeMap<String, Option<f64>>,
+    trend: Vec<(f64, f64)>,
+}
+
+#[derive(Clone, Copy)]
+enum Unit {
+    Seconds,
+    Count,
+    Percent,
+    // A 0-1 fraction shown as a percentage
+    Ratio,
+    Gwei,
+}
+
+struct Stat {
+    label: &'static str,
+    query: String,
+    unit: Unit,
+}
+
+// A canned report: instant stats over the period plus one trend line
+struct Report {
+    title: &'static str,
+    stats: Vec<Stat>,
+    trend_label: &'static str,
+    trend_query: String,
+    trend_unit: Unit,
+}
+
+/// Thin client for the Prometheus HTTP API.
+pub struct Prometheus {
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+impl Prometheus {
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Evaluates an instant query; scalar results come back as one
+    /// unlabelled sample.
+    pub async fn query(&self, promql: &str, time: Option<&str>) -> Result<Vec<Sample>> {
+        let mut params = vec![("query", promql.to_string())];
+        if let Some(time) = time {
+            params.push(("time", time.to_string()));
+        }
+
+        let data = self.get("query", &params).await?;
+        match data.result_type.as_str() {
+            "vector" => {
+                let results: Vec<PrometheusResult> = serde_json::from_value(data.result)?;
+                results
+                    .into_iter()
+                    .filter_map(|r| Some((r.metric, r.value?)))
+                    .map(|(metric, (timestamp, value))| {
+                        Ok(Sample { metric, timestamp, value: parse_value(&value)? })
+                    })
+                    .collect()
+            }
+            "scalar" => {
+                let (timestamp, value): (f64, String) = serde_json::from_value(data.result)?;
+                Ok(vec![Sample { metric: BTreeMap::new(), timestamp, value: parse_value(&value)? }])
+            }
+            other => bail!("Unsupported result type '{}' for an instant query", other),
+        }
+    }
+
+    pub async fn query_range(&self, promql: &str, start: f64, end: f64, step: u64) -> Result<Vec<Series>> {
+        let params = vec![
+            ("query", promql.to_string()),
+            ("start", start.to_string()),
+            ("end", end.to_string()),
+            ("step", step.to_string()),
+        ];
+
+        let data = self.get("query_range", &params).await?;
+        if data.result_type != "matrix" {
+            bail!("Unexpected result type '{}' for a range query", data.result_type);
+        }
+
+        let results: Vec<PrometheusResult> = serde_json::from_value(data.result)?;
+        results
+            .into_iter()
+            .map(|r| {
+                let values = r.values
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(at, value)| Ok((at, parse_value(&value)?)))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Series { metric: r.metric, values })
+            })
+            .collect()
+    }
+
+    async fn get(&self, api: &str, params: &[(&str, String)]) -> Result<PrometheusData> {
+        let url = format!("{}/api/v1/{}", self.endpoint, api);
+        // Bad queries come back as 4xx with an error body worth showing
+        let response: PrometheusResponse = self.client
+            .get(&url)
+            .query(params)
+            .timeout(Duration::from_secs(30))
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if response.status != "success" {
+            bail!("Prometheus query failed: {}", response.error.unwrap_or(response.status));
+        }
+        response.data.ok_or_else(|| anyhow!("Prometheus returned no data"))
+    }
+}
+
+/// `name{label="value",...}`, the way Prometheus prints a series.
+pub fn series_name(metric: &BTreeMap<String, String>) -> String {
+    let mut labels = metric.clone();
+    match labels.remove("__name__") {
+        Some(name) => exposition::series_name(&name, &labels),
+        None if labels.is_empty() => "{}".to_string(),
+        None => exposition::series_name("", &labels),
+    }
+}
+
+pub async fn query(endpoint: &str, promql: &str, time: Option<&str>, output: OutputFormat) -> Result<()> {
+    let samples = Prometheus::new(endpoint).query(promql, time).await?;
+
+    if !output.is_table() {
+        return emit(output, &samples);
+    }
+
+    if samples.is_empty() {
+        println!("{}", "No data".yellow());
+        return Ok(());
+    }
+
+    let rows: Vec<SampleRow> = samples
+        .iter()
+        .map(|s| SampleRow { series: series_name(&s.metric), value: format_number(s.value) })
+        .collect();
+    println!("{}", Table::new(rows));
+
+    Ok(())
+}
+
+pub async fn query_range(
+    endpoint: &str,
+    promql: &str,
+    since: &str,
+    step: Option<&str>,
+    width: usize,
+    chart: bool,
+    output: OutputFormat,
+) -> Result<()> {
+    let since = parse_duration(since)?;
+    let width = width.max(1);
+    let step = match step {
+        Some(step) => parse_duration(step)?,
+        None => default_step(since, width),
+    };
+    let end = now();
+    let start = end - since as f64;
+
+    let series = Prometheus::new(endpoint).query_range(promql, start, end, step).await?;
+
+    if !output.is_table() {
+        return emit(output, &RangeReport { query: promql, start, end, step, series: &series });
+    }
+
+    println!(
+        "{}",
+        format!("Last {}, step {}", format_duration(since), format_duration(step)).bright_blue().bold()
+    );
+    println!("{}", "=".repeat(70).bright_blue());
+
+    if series.is_empty() {
+        println!("{}", "No data".yellow());
+        return Ok(());
+    }
+
+    if chart {
+        for s in &series {
+            println!("\n{}", series_name(&s.metric).bright_cyan());
+            for line in render_chart(&points(&s.values), width, CHART_HEIGHT) {
+                println!("{}", line);
+            }
+        }
+        return Ok(());
+    }
+
+    let rows: Vec<SeriesRow> = series
+        .iter()
+        .map(|s| {
+            let values = points(&s.values);
+            let (min, max) = bounds(&values);
+            SeriesRow {
+                series: series_name(&s.metric),
+                min: min.map_or("-".to_string(), format_number),
+                max: max.map_or("-".to_string(), format_number),
+                last: values.last().map_or("-".to_string(), |v| format_number(*v)),
+                trend: sparkline(&values, width),
+            }
+        })
+        .collect();
+    println!("{}", Table::new(rows));
+
+    Ok(())
+}
+
+pub async fn report(endpoint: &str, command: ReportCommands, width: usize, output: OutputFormat) -> Result<()> {
+    let (name, since) = match &command {
+        ReportCommands::Blocktime { since } => ("blocktime", since),
+        ReportCommands::Gas { since } => ("gas", since),
+        ReportCommands::Forks { since } => ("forks", since),
+    };
+    let period = since.clone();
+    let since = parse_duration(since)?;
+    let report = match command {
+        ReportCommands::Blocktime { .. } => blocktime_report(since),
+        ReportCommands::Gas { .. } => gas_report(since),
+        ReportCommands::Forks { .. } => forks_report(since),
+    };
+
+    let prometheus = Prometheus::new(endpoint);
+    let mut stats = BTreeMap::new();
+    let mut rows = Vec::new();
+    for stat in &report.stats {
+        // Every stat aggregates to a single sample; an empty result means
+        // the series has not been scraped in the period
+        let value = prometheus
+            .query(&stat.query, None)
+            .await?
+            .first()
+            .map(|s| s.value)
+            .filter(|v| v.is_finite());
+        stats.insert(stat.label.to_string(), value);
+        rows.push(StatRow {
+            metric: stat.label.to_string(),
+            value: value.map_or("no data".to_string(), |v| format_unit(v, stat.unit)),
+        });
+    }
+
+    let end = now();
+    let step = default_step(since, width);
+    let trend = prometheus
+        .query_range(&report.trend_query, end - since as f64, end, step)
+        .await?
+        .into_iter()
+        .next()
+        .map(|s| s.values)
+        .unwrap_or_default();
+
+    if !output.is_table() {
+        return emit(output, &ReportOutput { report: name.to_string(), since_seconds: since, stats, trend });
+    }
+
+    println!(
+        "{}",
+        format!("{} report, last {}", report.title, period).bright_blue().bold()
+    );
+    println!("{}", "=".repeat(70).bright_blue());
+    println!("\n{}", Table::new(rows));
+
+    let values = points(&trend);
+    if values.is_empty() {
+        return Ok(());
+    }
+    let (min, max) = bounds(&values);
+    println!(
+        "\n{} ({} - {})",
+        report.trend_label,
+        min.map_or("-".to_string(), |v| format_unit(v, report.trend_unit)),
+        max.map_or("-".to_string(), |v| format_unit(v, report.trend_unit))
+    );
+    println!("{}", sparkline(&values, width).bright_green());
+
+    Ok(())
+}
+
+fn blocktime_report(since: u64) -> Report {
+    let window = format!("{}s", since);
+    let buckets = format!("sum by (le) (increase(etc_mordor_block_time_seconds_bucket[{}]))", window);
+    let count = format!("sum(increase(etc_mordor_block_time_seconds_count[{}]))", window);
+    let quantile = |q: f64| format!("histogram_quantile({}, {})", q, buckets);
+
+    Report {
+        title: "Block time",
+        stats: vec![
+            Stat { label: "Blocks", query: count.clone(), unit: Unit::Count },
+            Stat {
+                label: "Mean",
+                query: format!("sum(increase(etc_mordor_block_time_seconds_sum[{}])) / {}", window, count),
+                unit: Unit::Seconds,
+            },
+            Stat { label: "p50", query: quantile(0.5), unit: Unit::Seconds },
+            Stat { label: "p90", query: quantile(0.9), unit: Unit::Seconds },
+            Stat { label: "p99", query: quantile(0.99), unit: Unit::Seconds },
+            Stat {
+                label: "Slower than 30s",
+                query: format!(
+                    "1 - sum(increase(etc_mordor_block_time_seconds_bucket{{le=\"30\"}}[{}])) / {}",
+                    window, count
+                ),
+                unit: Unit::Ratio,
+            },
+        ],
+        trend_label: "Mean block time, 15m windows",
+        trend_query: "sum(rate(etc_mordor_block_time_seconds_sum[15m])) / sum(rate(etc_mordor_block_time_seconds_count[15m]))".to_string(),
+        trend_unit: Unit::Seconds,
+    }
+}
+
+fn gas_report(since: u64) -> Report {
+    let window = format!("{}s", since);
+
+    Report {
+        title: "Gas",
+        stats: vec![
+            Stat {
+                label: "Median gas price (avg)",
+                query: format!("avg(avg_over_time(etc_mordor_gas_price_median_wei[{}]))", window),
+                unit: Unit::Gwei,
+            },
+            Stat {
+                label: "Median gas price (max)",
+                query: format!("max(max_over_time(etc_mordor_gas_price_median_wei[{}]))", window),
+                unit: Unit::Gwei,
+            },
+            Stat {
+                label: "Utilization (avg)",
+                query: format!("avg(avg_over_time(etc_mordor_gas_utilization_percent[{}]))", window),
+                unit: Unit::Percent,
+            },
+            Stat {
+                label: "Full blocks",
+                query: format!("sum(increase(etc_mordor_full_blocks_total[{}]))", window),
+                unit: Unit::Count,
+            },
+        ],
+        trend_label: "Median gas price",
+        trend_query: "avg(etc_mordor_gas_price_median_wei)".to_string(),
+        trend_unit: Unit::Gwei,
+    }
+}
+
+fn forks_report(since: u64) -> Report {
+    let window = format!("{}s", since);
+
+    Report {
+        title: "Forks",
+        stats: vec![
+            Stat {
+                label: "Forks",
+                query: format!("sum(increase(etc_mordor_fork_total[{}]))", window),
+                unit: Unit::Count,
+            },
+            Stat {
+                label: "Deepest reorg (bucket)",
+                query: format!(
+                    "histogram_quantile(1, sum by (le) (increase(etc_mordor_fork_depth_bucket[{}])))",
+                    window
+                ),
+                unit: Unit::Count,
+            },
+            Stat {
+                label: "Missed blocks",
+                query: format!("sum(increase(etc_mordor_missed_blocks_total[{}]))", window),
+                unit: Unit::Count,
+            },
+            Stat {
+                label: "Most active forks",
+                query: format!("max(max_over_time(etc_mordor_active_forks[{}]))", window),
+                unit: Unit::Count,
+            },
+        ],
+        trend_label: "Active forks",
+        trend_query: "max(etc_mordor_active_forks)".to_string(),
+        trend_unit: Unit::Count,
+    }
+}
+
+/// Parses Prometheus-style durations such as `90s`, `30m`, `1h30m`, `7d`;
+/// a bare number is seconds.
+pub fn parse_duration(value: &str) -> Result<u64> {
+    let invalid = || anyhow!("Invalid duration '{}', expected e.g. 30m, 24h or 7d", value);
+
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in value.trim().chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3_600,
+            'd' => 86_400,
+            'w' => 604_800,
+            _ => return Err(invalid()),
+        };
+        let amount: u64 = digits.parse().map_err(|_| invalid())?;
+        total += amount * unit;
+        digits.clear();
+    }
+    if !digits.is_empty() {
+        total += digits.parse::<u64>().map_err(|_| invalid())?;
+    }
+
+    match total {
+        0 => Err(invalid()),
+        total => Ok(total),
+    }
+}
+
+fn format_duration(seconds: u64) -> String {
+    match seconds {
+        s if s % 86_400 == 0 => format!("{}d", s / 86_400),
+        s if s % 3_600 == 0 => format!("{}h", s / 3_600),
+        s if s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+// About one point per sparkline column
+fn default_step(since: u64, width: usize) -> u64 {
+    since.div_ceil(width as u64).max(1)
+}
+
+fn parse_value(value: &str) -> Result<f64> {
+    value
+        .parse()
+        .map_err(|_| anyhow!("Invalid sample value '{}'", value))
+}
+
+fn points(values: &[(f64, f64)]) -> Vec<f64> {
+    values.iter().map(|(_, v)| *v).filter(|v| v.is_finite()).collect()
+}
+
+fn bounds(values: &[f64]) -> (Option<f64>, Option<f64>) {
+    let min = values.iter().copied().reduce(f64::min);
+    let max = values.iter().copied().reduce(f64::max);
+    (min, max)
+}
+
+// Averages the values down to at most `width` columns
+fn resample(values: &[f64], width: usize) -> Vec<f64> {
+    if values.len() <= width {
+        return values.to_vec();
+    }
+    (0..width)
+        .map(|i| {
+            let from = i * values.len() / width;
+            let to = ((i + 1) * values.len() / width).max(from + 1);
+            values[from..to].iter().sum::<f64>() / (to - from) as f64
+        })
+        .collect()
+}
+
+fn sparkline(values: &[f64], width: usize) -> String {
+    let values = resample(values, width);
+    let (Some(min), Some(max)) = bounds(&values) else {
+        return String::new();
+    };
+    let span = max - min;
+
+    values
+        .iter()
+        .map(|v| {
+            let level = match span > 0.0 {
+                true => ((v - min) / span * (SPARK_CHARS.len() - 1) as f64).round() as usize,
+                false => SPARK_CHARS.len() / 2,
+            };
+            SPARK_CHARS[level.min(SPARK_CHARS.len() - 1)]
+        })
+        .collect()
+}
+
+// A column chart `height` rows tall with the max and min on the axis
+fn render_chart(values: &[f64], width: usize, height: usize) -> Vec<String> {
+    let values = resample(values, width);
+    let (Some(min), Some(max)) = bounds(&values) else {
+        return vec!["No data".to_string()];
+    };
+    let span = max - min;
+    // Filled rows per column, in eighths of a row; the minimum still
+    // gets a baseline
+    let levels: Vec<usize> = values
+        .iter()
+        .map(|v| match span > 0.0 {
+            true => (((v - min) / span * (height * 8) as f64).round() as usize).max(1),
+            false => height * 4,
+        })
+        .collect();
+
+    let max_label = format_number(max);
+    let min_label = format_number(min);
+    let label_width = max_label.len().max(min_label.len());
+
+    (0..height)
+        .rev()
+        .map(|row| {
+            let label = match row {
+                r if r == height - 1 => format!("{:>w$}", max_label, w = label_width),
+                0 => format!("{:>w$}", min_label, w = label_width),
+                _ => " ".repeat(label_width),
+            };
+            let bars: String = levels
+                .iter()
+                .map(|level| match level.saturating_sub(row * 8) {
+                    0 => ' ',
+                    n if n >= 8 => '█',
+                    n => SPARK_CHARS[n - 1],
+                })
+                .collect();
+            format!("{} ┤{}", label.bright_black(), bars.bright_green())
+        })
+        .collect()
+}
+
+fn format_number(value: f64) -> String {
+    if value.is_nan() || value.is_infinite() || value.fract() != 0.0 {
+        return match value.abs() {
+            v if v >= 1e6 && v.is_finite() => format!("{:.4e}", value),
+            _ => format!("{:.4}", value).trim_end_matches('0').trim_end_matches('.').to_string(),
+        };
+    }
+    format!("{}", value)
+}
+
+fn format_unit(value: f64, unit: Unit) -> String {
+    match unit {
+        Unit::Seconds => format!("{:.2}s", value),
+        Unit::Count => format!("{:.0}", value),
+        Unit::Percent => format!("{:.2}%", value),
+        Unit::Ratio => format!("{:.2}%", value * 100.0),
+        Unit::Gwei => format!("{:.3} gwei", value / 1e9),
+    }
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
@@ -0,0 +1,169 @@
+use serde::Serialize;
+
+use crate::exposition::MetricType;
+use crate::exposition::MetricType::{Counter, Gauge, Histogram};
+use Service::{ForkMonitor, GasEstimator, Indexer};
+
+/// The service that exports a metric, named as its Prometheus job.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Service {
+    ForkMonitor,
+    GasEstimator,
+    Indexer,
+}
+
+impl Service {
+    pub const ALL: [Service; 3] = [Service::ForkMonitor, Service::GasEstimator, Service::Indexer];
+
+    pub fn job(&self) -> &'static str {
+        match self {
+            Service::ForkMonitor => "fork-monitor",
+            Service::GasEstimator => "gas-estimator",
+            Service::Indexer => "indexer",
+        }
+    }
+}
+
+/// Description of one exported metric. Dashboards and rules refer to these
+/// constants rather than to metric names, and `gen check` compares them
+/// with what the services actually export.
+pub struct MetricSpec {
+    pub name: &'static str,
+    pub service: Service,
+    pub kind: MetricType,
+    pub help: &'static str,
+    pub labels: &'static [&'static str],
+}
+
+const fn metric(
+    name: &'static str,
+    service: Service,
+    kind: MetricType,
+    help: &'static str,
+    labels: &'static [&'static str],
+) -> MetricSpec {
+    MetricSpec { name, service, kind, help, labels }
+}
+
+// fork-monitor/src/metrics.rs
+pub const BLOCK_HEIGHT: MetricSpec = metric("etc_mordor_block_height", ForkMonitor, Gauge, "Current block height", &[]);
+pub const BLOCK_TIMESTAMP: MetricSpec = metric("etc_mordor_block_timestamp", ForkMonitor, Gauge, "Block timestamp", &[]);
+pub const BLOCK_GAS_USED: MetricSpec = metric("etc_mordor_block_gas_used", ForkMonitor, Gauge, "Gas used in current block", &[]);
+pub const BLOCK_GAS_LIMIT: MetricSpec = metric("etc_mordor_block_gas_limit", ForkMonitor, Gauge, "Block gas limit", &[]);
+pub const BLOCK_TIME: MetricSpec = metric("etc_mordor_block_time_seconds", ForkMonitor, Histogram, "Time between blocks", &[]);
+pub const BLOCK_DIFFICULTY: MetricSpec = metric("etc_mordor_block_difficulty", ForkMonitor, Histogram, "Block difficulty", &[]);
+pub const TRANSACTION_COUNT: MetricSpec = metric("etc_mordor_transaction_count", ForkMonitor, Gauge, "Transactions in current block", &[]);
+pub const FORKS: MetricSpec = metric("etc_mordor_fork_total", ForkMonitor, Counter, "Total number of forks detected", &[]);
+pub const FORK_DEPTH: MetricSpec = metric("etc_mordor_fork_depth", ForkMonitor, Histogram, "Fork reorganization depth", &[]);
+pub const ACTIVE_FORKS: MetricSpec = metric("etc_mordor_active_forks", ForkMonitor, Gauge, "Number of currently active forks", &[]);
+pub const MISSED_BLOCKS: MetricSpec = metric("etc_mordor_missed_blocks_total", ForkMonitor, Counter, "Total missed blocks", &[]);
+pub const GAS_LIMIT_VOTES: MetricSpec = metric("etc_mordor_gas_limit_votes_total", ForkMonitor, Counter, "Gas limit votes (up/down/hold) by miner", &["miner", "direction"]);
+pub const GAS_UTILIZATION_AVG: MetricSpec = metric("etc_mordor_gas_utilization_avg_percent", ForkMonitor, Gauge, "Average block gas utilization over a rolling window", &["window"]);
+pub const FULL_BLOCKS_TOTAL: MetricSpec = metric("etc_mordor_full_blocks_total", ForkMonitor, Counter, "Total blocks above 95% gas utilization", &[]);
+pub const FULL_BLOCKS: MetricSpec = metric("etc_mordor_full_blocks", ForkMonitor, Gauge, "Blocks above 95% gas utilization over a rolling window", &["window"]);
+pub const GAS_LIMIT_TREND: MetricSpec = metric("etc_mordor_gas_limit_trend_per_hour", ForkMonitor, Gauge, "Gas limit drift over the last hour (gas/hour)", &[]);
+pub const GAS_LIMIT_DRIFT: MetricSpec = metric("etc_mordor_gas_limit_drift_direction", ForkMonitor, Gauge, "Predicted gas limit direction (1 up, 0 stable, -1 down)", &[]);
+pub const PEER_COUNT: MetricSpec = metric("etc_mordor_peer_count", ForkMonitor, Gauge, "Connected peers (net_peerCount)", &[]);
+pub const PEER_COUNT_LOW: MetricSpec = metric("etc_mordor_peer_count_low", ForkMonitor, Gauge, "1 while the peer count is below the configured minimum", &[]);
+pub const PEERS_BY_DIRECTION: MetricSpec = metric("etc_mordor_peers", ForkMonitor, Gauge, "Connected peers by direction (inbound/outbound)", &["direction"]);
+pub const PEERS_BY_CLIENT: MetricSpec = metric("etc_mordor_peers_by_client", ForkMonitor, Gauge, "Connected peers by client version", &["client"]);
+pub const PEERS_BY_FORK_ID: MetricSpec = metric("etc_mordor_peers_by_fork_id", ForkMonitor, Gauge, "Connected peers by fork ID (match/mismatch/unknown)", &["status"]);
+pub const FORK_ID_MATCH_RATIO: MetricSpec = metric("etc_mordor_peers_fork_id_match_ratio", ForkMonitor, Gauge, "Fraction of peers with a known fork ID advertising the Mordor fork ID", &[]);
+pub const PEER_CONNECTS: MetricSpec = metric("etc_mordor_peer_connects_total", ForkMonitor, Counter, "Peers seen connecting", &[]);
+pub const PEER_DISCONNECTS: MetricSpec = metric("etc_mordor_peer_disconnects_total", ForkMonitor, Counter, "Peers seen disconnecting", &[]);
+pub const SYNC_SYNCING: MetricSpec = metric("etc_mordor_sync_syncing", ForkMonitor, Gauge, "1 while the node is syncing", &[]);
+pub const SYNC_CURRENT_BLOCK: MetricSpec = metric("etc_mordor_sync_current_block", ForkMonitor, Gauge, "Current block while syncing", &[]);
+pub const SYNC_HIGHEST_BLOCK: MetricSpec = metric("etc_mordor_sync_highest_block", ForkMonitor, Gauge, "Highest known block", &[]);
+pub const SYNC_PROGRESS: MetricSpec = metric("etc_mordor_sync_progress_percent", ForkMonitor, Gauge, "Current block as a percentage of the highest known block", &[]);
+pub const SYNC_KNOWN_STATES: MetricSpec = metric("etc_mordor_sync_known_states", ForkMonitor, Gauge, "Known state entries during state sync", &[]);
+pub const SYNC_PULLED_STATES: MetricSpec = metric("etc_mordor_sync_pulled_states", ForkMonitor, Gauge, "Pulled state entries during state sync", &[]);
+pub const SYNC_BLOCKS_PER_SECOND: MetricSpec = metric("etc_mordor_sync_blocks_per_second", ForkMonitor, Gauge, "Blocks imported per second over a 5 minute window", &[]);
+pub const SYNC_ETA: MetricSpec = metric("etc_mordor_sync_eta_seconds", ForkMonitor, Gauge, "Estimated seconds until the node is synced (-1 when unknown)", &[]);
+pub const ANOMALY_SCORE: MetricSpec = metric("etc_mordor_anomaly_score", ForkMonitor, Gauge, "Block time/hashrate anomaly score by kind (1 = alert threshold)", &["kind"]);
+pub const ANOMALIES: MetricSpec = metric("etc_mordor_anomalies_total", ForkMonitor, Counter, "Anomalies detected by kind", &["kind"]);
+pub const REORGED_TRANSACTIONS: MetricSpec = metric("etc_mordor_reorged_transactions_total", ForkMonitor, Counter, "Transactions in orphaned blocks by outcome (dropped/reincluded/replaced)", &["kind"]);
+pub const TIMESTAMP_VIOLATIONS: MetricSpec = metric("etc_mordor_timestamp_violations_total", ForkMonitor, Counter, "Block timestamp violations (future/non_monotonic/min_interval) by miner", &["kind", "miner"]);
+pub const RECOMMENDED_CONFIRMATIONS: MetricSpec = metric("etc_mordor_recommended_confirmations", ForkMonitor, Gauge, "Confirmations recommended for the configured target reorg risk (-1 when none is enough)", &[]);
+pub const HASHRATE_ESTIMATE: MetricSpec = metric("etc_mordor_hashrate_estimate", ForkMonitor, Gauge, "Network hashrate estimated from difficulty and block times (H/s)", &["window"]);
+
+// gas-estimator/src/metrics.rs
+pub const GAS_PRICE_MIN: MetricSpec = metric("etc_mordor_gas_price_min_wei", GasEstimator, Gauge, "Minimum gas price", &[]);
+pub const GAS_PRICE_MAX: MetricSpec = metric("etc_mordor_gas_price_max_wei", GasEstimator, Gauge, "Maximum gas price", &[]);
+pub const GAS_PRICE_MEDIAN: MetricSpec = metric("etc_mordor_gas_price_median_wei", GasEstimator, Gauge, "Median gas price", &[]);
+pub const GAS_PRICE_P25: MetricSpec = metric("etc_mordor_gas_price_p25_wei", GasEstimator, Gauge, "25th percentile gas price", &[]);
+pub const GAS_PRICE_P75: MetricSpec = metric("etc_mordor_gas_price_p75_wei", GasEstimator, Gauge, "75th percentile gas price", &[]);
+pub const GAS_PRICE_MEAN: MetricSpec = metric("etc_mordor_gas_price_mean_wei", GasEstimator, Gauge, "Mean gas price", &[]);
+pub const GAS_UTILIZATION: MetricSpec = metric("etc_mordor_gas_utilization_percent", GasEstimator, Gauge, "Gas utilization percentage", &[]);
+pub const AVG_TX_PER_BLOCK: MetricSpec = metric("etc_mordor_avg_tx_per_block", GasEstimator, Gauge, "Average transactions per block", &[]);
+pub const GAS_USED_BY_KIND: MetricSpec = metric("etc_mordor_gas_used_by_kind", GasEstimator, Gauge, "Gas used per transaction kind over a rolling window", &["window", "kind"]);
+pub const TOP_GAS_CONSUMERS: MetricSpec = metric("etc_mordor_top_gas_consumer_gas_used", GasEstimator, Gauge, "Gas used by the top contract/method consumers over a rolling window", &["window", "kind", "contract", "selector"]);
+
+// indexer/src/metrics.rs
+pub const INDEXER_INDEXED_HEIGHT: MetricSpec = metric("etc_mordor_indexer_indexed_height", Indexer, Gauge, "Highest contiguous indexed block", &[]);
+pub const INDEXER_CHAIN_HEIGHT: MetricSpec = metric("etc_mordor_indexer_chain_height", Indexer, Gauge, "Chain tip reported by the node", &[]);
+pub const INDEXER_LAG: MetricSpec = metric("etc_mordor_indexer_lag_blocks", Indexer, Gauge, "Blocks between the chain tip and the index", &[]);
+pub const INDEXER_BLOCKS: MetricSpec = metric("etc_mordor_indexer_blocks_indexed_total", Indexer, Counter, "Total blocks written to the index", &[]);
+pub const INDEXER_TRANSACTIONS: MetricSpec = metric("etc_mordor_indexer_transactions_indexed_total", Indexer, Counter, "Total transactions written to the index", &[]);
+pub const INDEXER_REORG_ROLLBACKS: MetricSpec = metric("etc_mordor_indexer_reorg_rollbacks_total", Indexer, Counter, "Total reorgs rolled back in the index", &[]);
+pub const INDEXER_ROLLED_BACK_BLOCKS: MetricSpec = metric("etc_mordor_indexer_rolled_back_blocks_total", Indexer, Counter, "Total orphaned blocks removed from the index", &[]);
+
+/// Every metric the services export.
+pub const METRICS: &[MetricSpec] = &[
+    BLOCK_HEIGHT,
+    BLOCK_TIMESTAMP,
+    BLOCK_GAS_USED,
+    BLOCK_GAS_LIMIT,
+    BLOCK_TIME,
+    BLOCK_DIFFICULTY,
+    TRANSACTION_COUNT,
+    FORKS,
+    FORK_DEPTH,
+    ACTIVE_FORKS,
+    MISSED_BLOCKS,
+    GAS_LIMIT_VOTES,
+    GAS_UTILIZATION_AVG,
+    FULL_BLOCKS_TOTAL,
+    FULL_BLOCKS,
+    GAS_LIMIT_TREND,
+    GAS_LIMIT_DRIFT,
+    PEER_COUNT,
+    PEER_COUNT_LOW,
+    PEERS_BY_DIRECTION,
+    PEERS_BY_CLIENT,
+    PEERS_BY_FORK_ID,
+    FORK_ID_MATCH_RATIO,
+    PEER_CONNECTS,
+    PEER_DISCONNECTS,
+    SYNC_SYNCING,
+    SYNC_CURRENT_BLOCK,
+    SYNC_HIGHEST_BLOCK,
+    SYNC_PROGRESS,
+    SYNC_KNOWN_STATES,
+    SYNC_PULLED_STATES,
+    SYNC_BLOCKS_PER_SECOND,
+    SYNC_ETA,
+    ANOMALY_SCORE,
+    ANOMALIES,
+    HASHRATE_ESTIMATE,
+    TIMESTAMP_VIOLATIONS,
+    REORGED_TRANSACTIONS,
+    RECOMMENDED_CONFIRMATIONS,
+    GAS_PRICE_MIN,
+    GAS_PRICE_MAX,
+    GAS_PRICE_MEDIAN,
+    GAS_PRICE_P25,
+    GAS_PRICE_P75,
+    GAS_PRICE_MEAN,
+    GAS_UTILIZATION,
+    AVG_TX_PER_BLOCK,
+    GAS_USED_BY_KIND,
+    TOP_GAS_CONSUMERS,
+    INDEXER_INDEXED_HEIGHT,
+    INDEXER_CHAIN_HEIGHT,
+    INDEXER_LAG,
+    INDEXER_BLOCKS,
+    INDEXER_TRANSACTIONS,
+    INDEXER_REORG_ROLLBACKS,
+    INDEXER_ROLLED_BACK_BLOCKS,
+];
@@ -0,0 +1,236 @@
+use anyhow::Result;
+use colored::*;
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::types::SyncingStatus;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::time::Instant;
+use tabled::{Table, Tabled};
+use tokio::time::{sleep, Duration};
+
+use crate::output::{emit, emit_record, OutputFormat};
+
+const PROGRESS_BAR_WIDTH: usize = 40;
+
+// Same shape as the fork monitor's /sync endpoint
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SyncStatus {
+    pub syncing: bool,
+    pub starting_block: u64,
+    pub current_block: u64,
+    pub highest_block: u64,
+    pub known_states: Option<u64>,
+    pub pulled_states: Option<u64>,
+    pub progress_percent: f64,
+    pub blocks_per_second: f64,
+    pub eta_seconds: Option<u64>,
+}
+
+#[derive(Tabled)]
+struct SyncRow {
+    metric: String,
+    value: String,
+}
+
+impl SyncStatus {
+    /// Builds a snapshot from eth_syncing; the rate needs more than one
+    /// sample and is left unknown.
+    pub fn from_rpc(status: &SyncingStatus, head: u64) -> Self {
+        match status {
+            SyncingStatus::IsFalse => SyncStatus {
+                syncing: false,
+                starting_block: head,
+                current_block: head,
+                highest_block: head,
+                known_states: None,
+                pulled_states: None,
+                progress_percent: 100.0,
+                blocks_per_second: 0.0,
+                eta_seconds: Some(0),
+            },
+            SyncingStatus::IsSyncing(progress) => {
+                let current = progress.current_block.as_u64();
+                let highest = progress.highest_block.as_u64();
+                SyncStatus {
+                    syncing: true,
+                    starting_block: progress.starting_block.as_u64(),
+                    current_block: current,
+                    highest_block: highest,
+                    known_states: progress.known_states.map(|s| s.as_u64()),
+                    pulled_states: progress.pulled_states.map(|s| s.as_u64()),
+                    progress_percent: progress_percent(current, highest),
+                    blocks_per_second: 0.0,
+                    eta_seconds: None,
+                }
+            }
+        }
+    }
+
+    pub fn with_rate(mut self, blocks_per_second: f64) -> Self {
+        self.blocks_per_second = blocks_per_second;
+        if self.syncing {
+            let remaining = self.highest_block.saturating_sub(self.current_block);
+            self.eta_seconds = (blocks_per_second > 0.0)
+                .then(|| (remaining as f64 / blocks_per_second) as u64);
+        }
+        self
+    }
+
+    pub fn rows(&self) -> Vec<(String, String)> {
+        let mut rows = vec![(
+            "Sync Progress".to_string(),
+            format!("{} / {} ({:.2}%)", self.current_block, self.highest_block, self.progress_percent),
+        )];
+        if let (Some(pulled), Some(known)) = (self.pulled_states, self.known_states) {
+            rows.push(("States".to_string(), format!("{} pulled / {} known", pulled, known)));
+        }
+        rows.push(("Sync Rate".to_string(), format!("{:.2} blocks/s", self.blocks_per_second)));
+        rows.push((
+            "Sync ETA".to_string(),
+            self.eta_seconds.map_or("unknown".to_string(), format_eta),
+        ));
+        rows
+    }
+}
+
+/// Reads the fork monitor's sync report, which carries a rate measured
+/// over its own moving window.
+pub async fn fetch_sync_status(endpoint: &str) -> Result<SyncStatus> {
+    let url = format!("{}/sync", endpoint.trim_end_matches('/'));
+    let client = reqwest::Client::new();
+
+    Ok(client
+        .get(&url)
+        .timeout(Duration::from_secs(2))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+pub async fn run(
+    rpc_url: &str,
+    fork_monitor: &str,
+    watch: bool,
+    interval: u64,
+    window: u64,
+    output: OutputFormat,
+) -> Result<()> {
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+
+    if watch {
+        return watch_sync(&provider, interval, window, output).await;
+    }
+
+    let status = snapshot(&provider).await?;
+    // Take the rate and ETA from the fork monitor when it is running
+    let status = match fetch_sync_status(fork_monitor).await {
+        Ok(monitored) if status.syncing => status.with_rate(monitored.blocks_per_second),
+        _ => status,
+    };
+
+    if !output.is_table() {
+        return emit(output, &status);
+    }
+
+    println!("{}", "Sync Status".bright_blue().bold());
+    println!("{}", "=".repeat(50).bright_blue());
+
+    if !status.syncing {
+        println!("\n{} at block {}", "Synced".green(), status.current_block);
+        return Ok(());
+    }
+
+    println!("\n{}", progress_bar(status.progress_percent));
+    let rows: Vec<SyncRow> = status
+        .rows()
+        .into_iter()
+        .map(|(metric, value)| SyncRow { metric, value })
+        .collect();
+    println!("{}", Table::new(rows));
+
+    Ok(())
+}
+
+async fn snapshot(provider: &Provider<Http>) -> Result<SyncStatus> {
+    let (status, head) = tokio::try_join!(provider.syncing(), provider.get_block_number())?;
+    Ok(SyncStatus::from_rpc(&status, head.as_u64()))
+}
+
+// Samples eth_syncing until the node is synced, with the rate taken over
+// the last `window` seconds
+async fn watch_sync(provider: &Provider<Http>, interval: u64, window: u64, output: OutputFormat) -> Result<()> {
+    let started = Instant::now();
+    let mut samples: VecDeque<(f64, u64)> = VecDeque::new();
+
+    loop {
+        let status = snapshot(provider).await?;
+        let now = started.elapsed().as_secs_f64();
+
+        samples.push_back((now, status.current_block));
+        while samples.front().is_some_and(|(at, _)| at + (window as f64) < now) {
+            samples.pop_front();
+        }
+        let status = status.with_rate(rate(&samples));
+
+        if output.is_table() {
+            if status.syncing {
+                print!(
+                    "\r{} {}/{} | {:.2} blocks/s | ETA {}   ",
+                    progress_bar(status.progress_percent),
+                    status.current_block,
+                    status.highest_block,
+                    status.blocks_per_second,
+                    status.eta_seconds.map_or("unknown".to_string(), format_eta)
+                );
+                std::io::stdout().flush()?;
+            } else {
+                println!("\r{} at block {}{}", "Synced".green(), status.current_block, " ".repeat(60));
+            }
+        } else {
+            emit_record(output, &status)?;
+        }
+
+        if !status.syncing {
+            return Ok(());
+        }
+
+        sleep(Duration::from_secs(interval)).await;
+    }
+}
+
+fn progress_percent(current: u64, highest: u64) -> f64 {
+    if highest == 0 {
+        return 100.0;
+    }
+    (current as f64 / highest as f64 * 100.0).min(100.0)
+}
+
+fn rate(samples: &VecDeque<(f64, u64)>) -> f64 {
+    match (samples.front(), samples.back()) {
+        (Some((t0, b0)), Some((t1, b1))) if t1 > t0 => b1.saturating_sub(*b0) as f64 / (t1 - t0),
+        _ => 0.0,
+    }
+}
+
+fn progress_bar(percent: f64) -> String {
+    let filled = ((percent / 100.0) * PROGRESS_BAR_WIDTH as f64).round() as usize;
+    let filled = filled.min(PROGRESS_BAR_WIDTH);
+    format!(
+        "[{}{}] {:>6.2}%",
+        "█".repeat(filled).bright_green(),
+        "░".repeat(PROGRESS_BAR_WIDTH - filled).bright_black(),
+        percent
+    )
+}
+
+fn format_eta(seconds: u64) -> String {
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3_599 => format!("{}m {}s", seconds / 60, seconds % 60),
+        3_600..=86_399 => format!("{}h {}m", seconds / 3_600, seconds % 3_600 / 60),
+        _ => format!("{}d {}h", seconds / 86_400, seconds % 86_400 / 3_600),
+    }
+}
@@ -0,0 +1,304 @@
+use anyhow::{anyhow, bail, Result};
+use colored::*;
+use ethers::abi::Abi;
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::types::{BlockNumber, Transaction, TransactionReceipt, H256};
+use ethers::utils::{format_ether, hex};
+use serde::Serialize;
+use std::path::Path;
+use tabled::{Table, Tabled};
+use tokio::time::{sleep, Duration};
+
+use crate::abi::{self, DecodedCall};
+use crate::output::{emit, OutputFormat};
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum TxStatus {
+    Pending,
+    Success,
+    Failed,
+    // Mined before receipts carried a status field
+    Mined,
+    Dropped,
+    Replaced,
+}
+
+impl TxStatus {
+    fn label(&self) -> ColoredString {
+        match self {
+            TxStatus::Pending => "pending".bright_yellow(),
+            TxStatus::Success => "success".bright_green(),
+            TxStatus::Failed => "failed".bright_red(),
+            TxStatus::Mined => "mined".bright_green(),
+            TxStatus::Dropped => "dropped".bright_red(),
+            TxStatus::Replaced => "replaced".bright_red(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TxReport {
+    hash: String,
+    status: TxStatus,
+    block_number: Option<u64>,
+    block_hash: Option<String>,
+    confirmations: u64,
+    from: String,
+    to: Option<String>,
+    contract_created: Option<String>,
+    value_wei: String,
+    value_etc: String,
+    nonce: u64,
+    gas_limit: u64,
+    gas_used: Option<u64>,
+    gas_used_percent: Option<f64>,
+    effective_gas_price_wei: Option<u128>,
+    fee_wei: Option<String>,
+    fee_etc: Option<String>,
+    logs: Option<usize>,
+    input_bytes: usize,
+    method: Option<String>,
+    decoded_input: Option<DecodedCall>,
+}
+
+#[derive(Tabled)]
+struct TxField {
+    field: String,
+    value: String,
+}
+
+fn build_report(
+    tx: &Transaction,
+    receipt: Option<&TransactionReceipt>,
+    tip: u64,
+    abi: Option<&Abi>,
+) -> Result<TxReport> {
+    let block_number = receipt.and_then(|r| r.block_number).or(tx.block_number).map(|n| n.as_u64());
+
+    let status = match receipt {
+        None => TxStatus::Pending,
+        Some(receipt) => match receipt.status.map(|s| s.as_u64()) {
+            Some(1) => TxStatus::Success,
+            Some(_) => TxStatus::Failed,
+            None => TxStatus::Mined,
+        },
+    };
+
+    let gas_used = receipt.and_then(|r| r.gas_used);
+    // ETC has no EIP-1559 so most receipts omit effectiveGasPrice
+    let gas_price = receipt.and_then(|r| r.effective_gas_price).or(tx.gas_price);
+    let fee = match (gas_used, gas_price) {
+        (Some(used), Some(price)) => Some(used * price),
+        _ => None,
+    };
+
+    let decoded_input = match abi {
+        Some(abi) => abi::decode_input(abi, &tx.input)?,
+        None => None,
+    };
+
+    Ok(TxReport {
+        hash: format!("{:?}", tx.hash),
+        status,
+        block_number,
+        block_hash: receipt.and_then(|r| r.block_hash).map(|h| format!("{:?}", h)),
+        confirmations: block_number.map_or(0, |n| tip.saturating_sub(n) + 1),
+        from: format!("{:?}", tx.from),
+        to: tx.to.map(|to| format!("{:?}", to)),
+        contract_created: receipt.and_then(|r| r.contract_address).map(|a| format!("{:?}", a)),
+        value_wei: tx.value.to_string(),
+        value_etc: format_ether(tx.value),
+        nonce: tx.nonce.as_u64(),
+        gas_limit: tx.gas.as_u64(),
+        gas_used: gas_used.map(|g| g.as_u64()),
+        gas_used_percent: gas_used.map(|g| g.as_u64() as f64 / tx.gas.as_u64() as f64 * 100.0),
+        effective_gas_price_wei: gas_price.map(|p| p.as_u128()),
+        fee_wei: fee.map(|f| f.to_string()),
+        fee_etc: fee.map(format_ether),
+        logs: receipt.map(|r| r.logs.len()),
+        input_bytes: tx.input.len(),
+        method: (tx.input.len() >= 4).then(|| format!("0x{}", hex::encode(&tx.input[..4]))),
+        decoded_input,
+    })
+}
+
+// A pending transaction whose nonce has been used by a mined one can never
+// be included any more, unless the mined one is the transaction itself, so
+// callers check its receipt before calling it replaced
+async fn nonce_used(provider: &Provider<Http>, tx: &Transaction) -> Result<bool> {
+    let next_nonce = provider.get_transaction_count(tx.from, Some(BlockNumber::Latest.into())).await?;
+    Ok(next_nonce > tx.nonce)
+}
+
+async fn fetch(provider: &Provider<Http>, hash: H256, abi: Option<&Abi>) -> Result<Option<(Transaction, TxReport)>> {
+    let tx = match provider.get_transaction(hash).await? {
+        Some(tx) => tx,
+        None => return Ok(None),
+    };
+
+    let mut receipt = match tx.block_number {
+        Some(_) => provider.get_transaction_receipt(hash).await?,
+        None => None,
+    };
+    let mut replaced = false;
+    if tx.block_number.is_none() && nonce_used(provider, &tx).await? {
+        // Either replaced or mined itself since it was fetched
+        receipt = provider.get_transaction_receipt(hash).await?;
+        replaced = receipt.is_none();
+    }
+    let tip = provider.get_block_number().await?.as_u64();
+
+    let mut report = build_report(&tx, receipt.as_ref(), tip, abi)?;
+    if replaced {
+        report.status = TxStatus::Replaced;
+    }
+
+    Ok(Some((tx, report)))
+}
+
+/// Shows a transaction and its receipt. With `wait`, polls until the
+/// transaction has the requested confirmations or is dropped or replaced,
+/// and fails unless it ends up mined successfully.
+pub async fn run(
+    rpc_url: &str,
+    hash: H256,
+    abi_path: Option<&Path>,
+    wait: bool,
+    confirmations: u64,
+    interval: u64,
+    output: OutputFormat,
+) -> Result<()> {
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+    let abi = abi_path.map(abi::load).transpose()?;
+
+    if !wait {
+        let (_, report) = fetch(&provider, hash, abi.as_ref()).await?
+            .ok_or_else(|| anyhow!("Transaction {:?} not found", hash))?;
+        return print_report(&report, output);
+    }
+
+    let mut last: Option<(Transaction, TxReport)> = None;
+    let mut progress = String::new();
+    loop {
+        match fetch(&provider, hash, abi.as_ref()).await? {
+            Some((tx, report)) => {
+                let done = match report.status {
+                    TxStatus::Pending => false,
+                    TxStatus::Success | TxStatus::Mined => report.confirmations >= confirmations,
+                    TxStatus::Failed | TxStatus::Dropped | TxStatus::Replaced => true,
+                };
+                if done {
+                    return finish(&report, output);
+                }
+
+                // Progress goes to stderr so --output json stays parseable
+                let line = match report.block_number {
+                    Some(number) => format!(
+                        "Waiting: {}/{} confirmations (block {})",
+                        report.confirmations, confirmations, number
+                    ),
+                    None => format!("Waiting: pending in the mempool (nonce {})", report.nonce),
+                };
+                if line != progress {
+                    eprintln!("{}", line);
+                    progress = line;
+                }
+                last = Some((tx, report));
+            }
+            None => match last.as_mut() {
+                None => eprintln!("Waiting: transaction not seen by the node yet"),
+                // Mined since the last poll; the next one reports it
+                Some(_) if provider.get_transaction_receipt(hash).await?.is_some() => {}
+                Some((tx, report)) => {
+                    // Gone from both the chain and the mempool
+                    report.status = if nonce_used(&provider, tx).await? {
+                        TxStatus::Replaced
+                    } else {
+                        TxStatus::Dropped
+                    };
+                    report.block_number = None;
+                    report.block_hash = None;
+                    report.confirmations = 0;
+                    return finish(report, output);
+                }
+            },
+        }
+
+        sleep(Duration::from_secs(interval)).await;
+    }
+}
+
+fn finish(report: &TxReport, output: OutputFormat) -> Result<()> {
+    print_report(report, output)?;
+    match report.status {
+        TxStatus::Success | TxStatus::Mined => Ok(()),
+        TxStatus::Failed => bail!("Transaction {} failed", report.hash),
+        TxStatus::Dropped => bail!("Transaction {} was dropped", report.hash),
+        TxStatus::Replaced => bail!("Transaction {} was replaced, nonce {} already used", report.hash, report.nonce),
+        TxStatus::Pending => Ok(()),
+    }
+}
+
+fn print_report(report: &TxReport, output: OutputFormat) -> Result<()> {
+    if !output.is_table() {
+        return emit(output, report);
+    }
+
+    println!("{}", "Transaction".bright_blue().bold());
+    println!("{}", "=".repeat(50).bright_blue());
+
+    let mut rows = vec![
+        TxField { field: "Hash".to_string(), value: report.hash.clone() },
+        TxField { field: "Status".to_string(), value: report.status.label().to_string() },
+    ];
+    if let Some(number) = report.block_number {
+        rows.push(TxField { field: "Block".to_string(), value: number.to_string() });
+        rows.push(TxField { field: "Confirmations".to_string(), value: report.confirmations.to_string() });
+    }
+    rows.push(TxField { field: "From".to_string(), value: report.from.clone() });
+    rows.push(TxField {
+        field: "To".to_string(),
+        value: report.to.clone().unwrap_or_else(|| "(contract creation)".to_string()),
+    });
+    if let Some(contract) = &report.contract_created {
+        rows.push(TxField { field: "Contract Created".to_string(), value: contract.clone() });
+    }
+    rows.push(TxField { field: "Value".to_string(), value: format!("{} ETC", report.value_etc) });
+    rows.push(TxField { field: "Nonce".to_string(), value: report.nonce.to_string() });
+    rows.push(TxField {
+        field: "Gas Limit / Used".to_string(),
+        value: match (report.gas_used, report.gas_used_percent) {
+            (Some(used), Some(percent)) => format!("{} / {} ({:.2}%)", report.gas_limit, used, percent),
+            _ => format!("{} / -", report.gas_limit),
+        },
+    });
+    if let Some(price) = report.effective_gas_price_wei {
+        rows.push(TxField { field: "Gas Price".to_string(), value: format!("{:.2} gwei", price as f64 / 1e9) });
+    }
+    if let Some(fee) = &report.fee_etc {
+        rows.push(TxField { field: "Fee".to_string(), value: format!("{} ETC", fee) });
+    }
+    if let Some(logs) = report.logs {
+        rows.push(TxField { field: "Logs".to_string(), value: logs.to_string() });
+    }
+    rows.push(TxField {
+        field: "Input".to_string(),
+        value: match &report.method {
+            Some(method) => format!("{} bytes, selector {}", report.input_bytes, method),
+            None => format!("{} bytes", report.input_bytes),
+        },
+    });
+
+    println!("\n{}", Table::new(rows));
+
+    if let Some(call) = &report.decoded_input {
+        println!("\n{} {}", "Decoded input:".bright_yellow().bold(), call.signature.bright_cyan());
+        for param in &call.params {
+            let name = if param.name.is_empty() { "_" } else { &param.name };
+            println!("  {} {} = {}", param.kind.bright_black(), name, param.value);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,665 @@
+use anyhow::{anyhow, bail, Result};
+use clap::{Args, Subcommand, ValueEnum};
+use colored::*;
+use ethers::core::rand::thread_rng;
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, BlockNumber, Bytes, Transaction, TransactionRequest, U256};
+use ethers::utils::{format_ether, hex, parse_ether};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::terminal;
+use serde::Serialize;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use tabled::{Table, Tabled};
+
+use crate::output::{emit, OutputFormat};
+use crate::tx;
+
+// Read instead of prompting when set, for scripts and CI
+const PASSWORD_ENV: &str = "MORDOR_WALLET_PASSWORD";
+
+#[derive(Subcommand)]
+pub enum WalletCommands {
+    /// Create a new encrypted keystore
+    New {
+        /// Keystore name
+        name: String,
+    },
+
+    /// Encrypt an existing private key into a keystore
+    Import {
+        /// Keystore name
+        name: String,
+
+        /// Hex private key (prompted for when omitted)
+        #[arg(long)]
+        private_key: Option<String>,
+    },
+
+    /// List keystores with their addresses and balances
+    List,
+
+    /// Send ETC and/or calldata from a keystore
+    Send {
+        /// Keystore to send from
+        #[arg(long)]
+        from: String,
+
+        /// Recipient address
+        #[arg(long)]
+        to: Address,
+
+        /// Amount in ETC
+        #[arg(long, default_value = "0")]
+        value: String,
+
+        /// Hex calldata
+        #[arg(long)]
+        data: Option<Bytes>,
+
+        /// Nonce (defaults to the next pending nonce)
+        #[arg(long)]
+        nonce: Option<u64>,
+
+        #[command(flatten)]
+        options: SendOptions,
+    },
+
+    /// Resend a stuck transaction with a higher gas price
+    Replace {
+        /// Keystore that sent the stuck transaction
+        #[arg(long)]
+        from: String,
+
+        /// Nonce of the stuck transaction
+        #[arg(long)]
+        nonce: u64,
+
+        /// New recipient (defaults to the pending transaction's)
+        #[arg(long)]
+        to: Option<Address>,
+
+        /// New amount in ETC (defaults to the pending transaction's)
+        #[arg(long)]
+        value: Option<String>,
+
+        /// New hex calldata (defaults to the pending transaction's)
+        #[arg(long)]
+        data: Option<Bytes>,
+
+        #[command(flatten)]
+        options: SendOptions,
+    },
+
+    /// Cancel a stuck transaction with a zero-value transfer to self
+    Cancel {
+        /// Keystore that sent the stuck transaction
+        #[arg(long)]
+        from: String,
+
+        /// Nonce of the stuck transaction
+        #[arg(long)]
+        nonce: u64,
+
+        #[command(flatten)]
+        options: SendOptions,
+    },
+}
+
+#[derive(Args)]
+pub struct SendOptions {
+    /// Gas limit (estimated when omitted)
+    #[arg(long)]
+    gas_limit: Option<u64>,
+
+    /// Gas price in gwei (overrides --speed)
+    #[arg(long)]
+    gas_price: Option<f64>,
+
+    /// Gas estimator recommendation to use
+    #[arg(long, value_enum, default_value = "standard")]
+    speed: Speed,
+
+    /// Minimum gas price increase over a replaced transaction, in percent
+    #[arg(long, default_value = "10")]
+    bump: u64,
+
+    /// Gas estimator endpoint (defaults to the profile's)
+    #[arg(long)]
+    gas_estimator: Option<String>,
+
+    /// Wait for confirmations after sending
+    #[arg(short, long)]
+    wait: bool,
+
+    /// Confirmations to wait for
+    #[arg(short, long, default_value = "12")]
+    confirmations: u64,
+
+    /// Poll interval in seconds while waiting
+    #[arg(short, long, default_value = "5")]
+    interval: u64,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Speed {
+    Slow,
+    Standard,
+    Fast,
+    Instant,
+}
+
+pub struct WalletConfig {
+    pub rpc_url: String,
+    pub keystore: PathBuf,
+    pub password_file: Option<PathBuf>,
+    // None when the profile has no gas estimator; the node's price is used
+    pub gas_estimator: Option<String>,
+}
+
+#[derive(Serialize, Tabled)]
+struct WalletEntry {
+    name: String,
+    address: String,
+    #[tabled(rename = "balance (ETC)")]
+    balance_etc: String,
+}
+
+#[derive(Serialize)]
+struct SentTransaction {
+    hash: String,
+    from: String,
+    to: String,
+    nonce: u64,
+    value_etc: String,
+    gas_limit: u64,
+    gas_price_wei: u128,
+    // Hash of the pending transaction this one replaces, when known
+    replaces: Option<String>,
+}
+
+/// Keystores live in ~/.mordor/keystore unless --keystore says otherwise.
+pub fn default_keystore_dir() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    Path::new(&home).join(".mordor").join("keystore")
+}
+
+pub async fn run(config: WalletConfig, command: WalletCommands, output: OutputFormat) -> Result<()> {
+    match command {
+        WalletCommands::New { name } => create(&config, &name, None, output),
+        WalletCommands::Import { name, private_key } => {
+            let private_key = match private_key {
+                Some(key) => key,
+                None => read_secret("Private key: ")?,
+            };
+            create(&config, &name, Some(&private_key), output)
+        }
+        WalletCommands::List => list(&config, output).await,
+        WalletCommands::Send { from, to, value, data, nonce, options } => {
+            let provider = Provider::<Http>::try_from(config.rpc_url.as_str())?;
+            let wallet = load(&config, &from)?;
+
+            let mut request = TransactionRequest::new()
+                .to(to)
+                .value(parse_ether(&value)?)
+                .data(data.unwrap_or_default());
+            if let Some(nonce) = nonce {
+                request = request.nonce(nonce);
+            }
+            send(&config, &provider, wallet, request, None, &options, output).await
+        }
+        WalletCommands::Replace { from, nonce, to, value, data, options } => {
+            let provider = Provider::<Http>::try_from(config.rpc_url.as_str())?;
+            let wallet = load(&config, &from)?;
+            let pending = pending_transaction(&provider, wallet.address(), nonce).await;
+
+            let original = pending.as_ref();
+            let to = match to.or_else(|| original.and_then(|tx| tx.to)) {
+                Some(to) => to,
+                None => bail!(
+                    "No pending transaction with nonce {} found in the node's txpool, pass --to (and --value/--data)",
+                    nonce
+                ),
+            };
+            let value = match value {
+                Some(value) => parse_ether(&value)?,
+                None => original.map(|tx| tx.value).unwrap_or_default(),
+            };
+            let data = data.or_else(|| original.map(|tx| tx.input.clone())).unwrap_or_default();
+
+            let request = TransactionRequest::new().to(to).value(value).data(data).nonce(nonce);
+            send(&config, &provider, wallet, request, Some(pending), &options, output).await
+        }
+        WalletCommands::Cancel { from, nonce, options } => {
+            let provider = Provider::<Http>::try_from(config.rpc_url.as_str())?;
+            let wallet = load(&config, &from)?;
+            let pending = pending_transaction(&provider, wallet.address(), nonce).await;
+
+            let request = TransactionRequest::new().to(wallet.address()).value(0).gas(21_000).nonce(nonce);
+            send(&config, &provider, wallet, request, Some(pending), &options, output).await
+        }
+    }
+}
+
+fn keystore_path(config: &WalletConfig, name: &str) -> PathBuf {
+    config.keystore.join(format!("{}.json", name))
+}
+
+fn create(config: &WalletConfig, name: &str, private_key: Option<&str>, output: OutputFormat) -> Result<()> {
+    let path = keystore_path(config, name);
+    if path.exists() {
+        bail!("Keystore '{}' already exists at {}", name, path.display());
+    }
+    std::fs::create_dir_all(&config.keystore)?;
+
+    let password = new_password(config)?;
+    let file_name = format!("{}.json", name);
+    let mut rng = thread_rng();
+    let wallet = match private_key {
+        Some(key) => {
+            let key = hex::decode(key.trim().trim_start_matches("0x"))?;
+            LocalWallet::encrypt_keystore(&config.keystore, &mut rng, key, &password, Some(&file_name))?.0
+        }
+        None => LocalWallet::new_keystore(&config.keystore, &mut rng, &password, Some(&file_name))?.0,
+    };
+
+    // Record the address like geth does, so listing needs no password
+    let mut json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+    json["address"] = serde_json::Value::String(hex::encode(wallet.address()));
+    std::fs::write(&path, serde_json::to_string(&json)?)?;
+
+    let entry = WalletEntry {
+        name: name.to_string(),
+        address: format!("{:?}", wallet.address()),
+        balance_etc: "0".to_string(),
+    };
+    if !output.is_table() {
+        return emit(output, &entry);
+    }
+
+    println!("{} {}", "✓ Created keystore".bright_green().bold(), path.display());
+    println!("  Address: {}", entry.address.bright_cyan());
+    println!("  Fund it from a Mordor faucet before sending transactions.");
+
+    Ok(())
+}
+
+async fn list(config: &WalletConfig, output: OutputFormat) -> Result<()> {
+    let provider = Provider::<Http>::try_from(config.rpc_url.as_str())?;
+    let mut entries = Vec::new();
+
+    let mut paths: Vec<PathBuf> = match std::fs::read_dir(&config.keystore) {
+        Ok(dir) => dir.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+    paths.sort();
+
+    for path in paths {
+        let json: serde_json::Value = match std::fs::read_to_string(&path).map(|text| serde_json::from_str(&text)) {
+            Ok(Ok(json)) => json,
+            _ => continue,
+        };
+        let address = json["address"].as_str().and_then(|address| address.parse::<Address>().ok());
+
+        let balance = match address {
+            Some(address) => provider.get_balance(address, None).await.ok().map(format_ether),
+            None => None,
+        };
+
+        entries.push(WalletEntry {
+            name: path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+            address: address.map(|a| format!("{:?}", a)).unwrap_or_else(|| "-".to_string()),
+            balance_etc: balance.unwrap_or_else(|| "-".to_string()),
+        });
+    }
+
+    if !output.is_table() {
+        return emit(output, &entries);
+    }
+
+    if entries.is_empty() {
+        println!("No keystores in {}", config.keystore.display());
+        return Ok(());
+    }
+
+    println!("{}", format!("Keystores in {}", config.keystore.display()).bright_blue().bold());
+    println!("{}", Table::new(entries));
+
+    Ok(())
+}
+
+pub fn load(config: &WalletConfig, name: &str) -> Result<LocalWallet> {
+    let path = keystore_path(config, name);
+    if !path.exists() {
+        bail!("No keystore named '{}' in {}", name, config.keystore.display());
+    }
+
+    let password = password(config, &format!("Password for '{}': ", name))?;
+    LocalWallet::decrypt_keystore(&path, password).map_err(|e| anyhow!("Failed to unlock '{}': {}", name, e))
+}
+
+// Looks the stuck transaction up in the node's txpool; not every node
+// exposes txpool_content, so a miss is not an error
+async fn pending_transaction(provider: &Provider<Http>, from: Address, nonce: u64) -> Option<Transaction> {
+    let mut content = provider.txpool_content().await.ok()?;
+    let key = nonce.to_string();
+
+    content.pending.get_mut(&from).and_then(|txs| txs.remove(&key))
+        .or_else(|| content.queued.get_mut(&from).and_then(|txs| txs.remove(&key)))
+}
+
+async fn gas_price(config: &WalletConfig, provider: &Provider<Http>, options: &SendOptions) -> Result<U256> {
+    if let Some(gwei) = options.gas_price {
+        return Ok(U256::from((gwei * 1e9) as u128));
+    }
+
+    // Fall back to the node's own price when the estimator has no data or
+    // can't be reached; on stderr so JSON output stays parseable
+    let estimator = match options.gas_estimator.as_ref().or(config.gas_estimator.as_ref()) {
+        Some(estimator) => estimator,
+        None => return Ok(provider.get_gas_price().await?),
+    };
+    let recommended = match crate::fetch_gas_recommendation(estimator).await {
+        Ok(gas) => match options.speed {
+            Speed::Slow => gas.slow_wei,
+            Speed::Standard => gas.standard_wei,
+            Speed::Fast => gas.fast_wei,
+            Speed::Instant => gas.instant_wei,
+        },
+        Err(e) => {
+            eprintln!("{} Gas estimator unavailable ({}), using the node's gas price", "⚠".bright_yellow(), e);
+            0
+        }
+    };
+
+    match recommended {
+        0 => Ok(provider.get_gas_price().await?),
+        wei => Ok(U256::from(wei)),
+    }
+}
+
+// Signs and submits `request`; a nonce set on it is used as is, otherwise
+// the next pending nonce is taken
+async fn send(
+    config: &WalletConfig,
+    provider: &Provider<Http>,
+    wallet: LocalWallet,
+    mut request: TransactionRequest,
+    // Some(..) for replacements, holding the pending transaction if found
+    replacing: Option<Option<Transaction>>,
+    options: &SendOptions,
+    output: OutputFormat,
+) -> Result<()> {
+    let chain_id = provider.get_chainid().await?.as_u64();
+    let wallet = wallet.with_chain_id(chain_id);
+    let address = wallet.address();
+
+    let mined_nonce = provider.get_transaction_count(address, Some(BlockNumber::Latest.into())).await?.as_u64();
+    let nonce = match request.nonce.map(|nonce| nonce.as_u64()) {
+        Some(nonce) if replacing.is_some() && nonce < mined_nonce => {
+            bail!("Nonce {} is already mined, nothing to replace", nonce)
+        }
+        Some(nonce) => nonce,
+        None => provider.get_transaction_count(address, Some(BlockNumber::Pending.into())).await?.as_u64(),
+    };
+
+    let mut price = gas_price(config, provider, options).await?;
+    if let Some(replaced) = &replacing {
+        // Nodes only accept a replacement that pays noticeably more
+        let minimum = match replaced.as_ref().and_then(|tx| tx.gas_price) {
+            Some(old) => old * (100 + options.bump) / 100 + 1,
+            // Unknown original price: bump over the current price instead
+            None => price * (100 + options.bump) / 100,
+        };
+        price = price.max(minimum);
+    }
+    let replaced = replacing.flatten();
+
+    request = request.from(address).nonce(nonce).gas_price(price).chain_id(chain_id);
+    if let Some(gas) = options.gas_limit {
+        request = request.gas(gas);
+    } else if request.gas.is_none() {
+        let gas = match &replaced {
+            // Same call as the stuck transaction: keep its gas limit
+            Some(tx) if request.data.as_ref() == Some(&tx.input) => tx.gas,
+            _ => provider.estimate_gas(&request.clone().into(), None).await?,
+        };
+        request = request.gas(gas);
+    }
+
+    let gas = request.gas.unwrap_or_default();
+    let value = request.value.unwrap_or_default();
+    let cost = value + gas * price;
+    let balance = provider.get_balance(address, None).await?;
+    if balance < cost {
+        bail!(
+            "Insufficient balance: {:?} has {} ETC but needs {} ETC, fund it from a Mordor faucet first",
+            address,
+            format_ether(balance),
+            format_ether(cost)
+        );
+    }
+
+    let typed: TypedTransaction = request.clone().into();
+    let signature = wallet.sign_transaction(&typed).await?;
+    let pending = provider.send_raw_transaction(typed.rlp_signed(&signature)).await?;
+    let hash = pending.tx_hash();
+
+    let sent = SentTransaction {
+        hash: format!("{:?}", hash),
+        from: format!("{:?}", address),
+        to: request.to.as_ref().and_then(|to| to.as_address()).map(|to| format!("{:?}", to)).unwrap_or_default(),
+        nonce,
+        value_etc: format_ether(value),
+        gas_limit: gas.as_u64(),
+        gas_price_wei: price.as_u128(),
+        replaces: replaced.map(|tx| format!("{:?}", tx.hash)),
+    };
+
+    if options.wait {
+        if output.is_table() {
+            println!("{} {}", "✓ Sent".bright_green().bold(), sent.hash);
+        }
+        return tx::run(&config.rpc_url, hash, None, true, options.confirmations, options.interval, output).await;
+    }
+
+    if !output.is_table() {
+        return emit(output, &sent);
+    }
+
+    println!("{} {}", "✓ Sent".bright_green().bold(), sent.hash.bright_cyan());
+    println!("  From:      {}", sent.from);
+    println!("  To:        {}", sent.to);
+    println!("  Nonce:     {}", sent.nonce);
+    println!("  Value:     {} ETC", sent.value_etc);
+    println!("  Gas:       {} @ {:.2} gwei", sent.gas_limit, sent.gas_price_wei as f64 / 1e9);
+    if let Some(replaces) = &sent.replaces {
+        println!("  Replaces:  {}", replaces);
+    }
+
+    Ok(())
+}
+
+fn password(config: &WalletConfig, prompt: &str) -> Result<String> {
+    if let Some(path) = &config.password_file {
+        return Ok(std::fs::read_to_string(path)?.trim_end_matches(['\r', '\n']).to_string());
+    }
+    if let Ok(password) = std::env::var(PASSWORD_ENV) {
+        return Ok(password);
+    }
+
+    read_secret(prompt)
+}
+
+// Prompts twice when the password is typed in, so a typo cannot lock the key
+fn new_password(config: &WalletConfig) -> Result<String> {
+    if config.password_file.is_some() || std::env::var(PASSWORD_ENV).is_ok() {
+        return password(config, "");
+    }
+
+    let password = read_secret("New password: ")?;
+    if read_secret("Repeat password: ")? != password {
+        bail!("Passwords do not match");
+    }
+
+    Ok(password)
+}
+
+// Reads a line without echoing it when stdin is a terminal
+fn read_secret(prompt: &str) -> Result<String> {
+    eprint!("{}", prompt);
+    std::io::stderr().flush()?;
+
+    if !std::io::stdin().is_terminal() {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+    }
+
+    terminal::enable_raw_mode()?;
+    let mut secret = String::new();
+    let result = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Enter => break Ok(()),
+                KeyCode::Backspace => {
+                    secret.pop();
+                }
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Err(anyhow!("Interrupted"))
+                }
+                KeyCode::Char(c) => secret.push(c),
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(e) => break Err(e.into()),
+        }
+    };
+    terminal::disable_raw_mode()?;
+    eprintln!();
+
+    result.map(|_| secret)
+}
+
+// These need anvil (Foundry) on PATH: cargo test -- --ignored
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::utils::{Anvil, AnvilInstance};
+
+    const GWEI: u64 = 1_000_000_000;
+
+    // Without mining, sent transactions stay in the txpool to be replaced
+    fn node() -> (AnvilInstance, Provider<Http>, LocalWallet, WalletConfig) {
+        let anvil = Anvil::new().arg("--no-mining").spawn();
+        let provider = Provider::<Http>::try_from(anvil.endpoint()).unwrap();
+        let wallet = LocalWallet::from(anvil.keys()[0].clone());
+        let config = WalletConfig {
+            rpc_url: anvil.endpoint(),
+            keystore: std::env::temp_dir(),
+            password_file: None,
+            // Nothing listens here, so prices come from --gas-price or the node
+            gas_estimator: Some("http://127.0.0.1:1".to_string()),
+        };
+        (anvil, provider, wallet, config)
+    }
+
+    fn options(gas_price: Option<f64>) -> SendOptions {
+        SendOptions {
+            gas_limit: None,
+            gas_price,
+            speed: Speed::Standard,
+            bump: 10,
+            gas_estimator: None,
+            wait: false,
+            confirmations: 1,
+            interval: 1,
+        }
+    }
+
+    async fn pending(provider: &Provider<Http>, from: Address, nonce: u64) -> Transaction {
+        pending_transaction(provider, from, nonce).await.expect("transaction in the txpool")
+    }
+
+    #[tokio::test]
+    #[ignore = "needs anvil"]
+    async fn send_uses_next_nonce_and_given_price() {
+        let (_anvil, provider, wallet, config) = node();
+        let from = wallet.address();
+        let to = Address::repeat_byte(0x11);
+
+        let request = TransactionRequest::new().to(to).value(parse_ether("1").unwrap());
+        send(&config, &provider, wallet, request, None, &options(Some(2.0)), OutputFormat::Json).await.unwrap();
+
+        let tx = pending(&provider, from, 0).await;
+        assert_eq!(tx.to, Some(to));
+        assert_eq!(tx.value, parse_ether("1").unwrap());
+        assert_eq!(tx.gas, U256::from(21_000));
+        assert_eq!(tx.gas_price, Some(U256::from(2 * GWEI)));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs anvil"]
+    async fn replace_bumps_gas_price_over_pending() {
+        let (_anvil, provider, wallet, config) = node();
+        let from = wallet.address();
+        let to = Address::repeat_byte(0x11);
+
+        let request = TransactionRequest::new().to(to).value(1).nonce(0);
+        send(&config, &provider, wallet.clone(), request, None, &options(Some(2.0)), OutputFormat::Json).await.unwrap();
+        let original = pending(&provider, from, 0).await;
+
+        // Asking for the same price still has to pay the 10% bump
+        let request = TransactionRequest::new().to(to).value(2).nonce(0);
+        send(&config, &provider, wallet, request, Some(Some(original.clone())), &options(Some(2.0)), OutputFormat::Json)
+            .await
+            .unwrap();
+
+        let replacement = pending(&provider, from, 0).await;
+        assert_ne!(replacement.hash, original.hash);
+        assert_eq!(replacement.value, U256::from(2));
+        assert_eq!(replacement.gas_price, Some(U256::from(2 * GWEI * 110 / 100 + 1)));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs anvil"]
+    async fn cancel_sends_zero_value_to_self() {
+        let (_anvil, provider, wallet, config) = node();
+        let from = wallet.address();
+
+        let request = TransactionRequest::new().to(Address::repeat_byte(0x11)).value(1).nonce(0);
+        send(&config, &provider, wallet.clone(), request, None, &options(Some(2.0)), OutputFormat::Json).await.unwrap();
+        let original = pending(&provider, from, 0).await;
+
+        // Falls back to the node's price with the estimator unreachable
+        let request = TransactionRequest::new().to(from).value(0).gas(21_000).nonce(0);
+        send(&config, &provider, wallet, request, Some(Some(original.clone())), &options(None), OutputFormat::Json)
+            .await
+            .unwrap();
+
+        let cancel = pending(&provider, from, 0).await;
+        assert_eq!(cancel.to, Some(from));
+        assert_eq!(cancel.value, U256::zero());
+        assert!(cancel.gas_price.unwrap() > original.gas_price.unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs anvil"]
+    async fn replace_rejects_mined_nonce() {
+        let (_anvil, provider, wallet, config) = node();
+        let from = wallet.address();
+
+        let request = TransactionRequest::new().to(from).value(0).nonce(0);
+        send(&config, &provider, wallet.clone(), request, None, &options(Some(2.0)), OutputFormat::Json).await.unwrap();
+        provider.request::<_, String>("evm_mine", ()).await.unwrap();
+
+        let request = TransactionRequest::new().to(from).value(0).nonce(0);
+        let error = send(&config, &provider, wallet, request, Some(None), &options(Some(2.0)), OutputFormat::Json)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("already mined"), "{}", error);
+    }
+}
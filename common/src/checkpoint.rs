@@ -0,0 +1,138 @@
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fs::File;
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// A JSON state file, rewritten while the service runs and read back on
+/// start so a restart carries on from the last processed block.
+pub struct Checkpoint {
+    path: PathBuf,
+    // Held for the whole save so saves don't overlap; set while saving
+    // fails, so the warning is logged once
+    failing: Mutex<bool>,
+}
+
+impl Checkpoint {
+    /// From `STATE_FILE`; an empty value turns checkpointing off.
+    pub fn from_env(default: &str) -> Option<Self> {
+        let path = std::env::var("STATE_FILE").unwrap_or_else(|_| default.to_string());
+        if path.is_empty() {
+            return None;
+        }
+        Some(Self {
+            path: PathBuf::from(path),
+            failing: Mutex::new(false),
+        })
+    }
+
+    /// The saved state, or None to start fresh when there is none or it
+    /// can't be read. Only called on start, before anything is polled.
+    pub fn load<T: DeserializeOwned>(&self) -> Option<T> {
+        let text = match std::fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                info!("No checkpoint at {}, starting fresh", self.path.display());
+                return None;
+            }
+            Err(e) => {
+                warn!("Could not read checkpoint {}: {}", self.path.display(), e);
+                return None;
+            }
+        };
+
+        match serde_json::from_str(&text) {
+            Ok(state) => Some(state),
+            Err(e) => {
+                warn!("Ignoring unreadable checkpoint {}: {}", self.path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Writes the state `snapshot` returns. The snapshot is taken once the
+    /// previous save has finished, so saves land in the order they were
+    /// taken and an older state never replaces a newer one.
+    pub async fn save<T, F, Fut>(&self, snapshot: F)
+    where
+        T: Serialize + Send + 'static,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let mut failing = self.failing.lock().await;
+        let state = snapshot().await;
+
+        // Serializing and writing a large state would hold up a runtime thread
+        let path = self.path.clone();
+        let result = match tokio::task::spawn_blocking(move || write(&path, &state)).await {
+            Ok(result) => result,
+            Err(e) => Err(e.into()),
+        };
+        match result {
+            Ok(()) => *failing = false,
+            Err(e) => {
+                if !*failing {
+                    warn!("Could not save checkpoint: {:#}", e);
+                }
+                *failing = true;
+            }
+        }
+    }
+}
+
+// Written to a temporary file, synced and renamed, so a crash or power loss
+// mid-write leaves the previous checkpoint intact
+fn write<T: Serialize>(path: &Path, state: &T) -> Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).with_context(|| format!("Creating {}", dir.display()))?;
+    }
+    let temp = path.with_extension("tmp");
+    let mut file = File::create(&temp).with_context(|| format!("Creating {}", temp.display()))?;
+    file.write_all(&serde_json::to_vec(state)?)
+        .and_then(|()| file.sync_all())
+        .with_context(|| format!("Writing {}", temp.display()))?;
+    std::fs::rename(&temp, path).with_context(|| format!("Replacing {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint(name: &str) -> Checkpoint {
+        let dir = std::env::temp_dir().join(format!("mordor-checkpoint-{}", std::process::id()));
+        Checkpoint {
+            path: dir.join(name),
+            failing: Mutex::new(false),
+        }
+    }
+
+    #[tokio::test]
+    async fn saved_state_loads_back() {
+        let checkpoint = checkpoint("roundtrip.json");
+        assert_eq!(checkpoint.load::<Vec<u64>>(), None);
+
+        checkpoint.save(|| async { vec![1u64, 2, 3] }).await;
+        checkpoint.save(|| async { vec![4u64] }).await;
+
+        assert_eq!(checkpoint.load::<Vec<u64>>(), Some(vec![4]));
+        assert!(!checkpoint.path.with_extension("tmp").exists());
+        std::fs::remove_file(&checkpoint.path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_save_keeps_running() {
+        // A directory where the file should be makes the rename fail
+        let checkpoint = checkpoint("occupied");
+        std::fs::create_dir_all(checkpoint.path.join("child")).unwrap();
+
+        checkpoint.save(|| async { 1u64 }).await;
+        assert!(*checkpoint.failing.lock().await);
+        assert_eq!(checkpoint.load::<u64>(), None);
+        std::fs::remove_dir_all(&checkpoint.path).unwrap();
+        std::fs::remove_file(checkpoint.path.with_extension("tmp")).unwrap();
+    }
+}
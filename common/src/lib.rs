@@ -0,0 +1,7 @@
+//! Plumbing shared by the fork monitor, gas estimator and indexer.
+
+pub mod checkpoint;
+#[cfg(feature = "otlp")]
+mod otlp;
+pub mod shutdown;
+pub mod telemetry;
@@ -0,0 +1,353 @@
+use anyhow::Result;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::{InstrumentationScope, KeyValue};
+use opentelemetry_otlp::{MetricExporter, Protocol, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::metrics::data::{
+    DataPoint, Gauge, Histogram, HistogramDataPoint, Metric, ResourceMetrics, ScopeMetrics, Sum,
+};
+use opentelemetry_sdk::metrics::exporter::PushMetricExporter;
+use opentelemetry_sdk::metrics::Temporality;
+use opentelemetry_sdk::runtime::Tokio;
+use opentelemetry_sdk::trace::{BatchConfigBuilder, BatchSpanProcessor, TracerProvider};
+use opentelemetry_sdk::Resource;
+use prometheus::proto::{MetricFamily, MetricType};
+use prometheus::Registry;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::{Layer, Registry as Spans};
+
+// Finished spans kept while the collector is unreachable
+const MAX_PENDING_SPANS: usize = 10_000;
+
+/// OTLP/HTTP export with JSON encoding. Spans go through the SDK's batch
+/// processor; the Prometheus registry is converted and pushed on the same
+/// interval.
+pub struct Exporter {
+    pub endpoint: String,
+    pub interval: Duration,
+    tracer_provider: TracerProvider,
+    metrics: Arc<MetricsPush>,
+    metrics_task: Mutex<Option<JoinHandle<()>>>,
+}
+
+struct MetricsPush {
+    exporter: MetricExporter,
+    resource: Resource,
+    scope: InstrumentationScope,
+    started: SystemTime,
+    registry: Mutex<Option<Registry>>,
+    // Set while pushing fails, so the warning is logged once
+    failing: AtomicBool,
+}
+
+impl Exporter {
+    /// None unless `OTEL_EXPORTER_OTLP_ENDPOINT` is set. The exporters read
+    /// the endpoint themselves and append `/v1/traces` and `/v1/metrics`.
+    pub fn from_env(service: &str) -> Result<Option<Self>> {
+        let endpoint = match std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+            Ok(endpoint) if !endpoint.is_empty() => endpoint,
+            _ => return Ok(None),
+        };
+        let interval = std::env::var("OTEL_EXPORT_INTERVAL_SECS")
+            .unwrap_or_else(|_| "15".to_string())
+            .parse::<u64>()?;
+        let interval = Duration::from_secs(interval.max(1));
+        let service = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| service.to_string());
+        let resource = Resource::new([KeyValue::new("service.name", service.clone())]);
+
+        let spans = SpanExporter::builder()
+            .with_http()
+            .with_protocol(Protocol::HttpJson)
+            .build()?;
+        let batch = BatchConfigBuilder::default()
+            .with_scheduled_delay(interval)
+            .with_max_queue_size(MAX_PENDING_SPANS)
+            .build();
+        let tracer_provider = TracerProvider::builder()
+            .with_span_processor(BatchSpanProcessor::builder(spans, Tokio).with_batch_config(batch).build())
+            .with_resource(resource.clone())
+            .build();
+
+        let metrics = MetricExporter::builder()
+            .with_http()
+            .with_protocol(Protocol::HttpJson)
+            .with_temporality(Temporality::Cumulative)
+            .build()?;
+
+        Ok(Some(Self {
+            endpoint,
+            interval,
+            tracer_provider,
+            metrics: Arc::new(MetricsPush {
+                exporter: metrics,
+                resource,
+                scope: InstrumentationScope::builder(service).build(),
+                started: SystemTime::now(),
+                registry: Mutex::new(None),
+                failing: AtomicBool::new(false),
+            }),
+            metrics_task: Mutex::new(None),
+        }))
+    }
+
+    /// Spans are exported at info regardless of how verbose the logs are.
+    pub fn layer(&self) -> Box<dyn Layer<Spans> + Send + Sync> {
+        let tracer = self.tracer_provider.tracer(self.metrics.scope.name().to_string());
+        tracing_opentelemetry::layer()
+            .with_tracer(tracer)
+            .with_filter(LevelFilter::INFO)
+            .boxed()
+    }
+
+    pub fn export_metrics(&self, registry: Registry) {
+        *self.metrics.registry.lock().unwrap() = Some(registry);
+
+        let metrics = self.metrics.clone();
+        let interval = self.interval;
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                metrics.push().await;
+            }
+        });
+        if let Some(previous) = self.metrics_task.lock().unwrap().replace(task) {
+            previous.abort();
+        }
+    }
+
+    pub async fn shutdown(self) {
+        if let Some(task) = self.metrics_task.lock().unwrap().take() {
+            task.abort();
+        }
+        self.metrics.push().await;
+        if let Err(e) = self.metrics.exporter.shutdown() {
+            warn!("OTLP metrics exporter did not shut down cleanly: {}", e);
+        }
+
+        // Blocks until the batch processor has sent what it still holds
+        let tracer_provider = self.tracer_provider;
+        match tokio::task::spawn_blocking(move || tracer_provider.shutdown()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("Could not flush OTLP traces: {}", e),
+            Err(e) => warn!("Could not flush OTLP traces: {}", e),
+        }
+    }
+}
+
+impl MetricsPush {
+    async fn push(&self) {
+        let Some(registry) = self.registry.lock().unwrap().clone() else {
+            return;
+        };
+        let now = SystemTime::now();
+        let mut metrics = ResourceMetrics {
+            resource: self.resource.clone(),
+            scope_metrics: vec![ScopeMetrics {
+                scope: self.scope.clone(),
+                metrics: registry
+                    .gather()
+                    .iter()
+                    .filter_map(|family| otlp_metric(family, self.started, now))
+                    .collect(),
+            }],
+        };
+
+        // Logs only when the collector goes away or comes back
+        match self.exporter.export(&mut metrics).await {
+            Err(e) => {
+                if !self.failing.swap(true, Ordering::Relaxed) {
+                    warn!("OTLP metrics export failed: {}", e);
+                }
+            }
+            Ok(()) => {
+                if self.failing.swap(false, Ordering::Relaxed) {
+                    info!("OTLP metrics export recovered");
+                }
+            }
+        }
+    }
+}
+
+// Gauges and counters map onto OTLP gauges and cumulative sums; histograms
+// onto explicit bucket histograms. Nothing here registers summaries, which
+// the SDK's data model has no type for.
+fn otlp_metric(family: &MetricFamily, started: SystemTime, now: SystemTime) -> Option<Metric> {
+    let labels = |metric: &prometheus::proto::Metric| -> Vec<KeyValue> {
+        metric
+            .get_label()
+            .iter()
+            .map(|label| KeyValue::new(label.get_name().to_string(), label.get_value().to_string()))
+            .collect()
+    };
+    let point = |metric: &prometheus::proto::Metric, start_time, value| DataPoint {
+        attributes: labels(metric),
+        start_time,
+        time: Some(now),
+        value,
+        exemplars: Vec::new(),
+    };
+
+    let data: Box<dyn opentelemetry_sdk::metrics::data::Aggregation> = match family.get_field_type() {
+        MetricType::GAUGE => Box::new(Gauge {
+            data_points: family
+                .get_metric()
+                .iter()
+                .map(|m| point(m, None, m.get_gauge().get_value()))
+                .collect(),
+        }),
+        MetricType::UNTYPED => Box::new(Gauge {
+            data_points: family
+                .get_metric()
+                .iter()
+                .map(|m| point(m, None, m.get_untyped().get_value()))
+                .collect(),
+        }),
+        MetricType::COUNTER => Box::new(Sum {
+            data_points: family
+                .get_metric()
+                .iter()
+                .map(|m| point(m, Some(started), m.get_counter().get_value()))
+                .collect(),
+            temporality: Temporality::Cumulative,
+            is_monotonic: true,
+        }),
+        MetricType::HISTOGRAM => Box::new(Histogram {
+            data_points: family
+                .get_metric()
+                .iter()
+                .map(|m| {
+                    let histogram = m.get_histogram();
+                    let (bounds, bucket_counts) = buckets(histogram);
+                    HistogramDataPoint {
+                        attributes: labels(m),
+                        start_time: started,
+                        time: now,
+                        count: histogram.get_sample_count(),
+                        bounds,
+                        bucket_counts,
+                        min: None,
+                        max: None,
+                        sum: histogram.get_sample_sum(),
+                        exemplars: Vec::new(),
+                    }
+                })
+                .collect(),
+            temporality: Temporality::Cumulative,
+        }),
+        MetricType::SUMMARY => return None,
+    };
+
+    Some(Metric {
+        name: family.get_name().to_string().into(),
+        description: family.get_help().to_string().into(),
+        unit: "".into(),
+        data,
+    })
+}
+
+// Prometheus buckets count every sample up to their bound, OTLP buckets only
+// those above the previous bound. The +Inf bucket, when present, becomes the
+// overflow bucket after the last bound.
+fn buckets(histogram: &prometheus::proto::Histogram) -> (Vec<f64>, Vec<u64>) {
+    let mut bounds = Vec::new();
+    let mut counts = Vec::new();
+    let mut previous = 0;
+    for bucket in histogram.get_bucket() {
+        if bucket.get_upper_bound().is_infinite() {
+            continue;
+        }
+        bounds.push(bucket.get_upper_bound());
+        counts.push(bucket.get_cumulative_count().saturating_sub(previous));
+        previous = bucket.get_cumulative_count();
+    }
+    counts.push(histogram.get_sample_count().saturating_sub(previous));
+    (bounds, counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::{Counter, HistogramOpts, HistogramVec, Opts};
+
+    fn histogram_points(metric: &Metric) -> &[HistogramDataPoint<f64>] {
+        &metric.data.as_any().downcast_ref::<Histogram<f64>>().unwrap().data_points
+    }
+
+    #[test]
+    fn histogram_buckets_are_decumulated() {
+        let registry = Registry::new();
+        let histogram = HistogramVec::new(
+            HistogramOpts::new("rpc_seconds", "RPC latency").buckets(vec![0.1, 0.5, 1.0]),
+            &["method"],
+        )
+        .unwrap();
+        registry.register(Box::new(histogram.clone())).unwrap();
+        for value in [0.05, 0.2, 0.3, 0.7, 2.0, 5.0] {
+            histogram.with_label_values(&["eth_getBlockByNumber"]).observe(value);
+        }
+
+        let families = registry.gather();
+        let now = SystemTime::now();
+        let metric = otlp_metric(&families[0], now, now).unwrap();
+        assert_eq!(metric.name, "rpc_seconds");
+
+        let points = histogram_points(&metric);
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].attributes, vec![KeyValue::new("method", "eth_getBlockByNumber")]);
+        assert_eq!(points[0].bounds, vec![0.1, 0.5, 1.0]);
+        // Cumulative 1, 3, 4 of 6 samples
+        assert_eq!(points[0].bucket_counts, vec![1, 2, 1, 2]);
+        assert_eq!(points[0].count, 6);
+        assert_eq!(points[0].bucket_counts.iter().sum::<u64>(), points[0].count);
+        assert!((points[0].sum - 8.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn explicit_inf_bucket_is_not_counted_twice() {
+        let mut histogram = prometheus::proto::Histogram::default();
+        for (bound, cumulative) in [(1.0, 2), (2.0, 2), (f64::INFINITY, 5)] {
+            let mut bucket = prometheus::proto::Bucket::default();
+            bucket.set_upper_bound(bound);
+            bucket.set_cumulative_count(cumulative);
+            histogram.mut_bucket().push(bucket);
+        }
+        histogram.set_sample_count(5);
+
+        assert_eq!(buckets(&histogram), (vec![1.0, 2.0], vec![2, 0, 3]));
+    }
+
+    #[test]
+    fn empty_histogram_has_zero_counts() {
+        let mut histogram = prometheus::proto::Histogram::default();
+        for bound in [1.0, 2.0] {
+            let mut bucket = prometheus::proto::Bucket::default();
+            bucket.set_upper_bound(bound);
+            histogram.mut_bucket().push(bucket);
+        }
+
+        assert_eq!(buckets(&histogram), (vec![1.0, 2.0], vec![0, 0, 0]));
+    }
+
+    #[test]
+    fn counters_become_cumulative_sums() {
+        let registry = Registry::new();
+        let counter = Counter::with_opts(Opts::new("reorgs_total", "Reorgs seen")).unwrap();
+        registry.register(Box::new(counter.clone())).unwrap();
+        counter.inc_by(3.0);
+
+        let families = registry.gather();
+        let started = SystemTime::UNIX_EPOCH;
+        let metric = otlp_metric(&families[0], started, SystemTime::now()).unwrap();
+        let sum = metric.data.as_any().downcast_ref::<Sum<f64>>().unwrap();
+
+        assert!(sum.is_monotonic);
+        assert_eq!(sum.temporality, Temporality::Cumulative);
+        assert_eq!(sum.data_points[0].value, 3.0);
+        assert_eq!(sum.data_points[0].start_time, Some(started));
+    }
+}
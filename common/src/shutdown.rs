@@ -0,0 +1,37 @@
+use anyhow::Result;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+use tokio::time::Duration;
+use tracing::{error, info};
+
+/// Set once SIGTERM or Ctrl-C arrives. Poll loops check it between polls,
+/// so a poll in progress always completes.
+#[derive(Clone)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    /// Listens for SIGTERM and Ctrl-C. Once signalled, the process has
+    /// `timeout` to wind down before it exits anyway.
+    pub fn listen(timeout: Duration) -> Result<Self> {
+        let mut terminate = signal(SignalKind::terminate())?;
+        let (sender, receiver) = watch::channel(false);
+
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = terminate.recv() => info!("Received SIGTERM, shutting down"),
+                _ = tokio::signal::ctrl_c() => info!("Received Ctrl-C, shutting down"),
+            }
+            let _ = sender.send(true);
+
+            tokio::time::sleep(timeout).await;
+            error!("Shutdown did not finish within {}s, exiting", timeout.as_secs());
+            std::process::exit(1);
+        });
+
+        Ok(Self(receiver))
+    }
+
+    pub async fn requested(mut self) {
+        let _ = self.0.wait_for(|stop| *stop).await;
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::{bail, Context as _, Result};
+use async_trait::async_trait;
+use ethers::providers::{Http, HttpClientError, JsonRpcClient};
+use prometheus::Registry;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::Debug;
+use tracing::{field, info_span, Instrument};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, Layer, Registry as Spans};
+
+#[cfg(feature = "otlp")]
+use crate::otlp::Exporter;
+
+/// Logging and optional OTLP export, configured from the environment:
+///
+/// - `RUST_LOG`: level directives, e.g. `info,fork_monitor::peers=debug`
+/// - `LOG_FORMAT`: `text` (default) or `json`
+/// - `OTEL_EXPORTER_OTLP_ENDPOINT`: OTLP/HTTP collector, e.g.
+///   `http://otel-collector:4318`; nothing is exported when unset
+/// - `OTEL_SERVICE_NAME`: defaults to the binary name
+/// - `OTEL_EXPORT_INTERVAL_SECS`: how often spans and metrics are pushed
+///
+/// Export needs the `otlp` feature, which is on by default.
+pub struct Telemetry {
+    #[cfg(feature = "otlp")]
+    exporter: Option<Exporter>,
+}
+
+#[cfg_attr(not(feature = "otlp"), allow(unused_variables))]
+pub fn init(service: &str) -> Result<Telemetry> {
+    let directives = std::env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string());
+    let targets: Targets = directives
+        .parse()
+        .with_context(|| format!("Invalid RUST_LOG '{}'", directives))?;
+
+    let json = match std::env::var("LOG_FORMAT").unwrap_or_default().as_str() {
+        "" | "text" => false,
+        "json" => true,
+        other => bail!("Invalid LOG_FORMAT '{}', expected text or json", other),
+    };
+
+    let mut layers: Vec<Box<dyn Layer<Spans> + Send + Sync>> = Vec::new();
+    if json {
+        // The span list already ends with the current span
+        layers.push(fmt::layer().json().with_current_span(false).with_filter(targets).boxed());
+    } else {
+        layers.push(fmt::layer().with_filter(targets).boxed());
+    }
+
+    #[cfg(feature = "otlp")]
+    let exporter = Exporter::from_env(service)?;
+    #[cfg(feature = "otlp")]
+    if let Some(exporter) = &exporter {
+        layers.push(exporter.layer());
+    }
+
+    tracing_subscriber::registry().with(layers).try_init()?;
+
+    #[cfg(feature = "otlp")]
+    if let Some(exporter) = &exporter {
+        tracing::info!(
+            "Exporting OTLP traces and metrics to {} every {}s",
+            exporter.endpoint,
+            exporter.interval.as_secs()
+        );
+    }
+    #[cfg(not(feature = "otlp"))]
+    if std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").is_ok_and(|endpoint| !endpoint.is_empty()) {
+        tracing::warn!("Built without the otlp feature, ignoring OTEL_EXPORTER_OTLP_ENDPOINT");
+    }
+
+    Ok(Telemetry {
+        #[cfg(feature = "otlp")]
+        exporter,
+    })
+}
+
+impl Telemetry {
+    /// Pushes the Prometheus registry to the collector as OTLP metrics.
+    #[cfg_attr(not(feature = "otlp"), allow(unused_variables))]
+    pub fn export_metrics(&self, registry: Registry) {
+        #[cfg(feature = "otlp")]
+        if let Some(exporter) = &self.exporter {
+            exporter.export_metrics(registry);
+        }
+    }
+
+    /// Sends the spans still queued and a last set of metrics. Called once
+    /// everything else has stopped, so nothing is lost on exit.
+    pub async fn shutdown(self) {
+        #[cfg(feature = "otlp")]
+        if let Some(exporter) = self.exporter {
+            exporter.shutdown().await;
+        }
+    }
+}
+
+/// HTTP transport that opens a client span for every JSON-RPC call.
+#[derive(Clone, Debug)]
+pub struct TracedHttp(Http);
+
+impl TracedHttp {
+    pub fn new(url: &str) -> Result<Self> {
+        Ok(Self(url.parse().with_context(|| format!("Invalid RPC URL '{}'", url))?))
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for TracedHttp {
+    type Error = HttpClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let span = info_span!(
+            "rpc",
+            otel.kind = "client",
+            rpc.method = method,
+            otel.status_message = field::Empty
+        );
+        let result = self.0.request(method, params).instrument(span.clone()).await;
+        // Marks the exported span as failed
+        if let Err(e) = &result {
+            span.record("otel.status_message", field::display(e));
+        }
+        result
+    }
+}
@@ -0,0 +1,413 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// ETC's difficulty adjustment aims for this; blocks arrive as a Poisson
+// process around it
+const TARGET_BLOCK_TIME: f64 = 13.0;
+
+// Blocks in the short and long hashrate windows (~4 minutes and ~1 hour)
+const SHORT_WINDOW: usize = 20;
+const LONG_WINDOW: usize = 240;
+
+// A gap this unlikely under the Poisson expectation is a drought
+// (1e-6 is about three minutes at 13s)
+const DROUGHT_P_VALUE: f64 = 1e-6;
+
+// Short-window hashrate at half or double the long window is a drop or spike
+const HASHRATE_RATIO: f64 = 2.0;
+
+// Exponential block times have a coefficient of variation of 1. Far lower
+// over the short window means timestamps are being chosen, not observed.
+const MIN_BLOCK_TIME_CV: f64 = 0.25;
+
+// Anomaly events kept for the /anomalies API
+const MAX_ANOMALY_EVENTS: usize = 1_000;
+
+#[derive(Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    Drought,
+    HashrateDrop,
+    HashrateSpike,
+    TimestampPattern,
+}
+
+impl AnomalyKind {
+    pub const ALL: [AnomalyKind; 4] = [
+        AnomalyKind::Drought,
+        AnomalyKind::HashrateDrop,
+        AnomalyKind::HashrateSpike,
+        AnomalyKind::TimestampPattern,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnomalyKind::Drought => "drought",
+            AnomalyKind::HashrateDrop => "hashrate_drop",
+            AnomalyKind::HashrateSpike => "hashrate_spike",
+            AnomalyKind::TimestampPattern => "timestamp_pattern",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AnomalyEvent {
+    pub detected_at: u64,
+    pub block: u64,
+    pub kind: AnomalyKind,
+    pub score: f64,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct AnomalyReport {
+    pub scores: BTreeMap<AnomalyKind, f64>,
+    pub hashrate_short: Option<f64>,
+    pub hashrate_long: Option<f64>,
+    pub events: Vec<AnomalyEvent>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Sample {
+    number: u64,
+    timestamp: u64,
+    difficulty: f64,
+    // Wall-clock time the block was first processed
+    seen_at: u64,
+}
+
+/// Scores block times, difficulty and the hashrate they imply against what
+/// an honest chain at ETC's target would produce. Each score is scaled so
+/// that 1.0 is the alerting threshold; an event is recorded when a score
+/// crosses it and not again until it has dropped back below.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AnomalyDetector {
+    samples: VecDeque<Sample>,
+    scores: BTreeMap<AnomalyKind, f64>,
+    events: VecDeque<AnomalyEvent>,
+}
+
+impl Default for AnomalyDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnomalyDetector {
+    pub fn new() -> Self {
+        Self {
+            samples: VecDeque::new(),
+            scores: AnomalyKind::ALL.iter().map(|kind| (*kind, 0.0)).collect(),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Scores a new block; returns the anomalies it started.
+    pub fn add_block(&mut self, number: u64, timestamp: u64, difficulty: f64) -> Vec<AnomalyEvent> {
+        // The tip is processed again on every poll until a new block arrives
+        if self
+            .samples
+            .back()
+            .is_some_and(|s| s.number == number && s.timestamp == timestamp && s.difficulty == difficulty)
+        {
+            return Vec::new();
+        }
+
+        // A block at an already seen height replaces the old branch
+        while self.samples.back().is_some_and(|s| s.number >= number) {
+            self.samples.pop_back();
+        }
+        self.samples.push_back(Sample { number, timestamp, difficulty, seen_at: unix_now() });
+        while self.samples.len() > LONG_WINDOW + 1 {
+            self.samples.pop_front();
+        }
+
+        let mut started = Vec::new();
+
+        // Only the gap from a directly preceding block is meaningful
+        let gap = match self.samples.iter().rev().nth(1) {
+            Some(parent) if parent.number + 1 == number => timestamp.saturating_sub(parent.timestamp),
+            _ => 0,
+        };
+        started.extend(self.score_drought(number, gap));
+
+        let (short, long) = (self.hashrate(SHORT_WINDOW), self.hashrate(LONG_WINDOW));
+        let ratio = match (short, long) {
+            // The long window must reach well past the short one
+            (Some(short), Some(long)) if long > 0.0 && self.samples.len() > 2 * SHORT_WINDOW => Some(short / long),
+            _ => None,
+        };
+        let log_ratio = ratio.map(|r| r.log(HASHRATE_RATIO)).unwrap_or(0.0);
+        started.extend(self.update(
+            AnomalyKind::HashrateDrop,
+            number,
+            if log_ratio < 0.0 { -log_ratio } else { 0.0 },
+            || format!("Hashrate over the last {} blocks is {:.0}% of the last hour's", SHORT_WINDOW, ratio.unwrap_or(0.0) * 100.0),
+        ));
+        started.extend(self.update(
+            AnomalyKind::HashrateSpike,
+            number,
+            if log_ratio > 0.0 { log_ratio } else { 0.0 },
+            || format!("Hashrate over the last {} blocks is {:.1}x the last hour's", SHORT_WINDOW, ratio.unwrap_or(0.0)),
+        ));
+
+        let cv = self.block_time_cv();
+        // Capped so identical timestamps still give a finite score
+        let pattern_score = cv.map(|cv| MIN_BLOCK_TIME_CV / cv.max(0.01)).unwrap_or(0.0);
+        started.extend(self.update(AnomalyKind::TimestampPattern, number, pattern_score, || {
+            format!(
+                "Block times over the last {} blocks vary too little to be random (CV {:.2}, expected ~1)",
+                SHORT_WINDOW,
+                cv.unwrap_or(0.0)
+            )
+        }));
+
+        started
+    }
+
+    /// Scores the time since the tip against the Poisson expectation, so a
+    /// drought is flagged while it is still going on. Time is counted from
+    /// the tip's timestamp, but never from before it was seen, so a tip that
+    /// was old on arrival (catching up, skewed clocks) is not a drought.
+    pub fn check_drought(&mut self) -> Vec<AnomalyEvent> {
+        let Some(tip) = self.samples.back() else {
+            return Vec::new();
+        };
+        let now = unix_now();
+        let elapsed = now.saturating_sub(tip.timestamp).min(now.saturating_sub(tip.seen_at));
+        let number = tip.number;
+
+        // Never lower the score the tip's own gap set
+        if Self::drought_score(elapsed) <= self.scores[&AnomalyKind::Drought] {
+            return Vec::new();
+        }
+        self.score_drought(number, elapsed).into_iter().collect()
+    }
+
+    pub fn scores(&self) -> &BTreeMap<AnomalyKind, f64> {
+        &self.scores
+    }
+
+    pub fn report(&self) -> AnomalyReport {
+        AnomalyReport {
+            scores: self.scores.clone(),
+            hashrate_short: self.hashrate_short(),
+            hashrate_long: self.hashrate_long(),
+            events: self.events.iter().cloned().collect(),
+        }
+    }
+
+    /// Estimated hashes per second over the last ~4 minutes.
+    pub fn hashrate_short(&self) -> Option<f64> {
+        self.hashrate(SHORT_WINDOW)
+    }
+
+    /// Estimated hashes per second over the last ~hour.
+    pub fn hashrate_long(&self) -> Option<f64> {
+        self.hashrate(LONG_WINDOW)
+    }
+
+    // The work the last `blocks` blocks represent over the time they took
+    fn hashrate(&self, blocks: usize) -> Option<f64> {
+        if self.samples.len() < 2 {
+            return None;
+        }
+        let window: Vec<&Sample> = self.samples.iter().rev().take(blocks + 1).collect();
+        let (newest, oldest) = (window.first()?, window.last()?);
+        let elapsed = newest.timestamp.checked_sub(oldest.timestamp).filter(|e| *e > 0)?;
+        let work: f64 = window[..window.len() - 1].iter().map(|s| s.difficulty).sum();
+        Some(work / elapsed as f64)
+    }
+
+    // -log10 of the chance of waiting this long, relative to the threshold
+    fn drought_score(gap: u64) -> f64 {
+        let p_log10 = -(gap as f64) / TARGET_BLOCK_TIME / std::f64::consts::LN_10;
+        p_log10 / DROUGHT_P_VALUE.log10()
+    }
+
+    fn score_drought(&mut self, number: u64, gap: u64) -> Option<AnomalyEvent> {
+        self.update(AnomalyKind::Drought, number, Self::drought_score(gap), || {
+            format!(
+                "{}s without a block after {}, expected {}s on average",
+                gap, number, TARGET_BLOCK_TIME
+            )
+        })
+    }
+
+    // Coefficient of variation of the short window's block times
+    fn block_time_cv(&self) -> Option<f64> {
+        if self.samples.len() <= SHORT_WINDOW {
+            return None;
+        }
+        let window: Vec<&Sample> = self.samples.iter().rev().take(SHORT_WINDOW + 1).collect();
+        let gaps: Vec<f64> = window
+            .windows(2)
+            .map(|pair| pair[0].timestamp.saturating_sub(pair[1].timestamp) as f64)
+            .collect();
+
+        let mean = gaps.iter().sum::<f64>() / gaps.len() as f64;
+        if mean <= 0.0 {
+            return Some(0.0);
+        }
+        let variance = gaps.iter().map(|g| (g - mean).powi(2)).sum::<f64>() / gaps.len() as f64;
+        Some(variance.sqrt() / mean)
+    }
+
+    fn update(
+        &mut self,
+        kind: AnomalyKind,
+        block: u64,
+        score: f64,
+        message: impl FnOnce() -> String,
+    ) -> Option<AnomalyEvent> {
+        let previous = self.scores.insert(kind, score).unwrap_or(0.0);
+        if score < 1.0 || previous >= 1.0 {
+            return None;
+        }
+
+        let event = AnomalyEvent {
+            detected_at: unix_now(),
+            block,
+            kind,
+            score,
+            message: message(),
+        };
+        self.events.push_back(event.clone());
+        if self.events.len() > MAX_ANOMALY_EVENTS {
+            self.events.pop_front();
+        }
+        Some(event)
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Adds the `from` block and one more per gap, returning every anomaly
+    // started
+    fn feed(detector: &mut AnomalyDetector, from: (u64, u64), gaps: &[u64], difficulty: f64) -> Vec<AnomalyEvent> {
+        let (mut number, mut timestamp) = from;
+        let mut started = detector.add_block(number, timestamp, difficulty);
+        for gap in gaps {
+            number += 1;
+            timestamp += gap;
+            started.extend(detector.add_block(number, timestamp, difficulty));
+        }
+        started
+    }
+
+    fn kinds(events: &[AnomalyEvent]) -> Vec<AnomalyKind> {
+        events.iter().map(|event| event.kind).collect()
+    }
+
+    // Alternating 5s/21s gaps: 13s mean, CV 8/13, so no timestamp pattern
+    fn honest(blocks: usize) -> Vec<u64> {
+        (0..blocks).map(|i| if i % 2 == 0 { 5 } else { 21 }).collect()
+    }
+
+    #[test]
+    fn drought_threshold_is_a_one_in_a_million_gap() {
+        // P(gap > t) = exp(-t / 13) = 1e-6 at t = 13 ln(1e6) ~ 179.6s
+        assert_eq!(AnomalyDetector::drought_score(0), 0.0);
+        assert!((AnomalyDetector::drought_score(90) - 0.5011).abs() < 1e-3);
+        assert!(AnomalyDetector::drought_score(179) < 1.0);
+        assert!(AnomalyDetector::drought_score(180) > 1.0);
+    }
+
+    #[test]
+    fn drought_is_only_scored_between_consecutive_blocks() {
+        let mut detector = AnomalyDetector::new();
+        detector.add_block(100, 1_000, 1.0);
+
+        let started = detector.add_block(101, 1_180, 1.0);
+        assert_eq!(kinds(&started), [AnomalyKind::Drought]);
+        assert!((started[0].score - 1.0025).abs() < 1e-3);
+        assert_eq!(started[0].block, 101);
+
+        // A skipped height leaves nothing to compare with
+        assert!(detector.add_block(103, 2_000, 1.0).is_empty());
+        assert_eq!(detector.scores()[&AnomalyKind::Drought], 0.0);
+    }
+
+    #[test]
+    fn events_fire_once_per_crossing() {
+        let mut detector = AnomalyDetector::new();
+        detector.add_block(100, 1_000, 1.0);
+        assert_eq!(detector.add_block(101, 1_200, 1.0).len(), 1);
+        // Still above the threshold
+        assert!(detector.add_block(102, 1_400, 1.0).is_empty());
+        // The same tip again is not a new block
+        assert!(detector.add_block(102, 1_400, 1.0).is_empty());
+        // Back below, then above again
+        detector.add_block(103, 1_413, 1.0);
+        assert_eq!(detector.add_block(104, 1_613, 1.0).len(), 1);
+        assert_eq!(detector.report().events.len(), 2);
+    }
+
+    #[test]
+    fn hashrate_is_work_over_time() {
+        let mut detector = AnomalyDetector::new();
+        assert_eq!(detector.hashrate_short(), None);
+
+        // 1300 work every 13s is 100 H/s on both windows
+        feed(&mut detector, (0, 0), &[13; 50], 1_300.0);
+        assert_eq!(detector.hashrate_short(), Some(100.0));
+        assert_eq!(detector.hashrate_long(), Some(100.0));
+    }
+
+    #[test]
+    fn hashrate_drop_scores_log2_of_the_ratio() {
+        let mut detector = AnomalyDetector::new();
+        let mut started = feed(&mut detector, (0, 0), &honest(100), 1_300.0);
+        assert!(started.is_empty(), "{:?}", kinds(&started));
+
+        // Blocks four times slower at the same difficulty: 25 H/s over the
+        // short window against 156000 work over 2340s on the long one
+        started = feed(&mut detector, (100, 1_300), &[52; 20], 1_300.0);
+        assert!(kinds(&started).contains(&AnomalyKind::HashrateDrop));
+        assert_eq!(detector.hashrate_short(), Some(25.0));
+        let ratio = 25.0 / (156_000.0 / 2_340.0);
+        assert!((detector.scores()[&AnomalyKind::HashrateDrop] - -f64::log2(ratio)).abs() < 1e-9);
+        assert_eq!(detector.scores()[&AnomalyKind::HashrateSpike], 0.0);
+    }
+
+    #[test]
+    fn hashrate_spike_needs_a_long_enough_history() {
+        let mut detector = AnomalyDetector::new();
+        // Too little history for the long window to mean anything
+        let started = feed(&mut detector, (0, 0), &[13, 13, 13, 3, 3], 1_300.0);
+        assert!(!kinds(&started).contains(&AnomalyKind::HashrateSpike));
+
+        let mut detector = AnomalyDetector::new();
+        feed(&mut detector, (0, 0), &honest(100), 1_300.0);
+        let started = feed(&mut detector, (100, 1_300), &[3; 20], 1_300.0);
+        assert!(kinds(&started).contains(&AnomalyKind::HashrateSpike));
+    }
+
+    #[test]
+    fn timestamp_pattern_scores_the_coefficient_of_variation() {
+        let mut detector = AnomalyDetector::new();
+        feed(&mut detector, (0, 0), &honest(SHORT_WINDOW - 1), 1.0);
+        // Not enough gaps yet
+        assert_eq!(detector.block_time_cv(), None);
+
+        feed(&mut detector, (19, 239), &[21], 1.0);
+        assert!((detector.block_time_cv().unwrap() - 8.0 / 13.0).abs() < 1e-9);
+        assert!((detector.scores()[&AnomalyKind::TimestampPattern] - 0.25 * 13.0 / 8.0).abs() < 1e-9);
+
+        // Exactly regular blocks are capped at CV 0.01
+        let mut detector = AnomalyDetector::new();
+        let started = feed(&mut detector, (0, 0), &[13; SHORT_WINDOW], 1.0);
+        assert_eq!(kinds(&started), [AnomalyKind::TimestampPattern]);
+        assert_eq!(detector.scores()[&AnomalyKind::TimestampPattern], 25.0);
+    }
+}
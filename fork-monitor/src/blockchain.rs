@@ -1,106 +1,430 @@
 use anyhow::Result;
-use ethers::providers::{Provider, Http, Middleware};
-use ethers::types::{Block, H256, U64};
+use ethers::providers::{Middleware, Provider};
+use ethers::types::{Block, SyncingStatus, Transaction, U64};
+use mordor_common::checkpoint::Checkpoint;
+use mordor_common::telemetry::TracedHttp;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{info, warn};
+use tracing::{info, instrument, warn};
 
-use crate::fork_detector::ForkDetector;
+use crate::anomaly::{AnomalyDetector, AnomalyEvent, AnomalyReport};
+use crate::confirmations::{ChainObservations, ConfirmationOracle, ConfirmationReport};
+use crate::fork_detector::{ForkDetector, ForkEvent};
+use crate::gas_limit::{GasLimitReport, GasLimitTracker};
 use crate::metrics::Metrics;
+use crate::reorg::{ReorgEvent, ReorgTracker, ReorgedTx, ReorgedTxKind};
+use crate::timestamps::{TimestampChecker, TimestampReport, ViolationKind};
+
+// Blocks fetched in one poll when catching up after a restart or a slow
+// poll; anything older is counted as missed. Also the depth of the fork
+// and reorg histories.
+pub const MAX_CATCH_UP_BLOCKS: u64 = 100;
+
+// What a restart resumes from
+#[derive(Serialize, Deserialize)]
+struct MonitorState {
+    last_block: Option<U64>,
+    fork_detector: ForkDetector,
+    gas_limit_tracker: GasLimitTracker,
+    // Missing from checkpoints written before anomaly detection
+    #[serde(default)]
+    anomaly_detector: AnomalyDetector,
+    #[serde(default)]
+    timestamp_checker: TimestampChecker,
+    #[serde(default)]
+    reorg_tracker: ReorgTracker,
+}
 
 pub struct BlockchainMonitor {
-    provider: Provider<Http>,
+    provider: Provider<TracedHttp>,
+    metrics: Arc<Metrics>,
+    fork_detector: Arc<RwLock<ForkDetector>>,
+    gas_limit_tracker: Arc<RwLock<GasLimitTracker>>,
+    anomaly_detector: Arc<RwLock<AnomalyDetector>>,
+    timestamp_checker: Arc<RwLock<TimestampChecker>>,
+    reorg_tracker: Arc<RwLock<ReorgTracker>>,
+    // Seconds a block may be timestamped ahead of the monitor's clock
+    future_tolerance: u64,
+    confirmation_oracle: ConfirmationOracle,
+    last_block: Arc<RwLock<Option<U64>>>,
+    checkpoint: Option<Checkpoint>,
+}
+
+impl BlockchainMonitor {
+    pub fn new(
+        provider: Provider<TracedHttp>,
         metrics: Arc<Metrics>,
-            fork_detector: Arc<RwLock<ForkDetector>>,
-                last_block: Arc<RwLock<Option<U64>>>,
+        checkpoint: Option<Checkpoint>,
+        future_tolerance: u64,
+        confirmation_oracle: ConfirmationOracle,
+    ) -> Self {
+        let state = checkpoint.as_ref().and_then(|c| c.load::<MonitorState>());
+        let state = match state {
+            Some(state) => {
+                if let Some(block) = state.last_block {
+                    info!("Resuming after checkpointed block {}", block);
+                }
+                state
+            }
+            None => MonitorState {
+                last_block: None,
+                fork_detector: ForkDetector::new(MAX_CATCH_UP_BLOCKS as usize),
+                gas_limit_tracker: GasLimitTracker::new(),
+                anomaly_detector: AnomalyDetector::new(),
+                timestamp_checker: TimestampChecker::new(),
+                reorg_tracker: ReorgTracker::new(),
+            },
+        };
+
+        Self {
+            provider,
+            metrics,
+            fork_detector: Arc::new(RwLock::new(state.fork_detector)),
+            gas_limit_tracker: Arc::new(RwLock::new(state.gas_limit_tracker)),
+            anomaly_detector: Arc::new(RwLock::new(state.anomaly_detector)),
+            timestamp_checker: Arc::new(RwLock::new(state.timestamp_checker)),
+            reorg_tracker: Arc::new(RwLock::new(state.reorg_tracker)),
+            future_tolerance,
+            confirmation_oracle,
+            last_block: Arc::new(RwLock::new(state.last_block)),
+            checkpoint,
+        }
+    }
+
+    #[instrument(name = "blocks.poll", skip_all)]
+    pub async fn poll(&self) -> Result<()> {
+        let current_block = self.provider.get_block_number().await?;
+
+        // Update block height metric
+        self.metrics.set_block_height(current_block.as_u64());
+
+        let mut last_block = self.last_block.write().await;
+
+        // Catch up from the last processed block, up to the fork history;
+        // a tip at or below it (reorg) is processed on its own
+        let first_block = match *last_block {
+            Some(prev_block) if current_block > prev_block => {
+                let first_block = (prev_block + 1).max(current_block.saturating_sub(U64::from(MAX_CATCH_UP_BLOCKS - 1)));
+                if first_block > prev_block + 1 {
+                    warn!("Missed {} blocks", first_block - prev_block - 1);
+                    self.metrics.increment_missed_blocks((first_block - prev_block - 1).as_u64());
+                }
+                first_block
+            }
+            _ => current_block,
+        };
+
+        for number in first_block.as_u64()..=current_block.as_u64() {
+            let block = match self.provider.get_block_with_txs(number).await? {
+                Some(block) => block,
+                None => break,
+            };
+            // The tip is fetched again on every poll until a new block
+            // arrives; only a different block at its height (a reorg) is new
+            if !self.reorg_tracker.read().await.contains(block.hash.unwrap()) {
+                self.process_block(block).await?;
+            }
+            *last_block = Some(U64::from(number));
+        }
+
+        drop(last_block);
+
+        // A syncing node's tip is old, not late
+        if matches!(self.provider.syncing().await?, SyncingStatus::IsFalse) {
+            let mut detector = self.anomaly_detector.write().await;
+            let events = detector.check_drought();
+            self.record_anomalies(&detector, &events);
+        }
+
+        let report = self.confirmation_report(None, None).await;
+        self.metrics
+            .set_recommended_confirmations(report.recommended_confirmations.map_or(-1, |k| k as i64));
+
+        Ok(())
+    }
+
+    pub async fn save_checkpoint(&self) {
+        let Some(checkpoint) = &self.checkpoint else {
+            return;
+        };
+        checkpoint
+            .save(|| async {
+                MonitorState {
+                    last_block: *self.last_block.read().await,
+                    fork_detector: self.fork_detector.read().await.clone(),
+                    gas_limit_tracker: self.gas_limit_tracker.read().await.clone(),
+                    anomaly_detector: self.anomaly_detector.read().await.clone(),
+                    timestamp_checker: self.timestamp_checker.read().await.clone(),
+                    reorg_tracker: self.reorg_tracker.read().await.clone(),
+                }
+            })
+            .await;
+    }
+
+    #[instrument(
+        name = "block.process",
+        skip_all,
+        fields(number = block.number.unwrap_or_default().as_u64(), hash = ?block.hash.unwrap_or_default())
+    )]
+    async fn process_block(&self, block: Block<Transaction>) -> Result<()> {
+        let block_number = block.number.unwrap().as_u64();
+        let block_hash = block.hash.unwrap();
+        let parent_hash = block.parent_hash;
+        let timestamp = block.timestamp.as_u64();
+        let difficulty = block.difficulty;
+        let gas_used = block.gas_used.as_u64();
+        let gas_limit = block.gas_limit.as_u64();
+        let tx_count = block.transactions.len() as u64;
+
+        // Update basic metrics
+        self.metrics.set_block_timestamp(timestamp);
+        self.metrics.set_block_gas_used(gas_used);
+        self.metrics.set_block_gas_limit(gas_limit);
+        self.metrics.set_transaction_count(tx_count);
+        self.metrics.observe_block_difficulty(difficulty.as_u128() as f64);
+
+        // Calculate block time, check the timestamp and track the gas limit
+        // vote. The parent is fetched by hash, as during a reorg its height
+        // may still hold the other branch's block.
+        if block_number > 0 {
+            if let Some(parent) = self.provider.get_block(parent_hash).await? {
+                let parent_timestamp = parent.timestamp.as_u64();
+                // Timestamps that don't increase are flagged, not timed
+                if timestamp > parent_timestamp {
+                    self.metrics.observe_block_time((timestamp - parent_timestamp) as f64);
+                }
+
+                self.check_timestamp(&block, parent_timestamp).await;
+                self.track_gas_limit(&block, parent.gas_limit.as_u64()).await;
+            }
+        }
+
+        // Check for forks
+        let mut fork_detector = self.fork_detector.write().await;
+        if let Some(fork_info) = fork_detector.add_block(
+            block_number,
+            block_hash,
+            parent_hash,
+        ) {
+            warn!(
+                "Fork detected at height {}: depth={}, competing_blocks={}",
+                fork_info.height, fork_info.depth, fork_info.competing_blocks
+            );
+
+            self.metrics.increment_fork_count();
+            self.metrics.observe_fork_depth(fork_info.depth as f64);
+            self.metrics.set_active_forks(fork_info.active_forks as i64);
+        }
+        drop(fork_detector);
+
+        self.track_reorg(&block).await?;
+
+        let mut anomaly_detector = self.anomaly_detector.write().await;
+        let events = anomaly_detector.add_block(block_number, timestamp, difficulty.as_u128() as f64);
+        self.record_anomalies(&anomaly_detector, &events);
+        drop(anomaly_detector);
+
+        info!(
+            "Block {}: {} txs, {} gas, {} difficulty",
+            block_number,
+            tx_count,
+            gas_used,
+            difficulty
+        );
+
+        Ok(())
+    }
+
+    // Diffs the transactions of the branch a block brings in against the
+    // blocks it orphans
+    async fn track_reorg(&self, block: &Block<Transaction>) -> Result<()> {
+        if self.reorg_tracker.read().await.extends(block) {
+            let settled = self.reorg_tracker.write().await.add_block(block);
+            self.record_reorged_txs(&settled, true);
+            return Ok(());
+        }
+
+        // Walk the new branch back to a tracked block, newest first
+        let oldest = self.reorg_tracker.read().await.oldest().unwrap_or(0);
+        let mut branch = vec![block.clone()];
+        for _ in 0..MAX_CATCH_UP_BLOCKS {
+            let first = branch.last().unwrap();
+            if self.reorg_tracker.read().await.contains(first.parent_hash)
+                || first.number.unwrap_or_default().as_u64() <= oldest
+            {
+                break;
+            }
+            match self.provider.get_block_with_txs(first.parent_hash).await? {
+                Some(parent) => branch.push(parent),
+                None => break,
+            }
+        }
+        branch.reverse();
+
+        let (event, settled) = self.reorg_tracker.write().await.reorg(&branch);
+        match event {
+            Some(event) => {
+                warn!(
+                    "Reorg after block {}: {} block(s) orphaned, {} transaction(s) affected",
+                    event.common_ancestor,
+                    event.depth,
+                    event.transactions.len()
+                );
+                self.record_reorged_txs(&event.transactions, false);
+            }
+            None => info!(
+                "Block {} does not connect to the tracked chain, restarting reorg history",
+                block.number.unwrap_or_default()
+            ),
+        }
+        self.record_reorged_txs(&settled, true);
+        Ok(())
+    }
+
+    // Dropped transactions may still be mined, so they are only counted once
+    // settled; each transaction is counted under one outcome
+    fn record_reorged_txs(&self, txs: &[ReorgedTx], settled: bool) {
+        for tx in txs {
+            match tx.kind {
+                ReorgedTxKind::Replaced => warn!(
+                    "Possible double spend: {:?} (from {:?}, nonce {}) in orphaned block {} was replaced by {:?} in block {}",
+                    tx.hash,
+                    tx.from,
+                    tx.nonce,
+                    tx.orphaned_block,
+                    tx.replaced_by.unwrap_or_default(),
+                    tx.canonical_block.unwrap_or_default()
+                ),
+                ReorgedTxKind::Dropped if !settled => {
+                    warn!(
+                        "Transaction {:?} in orphaned block {} is not in the canonical chain",
+                        tx.hash, tx.orphaned_block
+                    );
+                    continue;
                 }
+                ReorgedTxKind::Dropped => warn!(
+                    "Transaction {:?} in orphaned block {} was not mined again within {} blocks",
+                    tx.hash, tx.orphaned_block, MAX_CATCH_UP_BLOCKS
+                ),
+                ReorgedTxKind::Reincluded => info!(
+                    "Transaction {:?} in orphaned block {} was re-included in block {}",
+                    tx.hash,
+                    tx.orphaned_block,
+                    tx.canonical_block.unwrap_or_default()
+                ),
+            }
+            self.metrics.increment_reorged_transactions(tx.kind.as_str());
+        }
+    }
+
+    async fn check_timestamp(&self, block: &Block<Transaction>, parent_timestamp: u64) {
+        let violations = self
+            .timestamp_checker
+            .write()
+            .await
+            .check(block, parent_timestamp, self.future_tolerance);
+
+        for violation in violations {
+            let miner = format!("{:?}", violation.miner);
+            match violation.kind {
+                ViolationKind::Future => warn!(
+                    "Block {} from {} is timestamped {}s in the future",
+                    violation.block, miner, violation.offset
+                ),
+                ViolationKind::NonMonotonic => warn!(
+                    "Block {} from {} is timestamped {}s after its parent",
+                    violation.block, miner, violation.offset
+                ),
+                // Honest blocks land here too, about 7% of the time
+                ViolationKind::MinInterval => info!(
+                    "Block {} from {} is timestamped at the minimum interval",
+                    violation.block, miner
+                ),
+            }
+            self.metrics.increment_timestamp_violations(violation.kind.as_str(), &miner);
+        }
+    }
+
+    async fn track_gas_limit(&self, block: &Block<Transaction>, parent_gas_limit: u64) {
+        let miner = block.author.unwrap_or_default();
+        let gas_used = block.gas_used.as_u64();
+        let gas_limit = block.gas_limit.as_u64();
+
+        let mut tracker = self.gas_limit_tracker.write().await;
+        let vote = match tracker.add_block(block, parent_gas_limit) {
+            Some(vote) => vote,
+            None => return,
+        };
+        self.metrics.increment_gas_limit_vote(&format!("{:?}", miner), vote.as_str());
+
+        if GasLimitTracker::is_full(gas_used, gas_limit) {
+            self.metrics.increment_full_blocks();
+        }
+
+        let report = tracker.report();
+        for window in &report.utilization {
+            self.metrics.set_gas_utilization_avg(&window.window, window.avg_utilization_percent);
+            self.metrics.set_full_blocks_window(&window.window, window.full_blocks);
+        }
+        self.metrics.set_gas_limit_trend(report.trend_per_hour);
+        self.metrics.set_gas_limit_drift(report.drift.as_i64());
+    }
+
+    fn record_anomalies(&self, detector: &AnomalyDetector, events: &[AnomalyEvent]) {
+        for event in events {
+            warn!("Anomaly at block {}: {} (score {:.2})", event.block, event.message, event.score);
+            self.metrics.increment_anomalies(event.kind.as_str());
+        }
+        for (kind, score) in detector.scores() {
+            self.metrics.set_anomaly_score(kind.as_str(), *score);
+        }
+        if let Some(hashrate) = detector.hashrate_short() {
+            self.metrics.set_hashrate_estimate("short", hashrate);
+        }
+        if let Some(hashrate) = detector.hashrate_long() {
+            self.metrics.set_hashrate_estimate("long", hashrate);
+        }
+    }
+
+    pub async fn gas_limit_report(&self) -> GasLimitReport {
+        self.gas_limit_tracker.read().await.report()
+    }
+
+    pub async fn fork_events(&self) -> Vec<ForkEvent> {
+        self.fork_detector.read().await.events()
+    }
+
+    pub async fn anomaly_report(&self) -> AnomalyReport {
+        self.anomaly_detector.read().await.report()
+    }
+
+    pub async fn reorg_events(&self) -> Vec<ReorgEvent> {
+        self.reorg_tracker.read().await.events()
+    }
+
+    /// Reorg risk by confirmation depth, for the configured target risk and
+    /// attacker share unless others are given.
+    pub async fn confirmation_report(&self, target_risk: Option<f64>, attacker_share: Option<f64>) -> ConfirmationReport {
+        let (blocks_observed, reorg_depths) = {
+            let tracker = self.reorg_tracker.read().await;
+            (tracker.blocks_seen(), tracker.depths())
+        };
+        let (hashrate_short, hashrate_long) = {
+            let detector = self.anomaly_detector.read().await;
+            (detector.hashrate_short(), detector.hashrate_long())
+        };
+        let chain = ChainObservations {
+            block: self.last_block.read().await.unwrap_or_default().as_u64(),
+            blocks_observed,
+            reorg_depths,
+            hashrate_short,
+            hashrate_long,
+        };
+        self.confirmation_oracle.report(&chain, target_risk, attacker_share)
+    }
 
-                impl BlockchainMonitor {
-                    pub fn new(provider: Provider<Http>, metrics: Arc<Metrics>) -> Self {
-                            Self {
-                                        provider,
-                                                    metrics,
-                                                                fork_detector: Arc::new(RwLock::new(ForkDetector::new(100))),
-                                                                            last_block: Arc::new(RwLock::new(None)),
-                                                                                    }
-                                                                                        }
-
-                                                                                            pub async fn poll(&self) -> Result<()> {
-                                                                                                    let current_block = self.provider.get_block_number().await?;
-                                                                                                            
-                                                                                                                    // Update block height metric
-                                                                                                                            self.metrics.set_block_height(current_block.as_u64());
-
-                                                                                                                                    let mut last_block = self.last_block.write().await;
-                                                                                                                                            
-                                                                                                                                                    // Check if we missed blocks (reorg or restart)
-                                                                                                                                                            if let Some(prev_block) = *last_block {
-                                                                                                                                                                        if current_block > prev_block + 1 {
-                                                                                                                                                                                        warn!("Missed {} blocks", current_block - prev_block - 1);
-                                                                                                                                                                                                        self.metrics.increment_missed_blocks((current_block - prev_block - 1).as_u64());
-                                                                                                                                                                                                                    }
-                                                                                                                                                                                                                            }
-
-                                                                                                                                                                                                                                    // Fetch the block
-                                                                                                                                                                                                                                            if let Some(block) = self.provider.get_block(current_block).await? {
-                                                                                                                                                                                                                                                        self.process_block(block).await?;
-                                                                                                                                                                                                                                                                }
-
-                                                                                                                                                                                                                                                                        *last_block = Some(current_block);
-                                                                                                                                                                                                                                                                                Ok(())
-                                                                                                                                                                                                                                                                                    }
-
-                                                                                                                                                                                                                                                                                        async fn process_block(&self, block: Block<H256>) -> Result<()> {
-                                                                                                                                                                                                                                                                                                let block_number = block.number.unwrap().as_u64();
-                                                                                                                                                                                                                                                                                                        let block_hash = block.hash.unwrap();
-                                                                                                                                                                                                                                                                                                                let parent_hash = block.parent_hash;
-                                                                                                                                                                                                                                                                                                                        let timestamp = block.timestamp.as_u64();
-                                                                                                                                                                                                                                                                                                                                let difficulty = block.difficulty;
-                                                                                                                                                                                                                                                                                                                                        let gas_used = block.gas_used.as_u64();
-                                                                                                                                                                                                                                                                                                                                                let gas_limit = block.gas_limit.as_u64();
-                                                                                                                                                                                                                                                                                                                                                        let tx_count = block.transactions.len() as u64;
-
-                                                                                                                                                                                                                                                                                                                                                                // Update basic metrics
-                                                                                                                                                                                                                                                                                                                                                                        self.metrics.set_block_timestamp(timestamp);
-                                                                                                                                                                                                                                                                                                                                                                                self.metrics.set_block_gas_used(gas_used);
-                                                                                                                                                                                                                                                                                                                                                                                        self.metrics.set_block_gas_limit(gas_limit);
-                                                                                                                                                                                                                                                                                                                                                                                                self.metrics.set_transaction_count(tx_count);
-                                                                                                                                                                                                                                                                                                                                                                                                        self.metrics.observe_block_difficulty(difficulty.as_u128() as f64);
-
-                                                                                                                                                                                                                                                                                                                                                                                                                // Calculate block time
-                                                                                                                                                                                                                                                                                                                                                                                                                        if block_number > 0 {
-                                                                                                                                                                                                                                                                                                                                                                                                                                    if let Some(parent) = self.provider.get_block(block_number - 1).await? {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                    let block_time = timestamp - parent.timestamp.as_u64();
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                    self.metrics.observe_block_time(block_time as f64);
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                }
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        }
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                // Check for forks
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        let mut fork_detector = self.fork_detector.write().await;
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                if let Some(fork_info) = fork_detector.add_block(
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            block_number,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        block_hash,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    parent_hash,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            ) {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        warn!(
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        "Fork detected at height {}: depth={}, competing_blocks={}",
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        fork_info.height, fork_info.depth, fork_info.competing_blocks
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    );
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            self.metrics.increment_fork_count();
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        self.metrics.observe_fork_depth(fork_info.depth as f64);
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    self.metrics.set_active_forks(fork_info.active_forks as i64);
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            }
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    info!(
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                "Block {}: {} txs, {} gas, {} difficulty",
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            block_number,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        tx_count,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    gas_used,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                difficulty
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        );
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                Ok(())
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    }
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    }
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    
\ No newline at end of file
+    pub async fn timestamp_report(&self) -> TimestampReport {
+        self.timestamp_checker.read().await.report()
+    }
+}
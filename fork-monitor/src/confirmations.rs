@@ -0,0 +1,353 @@
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+// ETC's target block time
+const TARGET_BLOCK_TIME: f64 = 13.0;
+
+// ECBP-1100 (MESS) antigravity curve constants, as in core-geth
+const MESS_CURVE_DENOMINATOR: f64 = 128.0;
+const MESS_XCAP: f64 = 25_132.0;
+const MESS_AMPLITUDE: f64 = 15.0;
+
+/// What the oracle knows about the chain right now.
+pub struct ChainObservations {
+    pub block: u64,
+    pub blocks_observed: u64,
+    pub reorg_depths: Vec<u64>,
+    pub hashrate_short: Option<f64>,
+    pub hashrate_long: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct DepthRisk {
+    pub confirmations: u64,
+    // Chance of a reorg deeper than this from ordinary forks
+    pub natural: f64,
+    // Chance the attacker overtakes this many blocks
+    pub attack: f64,
+    pub probability: f64,
+    // Total difficulty a competing chain needs relative to ours (1 without MESS)
+    pub mess_factor: f64,
+}
+
+#[derive(Serialize)]
+pub struct ConfirmationReport {
+    pub block: u64,
+    pub target_risk: f64,
+    pub recommended_confirmations: Option<u64>,
+    pub attacker_share: f64,
+    pub unexplained_hashrate_share: f64,
+    pub hashrate_short: Option<f64>,
+    pub hashrate_long: Option<f64>,
+    pub mess_active: bool,
+    pub blocks_observed: u64,
+    pub reorgs_observed: usize,
+    pub max_reorg_depth: u64,
+    pub depths: Vec<DepthRisk>,
+}
+
+/// Estimates the chance that a block `k` deep is reorged out, from the
+/// reorgs seen so far and from an attacker holding part of the hashrate,
+/// and the confirmations needed to bring that under a target risk.
+pub struct ConfirmationOracle {
+    attacker_share: f64,
+    target_risk: f64,
+    max_depth: u64,
+    mess_activation: u64,
+    mess_deactivation: Option<u64>,
+}
+
+impl ConfirmationOracle {
+    /// From `CONFIRMATION_*` and `MESS_*`; MESS defaults to its Mordor
+    /// activation (ECBP-1100) and deactivation (Spiral).
+    pub fn from_env() -> Result<Self> {
+        let attacker_share = std::env::var("CONFIRMATION_ATTACKER_SHARE")
+            .unwrap_or_else(|_| "0.1".to_string())
+            .parse::<f64>()?;
+        let target_risk = std::env::var("CONFIRMATION_TARGET_RISK")
+            .unwrap_or_else(|_| "0.000001".to_string())
+            .parse::<f64>()?;
+        let max_depth = std::env::var("CONFIRMATION_MAX_DEPTH")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse::<u64>()?;
+        let mess_activation = std::env::var("MESS_ACTIVATION_BLOCK")
+            .unwrap_or_else(|_| "2380000".to_string())
+            .parse::<u64>()?;
+        // Empty keeps MESS active
+        let mess_deactivation = std::env::var("MESS_DEACTIVATION_BLOCK")
+            .unwrap_or_else(|_| "9957000".to_string());
+        let mess_deactivation = match mess_deactivation.as_str() {
+            "" => None,
+            block => Some(block.parse::<u64>()?),
+        };
+
+        Self::validate(Some(attacker_share), Some(target_risk))?;
+        Ok(Self {
+            attacker_share,
+            target_risk,
+            max_depth,
+            mess_activation,
+            mess_deactivation,
+        })
+    }
+
+    pub fn validate(attacker_share: Option<f64>, target_risk: Option<f64>) -> Result<()> {
+        if let Some(share) = attacker_share.filter(|share| !(0.0..1.0).contains(share)) {
+            bail!("Attacker share must be at least 0 and below 1, got {}", share);
+        }
+        if let Some(risk) = target_risk.filter(|risk| !(*risk > 0.0 && *risk < 1.0)) {
+            bail!("Target risk must be between 0 and 1, got {}", risk);
+        }
+        Ok(())
+    }
+
+    /// The report for the default target risk and attacker share, or the
+    /// ones given.
+    pub fn report(
+        &self,
+        chain: &ChainObservations,
+        target_risk: Option<f64>,
+        attacker_share: Option<f64>,
+    ) -> ConfirmationReport {
+        let target_risk = target_risk.unwrap_or(self.target_risk);
+
+        // Hashrate above the last hour's is counted as the attacker's too
+        let unexplained_hashrate_share = match (chain.hashrate_short, chain.hashrate_long) {
+            (Some(short), Some(long)) if short > long && short > 0.0 => (short - long) / short,
+            _ => 0.0,
+        };
+        let attacker_share = attacker_share
+            .unwrap_or(self.attacker_share)
+            .max(unexplained_hashrate_share);
+
+        let mess_active = chain.block >= self.mess_activation
+            && self.mess_deactivation.map_or(true, |block| chain.block < block);
+
+        let reorgs = chain.reorg_depths.len();
+        // Reorgs per block, with depths beyond the ones seen extrapolated
+        // from a geometric fit to their mean
+        let reorg_rate = if chain.blocks_observed > 0 {
+            reorgs as f64 / chain.blocks_observed as f64
+        } else {
+            0.0
+        };
+        let mean_depth = if reorgs > 0 {
+            chain.reorg_depths.iter().sum::<u64>() as f64 / reorgs as f64
+        } else {
+            1.0
+        };
+
+        let depths: Vec<DepthRisk> = (1..=self.max_depth)
+            .map(|k| {
+                let deeper = chain.reorg_depths.iter().filter(|depth| **depth > k).count();
+                let observed = if chain.blocks_observed > 0 {
+                    deeper as f64 / chain.blocks_observed as f64
+                } else {
+                    0.0
+                };
+                let natural = (reorg_rate * (1.0 - 1.0 / mean_depth).powi(k as i32)).max(observed).min(1.0);
+
+                let mess_factor = if mess_active {
+                    mess_factor(k as f64 * TARGET_BLOCK_TIME)
+                } else {
+                    1.0
+                };
+                let attack = catch_up_probability(effective_share(attacker_share, mess_factor), k);
+
+                DepthRisk {
+                    confirmations: k,
+                    natural,
+                    attack,
+                    probability: natural + attack - natural * attack,
+                    mess_factor,
+                }
+            })
+            .collect();
+
+        ConfirmationReport {
+            block: chain.block,
+            target_risk,
+            recommended_confirmations: depths
+                .iter()
+                .find(|depth| depth.probability <= target_risk)
+                .map(|depth| depth.confirmations),
+            attacker_share,
+            unexplained_hashrate_share,
+            hashrate_short: chain.hashrate_short,
+            hashrate_long: chain.hashrate_long,
+            mess_active,
+            blocks_observed: chain.blocks_observed,
+            reorgs_observed: reorgs,
+            max_reorg_depth: chain.reorg_depths.iter().copied().max().unwrap_or(0),
+            depths,
+        }
+    }
+}
+
+/// ECBP-1100's antigravity curve: the total difficulty, relative to the
+/// local chain's, a competing chain needs to replace `seconds` of history.
+/// Rises from 1 to 31 over about seven hours.
+fn mess_factor(seconds: f64) -> f64 {
+    let x = seconds.min(MESS_XCAP);
+    let height = MESS_CURVE_DENOMINATOR * MESS_AMPLITUDE * 2.0;
+    let numerator = MESS_CURVE_DENOMINATOR + (3.0 * x.powi(2) - 2.0 * x.powi(3) / MESS_XCAP) * height / MESS_XCAP.powi(2);
+    numerator / MESS_CURVE_DENOMINATOR
+}
+
+// An attacker held to `factor` times the honest chain's work does as well
+// as one with `factor` times less hashrate
+fn effective_share(share: f64, factor: f64) -> f64 {
+    let ratio = share / (1.0 - share) / factor;
+    ratio / (1.0 + ratio)
+}
+
+// Chance an attacker with share `q` of the hashrate ever overtakes a chain
+// `k` blocks ahead (section 11 of the Bitcoin paper). Summed as positive
+// terms rather than 1 - sum, which loses the small risks to rounding.
+fn catch_up_probability(q: f64, k: u64) -> f64 {
+    let p = 1.0 - q;
+    if q <= 0.0 {
+        return 0.0;
+    }
+    if q >= p {
+        return 1.0;
+    }
+
+    // Poisson(lambda) blocks mined by the attacker meanwhile; with j of
+    // them it still has to catch up k - j
+    let lambda = k as f64 * q / p;
+    let mut poisson = (-lambda).exp();
+    let mut probability = 0.0;
+    for j in 0.. {
+        if j > 0 {
+            poisson *= lambda / j as f64;
+        }
+        if j <= k {
+            probability += poisson * (q / p).powi((k - j) as i32);
+        } else if poisson <= probability * f64::EPSILON {
+            break;
+        } else {
+            // Already ahead
+            probability += poisson;
+        }
+    }
+    probability.min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oracle(mess_activation: u64) -> ConfirmationOracle {
+        ConfirmationOracle {
+            attacker_share: 0.1,
+            target_risk: 0.001,
+            max_depth: 30,
+            mess_activation,
+            mess_deactivation: None,
+        }
+    }
+
+    fn chain(reorg_depths: Vec<u64>) -> ChainObservations {
+        ChainObservations {
+            block: 1_000_000,
+            blocks_observed: 1_000,
+            reorg_depths,
+            hashrate_short: None,
+            hashrate_long: None,
+        }
+    }
+
+    #[test]
+    fn catch_up_probability_matches_nakamoto_table() {
+        // Section 11 of the Bitcoin paper, q = 0.1 and q = 0.3
+        let q_01 = [
+            1.0, 0.2045873, 0.0509779, 0.0131722, 0.0034552, 0.0009137, 0.0002428, 0.0000647, 0.0000173,
+            0.0000046, 0.0000012,
+        ];
+        for (k, expected) in q_01.iter().enumerate() {
+            let p = catch_up_probability(0.1, k as u64);
+            assert!((p - expected).abs() < 5e-8, "q=0.1 z={}: {} != {}", k, p, expected);
+        }
+
+        let q_03 = [
+            1.0, 0.1773523, 0.0416605, 0.0101008, 0.0024804, 0.0006132, 0.0001522, 0.0000379, 0.0000095,
+            0.0000024, 0.0000006,
+        ];
+        for (i, expected) in q_03.iter().enumerate() {
+            let p = catch_up_probability(0.3, 5 * i as u64);
+            assert!((p - expected).abs() < 5e-8, "q=0.3 z={}: {} != {}", 5 * i, p, expected);
+        }
+    }
+
+    #[test]
+    fn confirmations_for_a_tenth_of_a_percent_match_nakamoto() {
+        for (q, z) in [(0.10, 5), (0.15, 8), (0.20, 11), (0.25, 15), (0.30, 24), (0.35, 41), (0.40, 89), (0.45, 340)] {
+            let needed = (0..).find(|&k| catch_up_probability(q, k) < 0.001).unwrap();
+            assert_eq!(needed, z, "q={}", q);
+        }
+    }
+
+    #[test]
+    fn catch_up_probability_edge_cases() {
+        assert_eq!(catch_up_probability(0.0, 1), 0.0);
+        assert_eq!(catch_up_probability(0.5, 100), 1.0);
+        assert_eq!(catch_up_probability(0.6, 100), 1.0);
+    }
+
+    #[test]
+    fn mess_factor_follows_the_antigravity_curve() {
+        assert_eq!(mess_factor(0.0), 1.0);
+        // 3x^2 - 2x^3 is a half at the midpoint: 1 + 0.5 * 30
+        assert!((mess_factor(MESS_XCAP / 2.0) - 16.0).abs() < 1e-9);
+        assert!((mess_factor(MESS_XCAP) - 31.0).abs() < 1e-9);
+        assert_eq!(mess_factor(10.0 * MESS_XCAP), mess_factor(MESS_XCAP));
+        // One 13s block barely moves it
+        assert!((mess_factor(13.0) - (1.0 + 30.0 * (3.0 * (13.0 / MESS_XCAP).powi(2) - 2.0 * (13.0 / MESS_XCAP).powi(3)))).abs() < 1e-12);
+    }
+
+    #[test]
+    fn effective_share_divides_the_odds() {
+        assert!((effective_share(0.1, 1.0) - 0.1).abs() < 1e-12);
+        // Even odds held to 31 times the work are 1:31
+        assert!((effective_share(0.5, 31.0) - 1.0 / 32.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn report_recommends_the_first_depth_under_the_target() {
+        let report = oracle(u64::MAX).report(&chain(Vec::new()), None, None);
+
+        assert!(!report.mess_active);
+        assert_eq!(report.recommended_confirmations, Some(5));
+        assert!((report.depths[4].attack - 0.0009137).abs() < 5e-8);
+        assert!(report.depths.iter().all(|depth| depth.natural == 0.0 && depth.mess_factor == 1.0));
+
+        // MESS makes the attacker's job harder at every depth
+        let mess = oracle(0).report(&chain(Vec::new()), None, None);
+        assert!(mess.mess_active);
+        assert!(mess.depths.iter().zip(&report.depths).all(|(with, without)| with.attack < without.attack));
+    }
+
+    #[test]
+    fn report_extrapolates_observed_reorgs() {
+        // Two reorgs in 1000 blocks with a mean depth of 2: 0.002 * 0.5^k,
+        // but never below the share actually seen deeper than k
+        let report = oracle(u64::MAX).report(&chain(vec![1, 3]), None, Some(0.0));
+        let natural: Vec<f64> = report.depths[..4].iter().map(|depth| depth.natural).collect();
+        assert_eq!(natural, [0.001, 0.001, 0.00025, 0.000125]);
+        assert_eq!(report.max_reorg_depth, 3);
+        assert!(report.depths.iter().all(|depth| depth.attack == 0.0));
+    }
+
+    #[test]
+    fn hashrate_above_the_hourly_average_counts_as_the_attackers() {
+        let mut observations = chain(Vec::new());
+        observations.hashrate_short = Some(200.0);
+        observations.hashrate_long = Some(150.0);
+
+        let report = oracle(u64::MAX).report(&observations, None, None);
+        assert_eq!(report.unexplained_hashrate_share, 0.25);
+        assert_eq!(report.attacker_share, 0.25);
+        assert_eq!(report.recommended_confirmations, Some(15));
+    }
+}
@@ -1,106 +1,149 @@
 use ethers::types::H256;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Fork events kept for the /forks API
+const MAX_FORK_EVENTS: usize = 1_000;
 
 pub struct ForkInfo {
     pub height: u64,
-        pub depth: u64,
-            pub competing_blocks: usize,
-                pub active_forks: usize,
+    pub depth: u64,
+    pub competing_blocks: usize,
+    pub active_forks: usize,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ForkEvent {
+    pub detected_at: u64,
+    pub height: u64,
+    pub depth: u64,
+    pub competing_blocks: usize,
+    pub active_forks: usize,
+    pub hashes: Vec<H256>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ForkDetector {
+    // Map of block height -> list of block hashes at that height
+    blocks_at_height: HashMap<u64, Vec<H256>>,
+    // Map of block hash -> parent hash
+    parent_map: HashMap<H256, H256>,
+    // Keep last N blocks
+    max_history: usize,
+    // Track canonical chain
+    canonical_chain: VecDeque<(u64, H256)>,
+    // Detected forks, oldest first
+    events: VecDeque<ForkEvent>,
+}
+
+impl ForkDetector {
+    pub fn new(max_history: usize) -> Self {
+        Self {
+            blocks_at_height: HashMap::new(),
+            parent_map: HashMap::new(),
+            max_history,
+            canonical_chain: VecDeque::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    pub fn add_block(
+        &mut self,
+        height: u64,
+        block_hash: H256,
+        parent_hash: H256,
+    ) -> Option<ForkInfo> {
+        // Store parent relationship
+        self.parent_map.insert(block_hash, parent_hash);
+
+        // Get blocks at this height
+        let blocks = self.blocks_at_height.entry(height).or_default();
+
+        // Check if we already have this block
+        if blocks.contains(&block_hash) {
+            return None;
+        }
+
+        blocks.push(block_hash);
+        let competing_blocks = blocks.len();
+        let first_hash = blocks[0];
+        let hashes = blocks.clone();
+
+        // Update canonical chain
+        self.canonical_chain.push_back((height, block_hash));
+        if self.canonical_chain.len() > self.max_history {
+            if let Some((old_height, _)) = self.canonical_chain.pop_front() {
+                // Forget the parents too, or the map (and the checkpoint) grows forever
+                for hash in self.blocks_at_height.remove(&old_height).unwrap_or_default() {
+                    self.parent_map.remove(&hash);
                 }
+            }
+        }
+
+        // Detect fork
+        if competing_blocks > 1 {
+            let depth = self.calculate_fork_depth(height, &first_hash, block_hash);
+            let active_forks = self.count_active_forks();
+
+            self.events.push_back(ForkEvent {
+                detected_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0),
+                height,
+                depth,
+                competing_blocks,
+                active_forks,
+                hashes,
+            });
+            if self.events.len() > MAX_FORK_EVENTS {
+                self.events.pop_front();
+            }
+
+            Some(ForkInfo {
+                height,
+                depth,
+                competing_blocks,
+                active_forks,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn calculate_fork_depth(&self, _height: u64, hash1: &H256, hash2: H256) -> u64 {
+        let mut depth = 0;
+        let mut current1 = *hash1;
+        let mut current2 = hash2;
+
+        // Walk back until we find common ancestor
+        for _ in 0..self.max_history {
+            if current1 == current2 {
+                return depth;
+            }
+
+            match (self.parent_map.get(&current1), self.parent_map.get(&current2)) {
+                (Some(&p1), Some(&p2)) => {
+                    current1 = p1;
+                    current2 = p2;
+                    depth += 1;
+                }
+                _ => break,
+            }
+        }
+
+        depth
+    }
+
+    pub fn events(&self) -> Vec<ForkEvent> {
+        self.events.iter().cloned().collect()
+    }
 
-                pub struct ForkDetector {
-                    // Map of block height -> list of block hashes at that height
-                        blocks_at_height: HashMap<u64, Vec<H256>>,
-                            // Map of block hash -> parent hash
-                                parent_map: HashMap<H256, H256>,
-                                    // Keep last N blocks
-                                        max_history: usize,
-                                            // Track canonical chain
-                                                canonical_chain: VecDeque<(u64, H256)>,
-                                                }
-
-                                                impl ForkDetector {
-                                                    pub fn new(max_history: usize) -> Self {
-                                                            Self {
-                                                                        blocks_at_height: HashMap::new(),
-                                                                                    parent_map: HashMap::new(),
-                                                                                                max_history,
-                                                                                                            canonical_chain: VecDeque::new(),
-                                                                                                                    }
-                                                                                                                        }
-
-                                                                                                                            pub fn add_block(
-                                                                                                                                    &mut self,
-                                                                                                                                            height: u64,
-                                                                                                                                                    block_hash: H256,
-                                                                                                                                                            parent_hash: H256,
-                                                                                                                                                                ) -> Option<ForkInfo> {
-                                                                                                                                                                        // Store parent relationship
-                                                                                                                                                                                self.parent_map.insert(block_hash, parent_hash);
-
-                                                                                                                                                                                        // Get blocks at this height
-                                                                                                                                                                                                let blocks = self.blocks_at_height.entry(height).or_insert_with(Vec::new);
-                                                                                                                                                                                                        
-                                                                                                                                                                                                                // Check if we already have this block
-                                                                                                                                                                                                                        if blocks.contains(&block_hash) {
-                                                                                                                                                                                                                                    return None;
-                                                                                                                                                                                                                                            }
-
-                                                                                                                                                                                                                                                    blocks.push(block_hash);
-
-                                                                                                                                                                                                                                                            // Update canonical chain
-                                                                                                                                                                                                                                                                    self.canonical_chain.push_back((height, block_hash));
-                                                                                                                                                                                                                                                                            if self.canonical_chain.len() > self.max_history {
-                                                                                                                                                                                                                                                                                        if let Some((old_height, _)) = self.canonical_chain.pop_front() {
-                                                                                                                                                                                                                                                                                                        self.blocks_at_height.remove(&old_height);
-                                                                                                                                                                                                                                                                                                                    }
-                                                                                                                                                                                                                                                                                                                            }
-
-                                                                                                                                                                                                                                                                                                                                    // Detect fork
-                                                                                                                                                                                                                                                                                                                                            if blocks.len() > 1 {
-                                                                                                                                                                                                                                                                                                                                                        let depth = self.calculate_fork_depth(height, &blocks[0], block_hash);
-                                                                                                                                                                                                                                                                                                                                                                    let active_forks = self.count_active_forks();
-
-                                                                                                                                                                                                                                                                                                                                                                                Some(ForkInfo {
-                                                                                                                                                                                                                                                                                                                                                                                                height,
-                                                                                                                                                                                                                                                                                                                                                                                                                depth,
-                                                                                                                                                                                                                                                                                                                                                                                                                                competing_blocks: blocks.len(),
-                                                                                                                                                                                                                                                                                                                                                                                                                                                active_forks,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                            })
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                    } else {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                None
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        }
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            }
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                fn calculate_fork_depth(&self, _height: u64, hash1: &H256, hash2: H256) -> u64 {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        let mut depth = 0;
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                let mut current1 = *hash1;
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        let mut current2 = hash2;
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                // Walk back until we find common ancestor
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        for _ in 0..self.max_history {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    if current1 == current2 {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    return depth;
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                }
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            match (self.parent_map.get(&current1), self.parent_map.get(&current2)) {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            (Some(&p1), Some(&p2)) => {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                current1 = p1;
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    current2 = p2;
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        depth += 1;
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        }
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        _ => break,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    }
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            }
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    depth
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        }
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            fn count_active_forks(&self) -> usize {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    self.blocks_at_height
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                .values()
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            .filter(|blocks| blocks.len() > 1)
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        .count()
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            }
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            }
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            
\ No newline at end of file
+    fn count_active_forks(&self) -> usize {
+        self.blocks_at_height
+            .values()
+            .filter(|blocks| blocks.len() > 1)
+            .count()
+    }
+}
@@ -0,0 +1,249 @@
+use ethers::types::{Address, Block, H256};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+// Rolling utilization windows, by block timestamp
+pub const WINDOWS: [(&str, u64); 2] = [("1h", 3_600), ("24h", 86_400)];
+
+// A block counts as full above this utilization
+const FULL_BLOCK_THRESHOLD: f64 = 0.95;
+
+// Hourly drift below this fraction of the limit is reported as stable
+const STABLE_DRIFT_FRACTION: f64 = 0.001;
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Vote {
+    Up,
+    Down,
+    Hold,
+}
+
+impl Vote {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Vote::Up => "up",
+            Vote::Down => "down",
+            Vote::Hold => "hold",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Drift {
+    Up,
+    Down,
+    Stable,
+}
+
+impl Drift {
+    pub fn as_i64(&self) -> i64 {
+        match self {
+            Drift::Up => 1,
+            Drift::Down => -1,
+            Drift::Stable => 0,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct GasLimitSample {
+    number: u64,
+    hash: H256,
+    timestamp: u64,
+    miner: Address,
+    gas_used: u64,
+    gas_limit: u64,
+    vote: Vote,
+}
+
+impl GasLimitSample {
+    fn utilization(&self) -> f64 {
+        if self.gas_limit == 0 {
+            return 0.0;
+        }
+        self.gas_used as f64 / self.gas_limit as f64
+    }
+}
+
+#[derive(Serialize)]
+pub struct MinerVotes {
+    pub miner: Address,
+    pub up: u64,
+    pub down: u64,
+    pub hold: u64,
+    pub last_gas_limit: u64,
+}
+
+#[derive(Serialize)]
+pub struct UtilizationWindow {
+    pub window: String,
+    pub blocks: usize,
+    pub avg_utilization_percent: f64,
+    pub full_blocks: usize,
+}
+
+#[derive(Serialize)]
+pub struct GasLimitReport {
+    pub current_gas_limit: u64,
+    pub utilization: Vec<UtilizationWindow>,
+    pub miners: Vec<MinerVotes>,
+    pub trend_per_hour: f64,
+    pub drift: Drift,
+    pub predicted_gas_limit_1h: u64,
+}
+
+/// Tracks per-block gas limit votes by miner and rolling utilization, and
+/// extrapolates where the gas limit is drifting.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GasLimitTracker {
+    samples: VecDeque<GasLimitSample>,
+}
+
+impl GasLimitTracker {
+    pub fn new() -> Self {
+        Self {
+            samples: VecDeque::new(),
+        }
+    }
+
+    // Returns None when the block was already recorded
+    pub fn add_block<TX>(&mut self, block: &Block<TX>, parent_gas_limit: u64) -> Option<Vote> {
+        let number = block.number.unwrap().as_u64();
+        let hash = block.hash.unwrap();
+        let timestamp = block.timestamp.as_u64();
+        let gas_limit = block.gas_limit.as_u64();
+
+        if self.samples.iter().rev().any(|s| s.hash == hash) {
+            return None;
+        }
+
+        let vote = match gas_limit.cmp(&parent_gas_limit) {
+            std::cmp::Ordering::Greater => Vote::Up,
+            std::cmp::Ordering::Less => Vote::Down,
+            std::cmp::Ordering::Equal => Vote::Hold,
+        };
+
+        // A block at an already seen height replaces the old branch
+        while self.samples.back().is_some_and(|s| s.number >= number) {
+            self.samples.pop_back();
+        }
+
+        self.samples.push_back(GasLimitSample {
+            number,
+            hash,
+            timestamp,
+            miner: block.author.unwrap_or_default(),
+            gas_used: block.gas_used.as_u64(),
+            gas_limit,
+            vote,
+        });
+
+        let longest = WINDOWS.iter().map(|(_, secs)| *secs).max().unwrap_or(0);
+        while self
+            .samples
+            .front()
+            .is_some_and(|s| s.timestamp + longest < timestamp)
+        {
+            self.samples.pop_front();
+        }
+
+        Some(vote)
+    }
+
+    pub fn is_full(gas_used: u64, gas_limit: u64) -> bool {
+        gas_limit > 0 && gas_used as f64 / gas_limit as f64 > FULL_BLOCK_THRESHOLD
+    }
+
+    pub fn report(&self) -> GasLimitReport {
+        let current_gas_limit = self.samples.back().map(|s| s.gas_limit).unwrap_or(0);
+
+        let utilization = WINDOWS
+            .iter()
+            .map(|(name, secs)| {
+                let window: Vec<&GasLimitSample> = self.window(*secs).collect();
+                let avg = if window.is_empty() {
+                    0.0
+                } else {
+                    window.iter().map(|s| s.utilization()).sum::<f64>() / window.len() as f64
+                };
+
+                UtilizationWindow {
+                    window: name.to_string(),
+                    blocks: window.len(),
+                    avg_utilization_percent: avg * 100.0,
+                    full_blocks: window
+                        .iter()
+                        .filter(|s| Self::is_full(s.gas_used, s.gas_limit))
+                        .count(),
+                }
+            })
+            .collect();
+
+        let mut by_miner: HashMap<Address, MinerVotes> = HashMap::new();
+        for sample in &self.samples {
+            let votes = by_miner.entry(sample.miner).or_insert(MinerVotes {
+                miner: sample.miner,
+                up: 0,
+                down: 0,
+                hold: 0,
+                last_gas_limit: 0,
+            });
+            match sample.vote {
+                Vote::Up => votes.up += 1,
+                Vote::Down => votes.down += 1,
+                Vote::Hold => votes.hold += 1,
+            }
+            votes.last_gas_limit = sample.gas_limit;
+        }
+        let mut miners: Vec<MinerVotes> = by_miner.into_values().collect();
+        miners.sort_by_key(|m| std::cmp::Reverse(m.up + m.down + m.hold));
+
+        let trend_per_hour = self.trend_per_hour();
+        let drift = if trend_per_hour.abs() < current_gas_limit as f64 * STABLE_DRIFT_FRACTION {
+            Drift::Stable
+        } else if trend_per_hour > 0.0 {
+            Drift::Up
+        } else {
+            Drift::Down
+        };
+
+        GasLimitReport {
+            current_gas_limit,
+            utilization,
+            miners,
+            trend_per_hour,
+            drift,
+            predicted_gas_limit_1h: (current_gas_limit as f64 + trend_per_hour).max(0.0) as u64,
+        }
+    }
+
+    fn window(&self, secs: u64) -> impl Iterator<Item = &GasLimitSample> {
+        let tip = self.samples.back().map(|s| s.timestamp).unwrap_or(0);
+        self.samples.iter().filter(move |s| s.timestamp + secs >= tip)
+    }
+
+    // Least-squares slope of gas limit over the last hour, in gas per hour
+    fn trend_per_hour(&self) -> f64 {
+        let points: Vec<(f64, f64)> = self
+            .window(WINDOWS[0].1)
+            .map(|s| (s.timestamp as f64, s.gas_limit as f64))
+            .collect();
+        if points.len() < 2 {
+            return 0.0;
+        }
+
+        let n = points.len() as f64;
+        let mean_t = points.iter().map(|(t, _)| t).sum::<f64>() / n;
+        let mean_g = points.iter().map(|(_, g)| g).sum::<f64>() / n;
+
+        let covariance: f64 = points.iter().map(|(t, g)| (t - mean_t) * (g - mean_g)).sum();
+        let variance: f64 = points.iter().map(|(t, _)| (t - mean_t).powi(2)).sum();
+        if variance == 0.0 {
+            return 0.0;
+        }
+
+        covariance / variance * 3_600.0
+    }
+}
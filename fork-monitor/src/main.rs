@@ -1,90 +1,322 @@
 use anyhow::Result;
-use ethers::providers::{Provider, Http, Middleware};
+use ethers::providers::Provider;
 use hyper::{
     service::{make_service_fn, service_fn},
-        Body, Request, Response, Server,
+    Body, Request, Response, Server,
+};
+use mordor_common::checkpoint::Checkpoint;
+use mordor_common::shutdown::Shutdown;
+use mordor_common::telemetry::{self, TracedHttp};
+use prometheus::{Encoder, TextEncoder};
+use std::sync::Arc;
+use tokio::time::{interval, interval_at, Duration, Instant};
+use tracing::{info, error};
+
+mod anomaly;
+mod blockchain;
+mod confirmations;
+mod fork_detector;
+mod gas_limit;
+mod metrics;
+mod peers;
+mod reorg;
+mod sync;
+mod timestamps;
+
+use blockchain::BlockchainMonitor;
+use confirmations::ConfirmationOracle;
+use metrics::Metrics;
+use peers::PeerMonitor;
+use sync::SyncMonitor;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let telemetry = telemetry::init("fork-monitor")?;
+
+    let rpc_url = std::env::var("RPC_URL")
+        .unwrap_or_else(|_| "http://mordor-node:8545".to_string());
+
+    let poll_interval = std::env::var("POLL_INTERVAL_SECS")
+        .unwrap_or_else(|_| "5".to_string())
+        .parse::<u64>()?;
+
+    let peer_poll_interval = std::env::var("PEER_POLL_INTERVAL_SECS")
+        .unwrap_or_else(|_| "15".to_string())
+        .parse::<u64>()?;
+
+    let min_peers = std::env::var("MIN_PEERS")
+        .unwrap_or_else(|_| "3".to_string())
+        .parse::<u64>()?;
+
+    // Defaults to the fork ID in the node's own ENR
+    let fork_id = std::env::var("FORK_ID")
+        .ok()
+        .map(|id| peers::parse_fork_id(&id))
+        .transpose()?;
+
+    // core-geth rejects blocks more than 15s ahead of its own clock
+    let timestamp_tolerance = std::env::var("TIMESTAMP_FUTURE_TOLERANCE_SECS")
+        .unwrap_or_else(|_| "15".to_string())
+        .parse::<u64>()?;
+
+    let confirmation_oracle = ConfirmationOracle::from_env()?;
+
+    let checkpoint_interval = std::env::var("CHECKPOINT_INTERVAL_SECS")
+        .unwrap_or_else(|_| "60".to_string())
+        .parse::<u64>()?
+        .max(1);
+
+    let shutdown_timeout = std::env::var("SHUTDOWN_TIMEOUT_SECS")
+        .unwrap_or_else(|_| "8".to_string())
+        .parse::<u64>()?;
+
+    info!("Starting Mordor Fork Monitor");
+    info!("RPC URL: {}", rpc_url);
+    info!("Poll interval: {}s", poll_interval);
+    info!("Peer poll interval: {}s, minimum peers: {}", peer_poll_interval, min_peers);
+
+    let provider = Provider::new(TracedHttp::new(&rpc_url)?);
+    let metrics = Arc::new(Metrics::new());
+    telemetry.export_metrics(metrics.registry.clone());
+    let monitor = Arc::new(BlockchainMonitor::new(
+        provider.clone(),
+        metrics.clone(),
+        Checkpoint::from_env("/data/fork-monitor-state.json"),
+        timestamp_tolerance,
+        confirmation_oracle,
+    ));
+    let peer_monitor = Arc::new(PeerMonitor::new(provider.clone(), metrics.clone(), min_peers, fork_id));
+    let sync_monitor = Arc::new(SyncMonitor::new(provider, metrics.clone()));
+
+    // Docker sends SIGKILL 10s after SIGTERM, so finish before that
+    let shutdown = Shutdown::listen(Duration::from_secs(shutdown_timeout))?;
+
+    // Start monitoring loop
+    let monitor_clone = monitor.clone();
+    let shutdown_clone = shutdown.clone();
+    let poll_task = tokio::spawn(async move {
+        let mut interval = interval(Duration::from_secs(poll_interval));
+        loop {
+            tokio::select! {
+                biased;
+                _ = shutdown_clone.clone().requested() => break,
+                _ = interval.tick() => {}
+            }
+            if let Err(e) = monitor_clone.poll().await {
+                error!("Monitoring error: {}", e);
+            }
+        }
+    });
+
+    // Saved on its own interval rather than after every poll, since the
+    // state holds a day of gas limit samples
+    let monitor_clone = monitor.clone();
+    let shutdown_clone = shutdown.clone();
+    let checkpoint_task = tokio::spawn(async move {
+        let period = Duration::from_secs(checkpoint_interval);
+        let mut interval = interval_at(Instant::now() + period, period);
+        loop {
+            tokio::select! {
+                biased;
+                _ = shutdown_clone.clone().requested() => break,
+                _ = interval.tick() => {}
+            }
+            monitor_clone.save_checkpoint().await;
+        }
+    });
+
+    // Start sync progress loop
+    let sync_monitor_clone = sync_monitor.clone();
+    let shutdown_clone = shutdown.clone();
+    let sync_task = tokio::spawn(async move {
+        let mut interval = interval(Duration::from_secs(poll_interval));
+        loop {
+            tokio::select! {
+                biased;
+                _ = shutdown_clone.clone().requested() => break,
+                _ = interval.tick() => {}
+            }
+            if let Err(e) = sync_monitor_clone.poll().await {
+                error!("Sync monitoring error: {}", e);
+            }
+        }
+    });
+
+    // Start peer monitoring loop
+    let peer_monitor_clone = peer_monitor.clone();
+    let shutdown_clone = shutdown.clone();
+    let peer_task = tokio::spawn(async move {
+        let mut interval = interval(Duration::from_secs(peer_poll_interval));
+        loop {
+            tokio::select! {
+                biased;
+                _ = shutdown_clone.clone().requested() => break,
+                _ = interval.tick() => {}
+            }
+            if let Err(e) = peer_monitor_clone.poll().await {
+                error!("Peer monitoring error: {}", e);
+            }
+        }
+    });
+
+    // Start metrics HTTP server
+    let metrics_clone = metrics.clone();
+    let monitor_clone = monitor.clone();
+    let make_svc = make_service_fn(move |_| {
+        let metrics = metrics_clone.clone();
+        let monitor = monitor.clone();
+        let peer_monitor = peer_monitor.clone();
+        let sync_monitor = sync_monitor.clone();
+        async move {
+            Ok::<_, hyper::Error>(service_fn(move |req| {
+                serve_metrics(req, metrics.clone(), monitor.clone(), peer_monitor.clone(), sync_monitor.clone())
+            }))
+        }
+    });
+
+    let addr = ([0, 0, 0, 0], 9090).into();
+    let server = Server::bind(&addr)
+        .serve(make_svc)
+        .with_graceful_shutdown(shutdown.requested());
+
+    info!("Metrics server listening on http://{}", addr);
+    // Returns once shutdown is requested and in-flight responses are sent
+    server.await?;
+
+    for task in [poll_task, checkpoint_task, sync_task, peer_task] {
+        task.await?;
+    }
+    monitor_clone.save_checkpoint().await;
+
+    telemetry.shutdown().await;
+    info!("Shutdown complete");
+    Ok(())
+}
+
+async fn serve_metrics(
+    req: Request<Body>,
+    metrics: Arc<Metrics>,
+    monitor: Arc<BlockchainMonitor>,
+    peer_monitor: Arc<PeerMonitor>,
+    sync_monitor: Arc<SyncMonitor>,
+) -> Result<Response<Body>, hyper::Error> {
+    if req.uri().path() == "/metrics" {
+        let encoder = TextEncoder::new();
+        let metric_families = metrics.registry.gather();
+        let mut buffer = vec![];
+        encoder.encode(&metric_families, &mut buffer).unwrap();
+
+        Ok(Response::new(Body::from(buffer)))
+    } else if req.uri().path() == "/gas-limit" {
+        let report = monitor.gas_limit_report().await;
+        let body = serde_json::to_vec(&report).unwrap();
+
+        Ok(Response::builder()
+            .header("Content-Type", "application/json")
+            .body(Body::from(body))
+            .unwrap())
+    } else if req.uri().path() == "/forks" {
+        let events = monitor.fork_events().await;
+        let body = serde_json::to_vec(&events).unwrap();
+
+        Ok(Response::builder()
+            .header("Content-Type", "application/json")
+            .body(Body::from(body))
+            .unwrap())
+    } else if req.uri().path() == "/anomalies" {
+        let report = monitor.anomaly_report().await;
+        let body = serde_json::to_vec(&report).unwrap();
+
+        Ok(Response::builder()
+            .header("Content-Type", "application/json")
+            .body(Body::from(body))
+            .unwrap())
+    } else if req.uri().path() == "/confirmations" {
+        let param = |name: &str| {
+            req.uri()
+                .query()
+                .and_then(|query| query.split('&').find_map(|pair| pair.strip_prefix(name)?.strip_prefix('=')))
+                .map(|value| value.parse::<f64>())
+                .transpose()
         };
-        use prometheus::{Encoder, TextEncoder};
-        use std::sync::Arc;
-        use tokio::time::{interval, Duration};
-        use tracing::{info, error};
-
-        mod blockchain;
-        mod fork_detector;
-        mod metrics;
-
-        use blockchain::BlockchainMonitor;
-        use metrics::Metrics;
-
-        #[tokio::main]
-        async fn main() -> Result<()> {
-            tracing_subscriber::fmt::init();
-
-                let rpc_url = std::env::var("RPC_URL")
-                        .unwrap_or_else(|_| "http://mordor-node:8545".to_string());
-                            
-                                let poll_interval = std::env::var("POLL_INTERVAL_SECS")
-                                        .unwrap_or_else(|_| "5".to_string())
-                                                .parse::<u64>()?;
-
-                                                    info!("Starting Mordor Fork Monitor");
-                                                        info!("RPC URL: {}", rpc_url);
-                                                            info!("Poll interval: {}s", poll_interval);
-
-                                                                let provider = Provider::<Http>::try_from(&rpc_url)?;
-                                                                    let metrics = Arc::new(Metrics::new());
-                                                                        let monitor = Arc::new(BlockchainMonitor::new(provider, metrics.clone()));
-
-                                                                            // Start monitoring loop
-                                                                                let monitor_clone = monitor.clone();
-                                                                                    tokio::spawn(async move {
-                                                                                            let mut interval = interval(Duration::from_secs(poll_interval));
-                                                                                                    loop {
-                                                                                                                interval.tick().await;
-                                                                                                                            if let Err(e) = monitor_clone.poll().await {
-                                                                                                                                            error!("Monitoring error: {}", e);
-                                                                                                                                                        }
-                                                                                                                                                                }
-                                                                                                                                                                    });
-
-                                                                                                                                                                        // Start metrics HTTP server
-                                                                                                                                                                            let metrics_clone = metrics.clone();
-                                                                                                                                                                                let make_svc = make_service_fn(move |_| {
-                                                                                                                                                                                        let metrics = metrics_clone.clone();
-                                                                                                                                                                                                async move {
-                                                                                                                                                                                                            Ok::<_, hyper::Error>(service_fn(move |req| {
-                                                                                                                                                                                                                            serve_metrics(req, metrics.clone())
-                                                                                                                                                                                                                                        }))
-                                                                                                                                                                                                                                                }
-                                                                                                                                                                                                                                                    });
-
-                                                                                                                                                                                                                                                        let addr = ([0, 0, 0, 0], 9090).into();
-                                                                                                                                                                                                                                                            let server = Server::bind(&addr).serve(make_svc);
-                                                                                                                                                                                                                                                                
-                                                                                                                                                                                                                                                                    info!("Metrics server listening on http://{}", addr);
-                                                                                                                                                                                                                                                                        server.await?;
-
-                                                                                                                                                                                                                                                                            Ok(())
-                                                                                                                                                                                                                                                                            }
-
-                                                                                                                                                                                                                                                                            async fn serve_metrics(
-                                                                                                                                                                                                                                                                                req: Request<Body>,
-                                                                                                                                                                                                                                                                                    metrics: Arc<Metrics>,
-                                                                                                                                                                                                                                                                                    ) -> Result<Response<Body>, hyper::Error> {
-                                                                                                                                                                                                                                                                                        if req.uri().path() == "/metrics" {
-                                                                                                                                                                                                                                                                                                let encoder = TextEncoder::new();
-                                                                                                                                                                                                                                                                                                        let metric_families = metrics.registry.gather();
-                                                                                                                                                                                                                                                                                                                let mut buffer = vec![];
-                                                                                                                                                                                                                                                                                                                        encoder.encode(&metric_families, &mut buffer).unwrap();
-                                                                                                                                                                                                                                                                                                                                
-                                                                                                                                                                                                                                                                                                                                        Ok(Response::new(Body::from(buffer)))
-                                                                                                                                                                                                                                                                                                                                            } else if req.uri().path() == "/health" {
-                                                                                                                                                                                                                                                                                                                                                    Ok(Response::new(Body::from("OK")))
-                                                                                                                                                                                                                                                                                                                                                        } else {
-                                                                                                                                                                                                                                                                                                                                                                Ok(Response::builder()
-                                                                                                                                                                                                                                                                                                                                                                            .status(404)
-                                                                                                                                                                                                                                                                                                                                                                                        .body(Body::from("Not Found"))
-                                                                                                                                                                                                                                                                                                                                                                                                    .unwrap())
-                                                                                                                                                                                                                                                                                                                                                                                                        }
-                                                                                                                                                                                                                                                                                                                                                                                                        }
-                                                                                                                                                                                                                                                                                                                                                                                                        
\ No newline at end of file
+        let (target_risk, attacker_share) = match (param("risk"), param("attacker_share")) {
+            (Ok(target_risk), Ok(attacker_share)) => (target_risk, attacker_share),
+            _ => {
+                return Ok(Response::builder()
+                    .status(400)
+                    .body(Body::from("risk and attacker_share must be numbers"))
+                    .unwrap())
+            }
+        };
+        if let Err(e) = ConfirmationOracle::validate(attacker_share, target_risk) {
+            return Ok(Response::builder()
+                .status(400)
+                .body(Body::from(e.to_string()))
+                .unwrap());
+        }
+
+        let report = monitor.confirmation_report(target_risk, attacker_share).await;
+        let body = serde_json::to_vec(&report).unwrap();
+
+        Ok(Response::builder()
+            .header("Content-Type", "application/json")
+            .body(Body::from(body))
+            .unwrap())
+    } else if req.uri().path() == "/reorgs" {
+        let events = monitor.reorg_events().await;
+        let body = serde_json::to_vec(&events).unwrap();
+
+        Ok(Response::builder()
+            .header("Content-Type", "application/json")
+            .body(Body::from(body))
+            .unwrap())
+    } else if req.uri().path() == "/timestamps" {
+        let report = monitor.timestamp_report().await;
+        let body = serde_json::to_vec(&report).unwrap();
+
+        Ok(Response::builder()
+            .header("Content-Type", "application/json")
+            .body(Body::from(body))
+            .unwrap())
+    } else if req.uri().path() == "/peers" {
+        // Not available until the first peer poll has completed
+        let report = match peer_monitor.report().await {
+            Some(report) => report,
+            None => {
+                return Ok(Response::builder()
+                    .status(503)
+                    .body(Body::from("Peer data not available yet"))
+                    .unwrap())
+            }
+        };
+        let body = serde_json::to_vec(&report).unwrap();
+
+        Ok(Response::builder()
+            .header("Content-Type", "application/json")
+            .body(Body::from(body))
+            .unwrap())
+    } else if req.uri().path() == "/sync" {
+        let report = match sync_monitor.report().await {
+            Some(report) => report,
+            None => {
+                return Ok(Response::builder()
+                    .status(503)
+                    .body(Body::from("Sync data not available yet"))
+                    .unwrap())
+            }
+        };
+        let body = serde_json::to_vec(&report).unwrap();
+
+        Ok(Response::builder()
+            .header("Content-Type", "application/json")
+            .body(Body::from(body))
+            .unwrap())
+    } else if req.uri().path() == "/health" {
+        Ok(Response::new(Body::from("OK")))
+    } else {
+        Ok(Response::builder()
+                .status(404)
+                .body(Body::from("Not Found"))
+                .unwrap())
+    }
+}
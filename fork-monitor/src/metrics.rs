@@ -1,162 +1,514 @@
 use prometheus::{
-        Counter, Histogram, HistogramOpts, IntCounter, IntGauge, Opts, Registry,
-        };
-
-        pub struct Metrics {
-            pub registry: Registry,
-                
-                    // Block metrics
-                        block_height: IntGauge,
-                            block_timestamp: IntGauge,
-                                block_gas_used: IntGauge,
-                                    block_gas_limit: IntGauge,
-                                        block_time: Histogram,
-                                            block_difficulty: Histogram,
-                                                
-                                                    // Transaction metrics
-                                                        transaction_count: IntGauge,
-                                                            
-                                                                // Fork metrics
-                                                                    fork_count: IntCounter,
-                                                                        fork_depth: Histogram,
-                                                                            active_forks: IntGauge,
-                                                                                missed_blocks: IntCounter,
-                                                                                }
-
-                                                                                impl Metrics {
-                                                                                    pub fn new() -> Self {
-                                                                                            let registry = Registry::new();
-
-                                                                                                    let block_height = IntGauge::with_opts(
-                                                                                                                Opts::new("mordor_block_height", "Current block height")
-                                                                                                                                .namespace("etc"),
-                                                                                                                                        ).unwrap();
-
-                                                                                                                                                let block_timestamp = IntGauge::with_opts(
-                                                                                                                                                            Opts::new("mordor_block_timestamp", "Block timestamp")
-                                                                                                                                                                            .namespace("etc"),
-                                                                                                                                                                                    ).unwrap();
-
-                                                                                                                                                                                            let block_gas_used = IntGauge::with_opts(
-                                                                                                                                                                                                        Opts::new("mordor_block_gas_used", "Gas used in current block")
-                                                                                                                                                                                                                        .namespace("etc"),
-                                                                                                                                                                                                                                ).unwrap();
-
-                                                                                                                                                                                                                                        let block_gas_limit = IntGauge::with_opts(
-                                                                                                                                                                                                                                                    Opts::new("mordor_block_gas_limit", "Block gas limit")
-                                                                                                                                                                                                                                                                    .namespace("etc"),
-                                                                                                                                                                                                                                                                            ).unwrap();
-
-                                                                                                                                                                                                                                                                                    let block_time = Histogram::with_opts(
-                                                                                                                                                                                                                                                                                                HistogramOpts::new("mordor_block_time_seconds", "Time between blocks")
-                                                                                                                                                                                                                                                                                                                .namespace("etc")
-                                                                                                                                                                                                                                                                                                                                .buckets(vec![1.0, 5.0, 10.0, 13.0, 15.0, 20.0, 30.0, 60.0]),
-                                                                                                                                                                                                                                                                                                                                        ).unwrap();
-
-                                                                                                                                                                                                                                                                                                                                                let block_difficulty = Histogram::with_opts(
-                                                                                                                                                                                                                                                                                                                                                            HistogramOpts::new("mordor_block_difficulty", "Block difficulty")
-                                                                                                                                                                                                                                                                                                                                                                            .namespace("etc")
-                                                                                                                                                                                                                                                                                                                                                                                            .buckets(prometheus::exponential_buckets(1e9, 2.0, 20).unwrap()),
-                                                                                                                                                                                                                                                                                                                                                                                                    ).unwrap();
-
-                                                                                                                                                                                                                                                                                                                                                                                                            let transaction_count = IntGauge::with_opts(
-                                                                                                                                                                                                                                                                                                                                                                                                                        Opts::new("mordor_transaction_count", "Transactions in current block")
-                                                                                                                                                                                                                                                                                                                                                                                                                                        .namespace("etc"),
-                                                                                                                                                                                                                                                                                                                                                                                                                                                ).unwrap();
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                        let fork_count = IntCounter::with_opts(
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                    Opts::new("mordor_fork_total", "Total number of forks detected")
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    .namespace("etc"),
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            ).unwrap();
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    let fork_depth = Histogram::with_opts(
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                HistogramOpts::new("mordor_fork_depth", "Fork reorganization depth")
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                .namespace("etc")
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                .buckets(vec![1.0, 2.0, 5.0, 10.0, 50.0, 100.0, 500.0, 1000.0]),
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        ).unwrap();
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                let active_forks = IntGauge::with_opts(
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            Opts::new("mordor_active_forks", "Number of currently active forks")
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            .namespace("etc"),
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    ).unwrap();
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            let missed_blocks = IntCounter::with_opts(
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        Opts::new("mordor_missed_blocks_total", "Total missed blocks")
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        .namespace("etc"),
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                ).unwrap();
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        // Register all metrics
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                registry.register(Box::new(block_height.clone())).unwrap();
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        registry.register(Box::new(block_timestamp.clone())).unwrap();
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                registry.register(Box::new(block_gas_used.clone())).unwrap();
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        registry.register(Box::new(block_gas_limit.clone())).unwrap();
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                registry.register(Box::new(block_time.clone())).unwrap();
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        registry.register(Box::new(block_difficulty.clone())).unwrap();
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                registry.register(Box::new(transaction_count.clone())).unwrap();
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        registry.register(Box::new(fork_count.clone())).unwrap();
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                registry.register(Box::new(fork_depth.clone())).unwrap();
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        registry.register(Box::new(active_forks.clone())).unwrap();
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                registry.register(Box::new(missed_blocks.clone())).unwrap();
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        Self {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    registry,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                block_height,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            block_timestamp,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        block_gas_used,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    block_gas_limit,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                block_time,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            block_difficulty,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        transaction_count,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    fork_count,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                fork_depth,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                            active_forks,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        missed_blocks,
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                }
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    }
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        pub fn set_block_height(&self, height: u64) {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                self.block_height.set(height as i64);
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    }
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        pub fn set_block_timestamp(&self, timestamp: u64) {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                self.block_timestamp.set(timestamp as i64);
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    }
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        pub fn set_block_gas_used(&self, gas: u64) {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                self.block_gas_used.set(gas as i64);
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    }
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        pub fn set_block_gas_limit(&self, limit: u64) {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                self.block_gas_limit.set(limit as i64);
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    }
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        pub fn observe_block_time(&self, seconds: f64) {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                self.block_time.observe(seconds);
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    }
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        pub fn observe_block_difficulty(&self, difficulty: f64) {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                self.block_difficulty.observe(difficulty);
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    }
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        pub fn set_transaction_count(&self, count: u64) {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                self.transaction_count.set(count as i64);
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    }
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        pub fn increment_fork_count(&self) {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                self.fork_count.inc();
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    }
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        pub fn observe_fork_depth(&self, depth: f64) {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                self.fork_depth.observe(depth);
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    }
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        pub fn set_active_forks(&self, count: i64) {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                self.active_forks.set(count);
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    }
-
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                        pub fn increment_missed_blocks(&self, count: u64) {
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                self.missed_blocks.inc_by(count);
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    }
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    }
-                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                                    
-}
\ No newline at end of file
+    Gauge, GaugeVec, Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+    Opts, Registry,
+};
+use std::collections::BTreeMap;
+
+pub struct Metrics {
+    pub registry: Registry,
+
+    // Block metrics
+    block_height: IntGauge,
+    block_timestamp: IntGauge,
+    block_gas_used: IntGauge,
+    block_gas_limit: IntGauge,
+    block_time: Histogram,
+    block_difficulty: Histogram,
+
+    // Transaction metrics
+    transaction_count: IntGauge,
+
+    // Fork metrics
+    fork_count: IntCounter,
+    fork_depth: Histogram,
+    active_forks: IntGauge,
+    missed_blocks: IntCounter,
+
+    // Anomaly metrics
+    anomaly_score: GaugeVec,
+    anomalies: IntCounterVec,
+    hashrate_estimate: GaugeVec,
+    timestamp_violations: IntCounterVec,
+    reorged_transactions: IntCounterVec,
+    recommended_confirmations: IntGauge,
+
+    // Gas limit metrics
+    gas_limit_votes: IntCounterVec,
+    gas_utilization_avg: GaugeVec,
+    full_blocks: IntCounter,
+    full_blocks_window: IntGaugeVec,
+    gas_limit_trend: Gauge,
+    gas_limit_drift: IntGauge,
+
+    // Peer metrics
+    peer_count: IntGauge,
+    peer_count_low: IntGauge,
+    peers_by_direction: IntGaugeVec,
+    peers_by_client: IntGaugeVec,
+    peers_by_fork_id: IntGaugeVec,
+    fork_id_match_ratio: Gauge,
+    peer_connects: IntCounter,
+    peer_disconnects: IntCounter,
+
+    // Sync metrics
+    sync_syncing: IntGauge,
+    sync_current_block: IntGauge,
+    sync_highest_block: IntGauge,
+    sync_progress: Gauge,
+    sync_known_states: IntGauge,
+    sync_pulled_states: IntGauge,
+    sync_blocks_per_second: Gauge,
+    sync_eta: IntGauge,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let block_height = IntGauge::with_opts(
+            Opts::new("mordor_block_height", "Current block height")
+                .namespace("etc"),
+        ).unwrap();
+
+        let block_timestamp = IntGauge::with_opts(
+            Opts::new("mordor_block_timestamp", "Block timestamp")
+                .namespace("etc"),
+        ).unwrap();
+
+        let block_gas_used = IntGauge::with_opts(
+            Opts::new("mordor_block_gas_used", "Gas used in current block")
+                .namespace("etc"),
+        ).unwrap();
+
+        let block_gas_limit = IntGauge::with_opts(
+            Opts::new("mordor_block_gas_limit", "Block gas limit")
+                .namespace("etc"),
+        ).unwrap();
+
+        let block_time = Histogram::with_opts(
+            HistogramOpts::new("mordor_block_time_seconds", "Time between blocks")
+                .namespace("etc")
+                .buckets(vec![1.0, 5.0, 10.0, 13.0, 15.0, 20.0, 30.0, 60.0]),
+        ).unwrap();
+
+        let block_difficulty = Histogram::with_opts(
+            HistogramOpts::new("mordor_block_difficulty", "Block difficulty")
+                .namespace("etc")
+                .buckets(prometheus::exponential_buckets(1e9, 2.0, 20).unwrap()),
+        ).unwrap();
+
+        let transaction_count = IntGauge::with_opts(
+            Opts::new("mordor_transaction_count", "Transactions in current block")
+                .namespace("etc"),
+        ).unwrap();
+
+        let fork_count = IntCounter::with_opts(
+            Opts::new("mordor_fork_total", "Total number of forks detected")
+                .namespace("etc"),
+        ).unwrap();
+
+        let fork_depth = Histogram::with_opts(
+            HistogramOpts::new("mordor_fork_depth", "Fork reorganization depth")
+                .namespace("etc")
+                .buckets(vec![1.0, 2.0, 5.0, 10.0, 50.0, 100.0, 500.0, 1000.0]),
+        ).unwrap();
+
+        let active_forks = IntGauge::with_opts(
+            Opts::new("mordor_active_forks", "Number of currently active forks")
+                .namespace("etc"),
+        ).unwrap();
+
+        let missed_blocks = IntCounter::with_opts(
+            Opts::new("mordor_missed_blocks_total", "Total missed blocks")
+                .namespace("etc"),
+        ).unwrap();
+
+        let anomaly_score = GaugeVec::new(
+            Opts::new("mordor_anomaly_score", "Block time/hashrate anomaly score by kind (1 = alert threshold)")
+                .namespace("etc"),
+            &["kind"],
+        ).unwrap();
+
+        let anomalies = IntCounterVec::new(
+            Opts::new("mordor_anomalies_total", "Anomalies detected by kind")
+                .namespace("etc"),
+            &["kind"],
+        ).unwrap();
+
+        let hashrate_estimate = GaugeVec::new(
+            Opts::new("mordor_hashrate_estimate", "Network hashrate estimated from difficulty and block times (H/s)")
+                .namespace("etc"),
+            &["window"],
+        ).unwrap();
+
+        let timestamp_violations = IntCounterVec::new(
+            Opts::new("mordor_timestamp_violations_total", "Block timestamp violations (future/non_monotonic/min_interval) by miner")
+                .namespace("etc"),
+            &["kind", "miner"],
+        ).unwrap();
+
+        let reorged_transactions = IntCounterVec::new(
+            Opts::new("mordor_reorged_transactions_total", "Transactions in orphaned blocks by outcome (dropped/reincluded/replaced)")
+                .namespace("etc"),
+            &["kind"],
+        ).unwrap();
+
+        let recommended_confirmations = IntGauge::with_opts(
+            Opts::new("mordor_recommended_confirmations", "Confirmations recommended for the configured target reorg risk (-1 when none is enough)")
+                .namespace("etc"),
+        ).unwrap();
+
+        let gas_limit_votes = IntCounterVec::new(
+            Opts::new("mordor_gas_limit_votes_total", "Gas limit votes (up/down/hold) by miner")
+                .namespace("etc"),
+            &["miner", "direction"],
+        ).unwrap();
+
+        let gas_utilization_avg = GaugeVec::new(
+            Opts::new("mordor_gas_utilization_avg_percent", "Average block gas utilization over a rolling window")
+                .namespace("etc"),
+            &["window"],
+        ).unwrap();
+
+        let full_blocks = IntCounter::with_opts(
+            Opts::new("mordor_full_blocks_total", "Total blocks above 95% gas utilization")
+                .namespace("etc"),
+        ).unwrap();
+
+        let full_blocks_window = IntGaugeVec::new(
+            Opts::new("mordor_full_blocks", "Blocks above 95% gas utilization over a rolling window")
+                .namespace("etc"),
+            &["window"],
+        ).unwrap();
+
+        let gas_limit_trend = Gauge::with_opts(
+            Opts::new("mordor_gas_limit_trend_per_hour", "Gas limit drift over the last hour (gas/hour)")
+                .namespace("etc"),
+        ).unwrap();
+
+        let gas_limit_drift = IntGauge::with_opts(
+            Opts::new("mordor_gas_limit_drift_direction", "Predicted gas limit direction (1 up, 0 stable, -1 down)")
+                .namespace("etc"),
+        ).unwrap();
+
+        let peer_count = IntGauge::with_opts(
+            Opts::new("mordor_peer_count", "Connected peers (net_peerCount)")
+                .namespace("etc"),
+        ).unwrap();
+
+        let peer_count_low = IntGauge::with_opts(
+            Opts::new("mordor_peer_count_low", "1 while the peer count is below the configured minimum")
+                .namespace("etc"),
+        ).unwrap();
+
+        let peers_by_direction = IntGaugeVec::new(
+            Opts::new("mordor_peers", "Connected peers by direction (inbound/outbound)")
+                .namespace("etc"),
+            &["direction"],
+        ).unwrap();
+
+        let peers_by_client = IntGaugeVec::new(
+            Opts::new("mordor_peers_by_client", "Connected peers by client version")
+                .namespace("etc"),
+            &["client"],
+        ).unwrap();
+
+        let peers_by_fork_id = IntGaugeVec::new(
+            Opts::new("mordor_peers_by_fork_id", "Connected peers by fork ID (match/mismatch/unknown)")
+                .namespace("etc"),
+            &["status"],
+        ).unwrap();
+
+        let fork_id_match_ratio = Gauge::with_opts(
+            Opts::new("mordor_peers_fork_id_match_ratio", "Fraction of peers with a known fork ID advertising the Mordor fork ID")
+                .namespace("etc"),
+        ).unwrap();
+
+        let peer_connects = IntCounter::with_opts(
+            Opts::new("mordor_peer_connects_total", "Peers seen connecting")
+                .namespace("etc"),
+        ).unwrap();
+
+        let peer_disconnects = IntCounter::with_opts(
+            Opts::new("mordor_peer_disconnects_total", "Peers seen disconnecting")
+                .namespace("etc"),
+        ).unwrap();
+
+        let sync_syncing = IntGauge::with_opts(
+            Opts::new("mordor_sync_syncing", "1 while the node is syncing")
+                .namespace("etc"),
+        ).unwrap();
+
+        let sync_current_block = IntGauge::with_opts(
+            Opts::new("mordor_sync_current_block", "Current block while syncing")
+                .namespace("etc"),
+        ).unwrap();
+
+        let sync_highest_block = IntGauge::with_opts(
+            Opts::new("mordor_sync_highest_block", "Highest known block")
+                .namespace("etc"),
+        ).unwrap();
+
+        let sync_progress = Gauge::with_opts(
+            Opts::new("mordor_sync_progress_percent", "Current block as a percentage of the highest known block")
+                .namespace("etc"),
+        ).unwrap();
+
+        let sync_known_states = IntGauge::with_opts(
+            Opts::new("mordor_sync_known_states", "Known state entries during state sync")
+                .namespace("etc"),
+        ).unwrap();
+
+        let sync_pulled_states = IntGauge::with_opts(
+            Opts::new("mordor_sync_pulled_states", "Pulled state entries during state sync")
+                .namespace("etc"),
+        ).unwrap();
+
+        let sync_blocks_per_second = Gauge::with_opts(
+            Opts::new("mordor_sync_blocks_per_second", "Blocks imported per second over a 5 minute window")
+                .namespace("etc"),
+        ).unwrap();
+
+        let sync_eta = IntGauge::with_opts(
+            Opts::new("mordor_sync_eta_seconds", "Estimated seconds until the node is synced (-1 when unknown)")
+                .namespace("etc"),
+        ).unwrap();
+
+        // Register all metrics
+        registry.register(Box::new(block_height.clone())).unwrap();
+        registry.register(Box::new(block_timestamp.clone())).unwrap();
+        registry.register(Box::new(block_gas_used.clone())).unwrap();
+        registry.register(Box::new(block_gas_limit.clone())).unwrap();
+        registry.register(Box::new(block_time.clone())).unwrap();
+        registry.register(Box::new(block_difficulty.clone())).unwrap();
+        registry.register(Box::new(transaction_count.clone())).unwrap();
+        registry.register(Box::new(fork_count.clone())).unwrap();
+        registry.register(Box::new(fork_depth.clone())).unwrap();
+        registry.register(Box::new(active_forks.clone())).unwrap();
+        registry.register(Box::new(missed_blocks.clone())).unwrap();
+        registry.register(Box::new(anomaly_score.clone())).unwrap();
+        registry.register(Box::new(anomalies.clone())).unwrap();
+        registry.register(Box::new(hashrate_estimate.clone())).unwrap();
+        registry.register(Box::new(timestamp_violations.clone())).unwrap();
+        registry.register(Box::new(reorged_transactions.clone())).unwrap();
+        registry.register(Box::new(recommended_confirmations.clone())).unwrap();
+        registry.register(Box::new(gas_limit_votes.clone())).unwrap();
+        registry.register(Box::new(gas_utilization_avg.clone())).unwrap();
+        registry.register(Box::new(full_blocks.clone())).unwrap();
+        registry.register(Box::new(full_blocks_window.clone())).unwrap();
+        registry.register(Box::new(gas_limit_trend.clone())).unwrap();
+        registry.register(Box::new(gas_limit_drift.clone())).unwrap();
+        registry.register(Box::new(peer_count.clone())).unwrap();
+        registry.register(Box::new(peer_count_low.clone())).unwrap();
+        registry.register(Box::new(peers_by_direction.clone())).unwrap();
+        registry.register(Box::new(peers_by_client.clone())).unwrap();
+        registry.register(Box::new(peers_by_fork_id.clone())).unwrap();
+        registry.register(Box::new(fork_id_match_ratio.clone())).unwrap();
+        registry.register(Box::new(peer_connects.clone())).unwrap();
+        registry.register(Box::new(peer_disconnects.clone())).unwrap();
+        registry.register(Box::new(sync_syncing.clone())).unwrap();
+        registry.register(Box::new(sync_current_block.clone())).unwrap();
+        registry.register(Box::new(sync_highest_block.clone())).unwrap();
+        registry.register(Box::new(sync_progress.clone())).unwrap();
+        registry.register(Box::new(sync_known_states.clone())).unwrap();
+        registry.register(Box::new(sync_pulled_states.clone())).unwrap();
+        registry.register(Box::new(sync_blocks_per_second.clone())).unwrap();
+        registry.register(Box::new(sync_eta.clone())).unwrap();
+
+        Self {
+            registry,
+            block_height,
+            block_timestamp,
+            block_gas_used,
+            block_gas_limit,
+            block_time,
+            block_difficulty,
+            transaction_count,
+            fork_count,
+            fork_depth,
+            active_forks,
+            missed_blocks,
+            anomaly_score,
+            anomalies,
+            hashrate_estimate,
+            timestamp_violations,
+            reorged_transactions,
+            recommended_confirmations,
+            gas_limit_votes,
+            gas_utilization_avg,
+            full_blocks,
+            full_blocks_window,
+            gas_limit_trend,
+            gas_limit_drift,
+            peer_count,
+            peer_count_low,
+            peers_by_direction,
+            peers_by_client,
+            peers_by_fork_id,
+            fork_id_match_ratio,
+            peer_connects,
+            peer_disconnects,
+            sync_syncing,
+            sync_current_block,
+            sync_highest_block,
+            sync_progress,
+            sync_known_states,
+            sync_pulled_states,
+            sync_blocks_per_second,
+            sync_eta,
+        }
+    }
+
+    pub fn set_block_height(&self, height: u64) {
+        self.block_height.set(height as i64);
+    }
+
+    pub fn set_block_timestamp(&self, timestamp: u64) {
+        self.block_timestamp.set(timestamp as i64);
+    }
+
+    pub fn set_block_gas_used(&self, gas: u64) {
+        self.block_gas_used.set(gas as i64);
+    }
+
+    pub fn set_block_gas_limit(&self, limit: u64) {
+        self.block_gas_limit.set(limit as i64);
+    }
+
+    pub fn observe_block_time(&self, seconds: f64) {
+        self.block_time.observe(seconds);
+    }
+
+    pub fn observe_block_difficulty(&self, difficulty: f64) {
+        self.block_difficulty.observe(difficulty);
+    }
+
+    pub fn set_transaction_count(&self, count: u64) {
+        self.transaction_count.set(count as i64);
+    }
+
+    pub fn increment_fork_count(&self) {
+        self.fork_count.inc();
+    }
+
+    pub fn observe_fork_depth(&self, depth: f64) {
+        self.fork_depth.observe(depth);
+    }
+
+    pub fn set_active_forks(&self, count: i64) {
+        self.active_forks.set(count);
+    }
+
+    pub fn increment_missed_blocks(&self, count: u64) {
+        self.missed_blocks.inc_by(count);
+    }
+
+    pub fn set_anomaly_score(&self, kind: &str, score: f64) {
+        self.anomaly_score.with_label_values(&[kind]).set(score);
+    }
+
+    pub fn increment_anomalies(&self, kind: &str) {
+        self.anomalies.with_label_values(&[kind]).inc();
+    }
+
+    pub fn set_hashrate_estimate(&self, window: &str, hashes_per_second: f64) {
+        self.hashrate_estimate.with_label_values(&[window]).set(hashes_per_second);
+    }
+
+    pub fn increment_timestamp_violations(&self, kind: &str, miner: &str) {
+        self.timestamp_violations.with_label_values(&[kind, miner]).inc();
+    }
+
+    pub fn increment_reorged_transactions(&self, kind: &str) {
+        self.reorged_transactions.with_label_values(&[kind]).inc();
+    }
+
+    pub fn set_recommended_confirmations(&self, confirmations: i64) {
+        self.recommended_confirmations.set(confirmations);
+    }
+
+    pub fn increment_gas_limit_vote(&self, miner: &str, direction: &str) {
+        self.gas_limit_votes.with_label_values(&[miner, direction]).inc();
+    }
+
+    pub fn set_gas_utilization_avg(&self, window: &str, percent: f64) {
+        self.gas_utilization_avg.with_label_values(&[window]).set(percent);
+    }
+
+    pub fn increment_full_blocks(&self) {
+        self.full_blocks.inc();
+    }
+
+    pub fn set_full_blocks_window(&self, window: &str, count: usize) {
+        self.full_blocks_window.with_label_values(&[window]).set(count as i64);
+    }
+
+    pub fn set_gas_limit_trend(&self, gas_per_hour: f64) {
+        self.gas_limit_trend.set(gas_per_hour);
+    }
+
+    pub fn set_gas_limit_drift(&self, direction: i64) {
+        self.gas_limit_drift.set(direction);
+    }
+
+    pub fn set_peer_count(&self, count: u64) {
+        self.peer_count.set(count as i64);
+    }
+
+    pub fn set_peer_count_low(&self, low: bool) {
+        self.peer_count_low.set(low as i64);
+    }
+
+    pub fn set_peers_by_direction(&self, inbound: usize, outbound: usize) {
+        self.peers_by_direction.with_label_values(&["inbound"]).set(inbound as i64);
+        self.peers_by_direction.with_label_values(&["outbound"]).set(outbound as i64);
+    }
+
+    pub fn set_peers_by_client(&self, clients: &BTreeMap<String, usize>) {
+        // Drop versions that are no longer connected
+        self.peers_by_client.reset();
+        for (client, count) in clients {
+            self.peers_by_client.with_label_values(&[client]).set(*count as i64);
+        }
+    }
+
+    pub fn set_peers_by_fork_id(&self, status: &str, count: usize) {
+        self.peers_by_fork_id.with_label_values(&[status]).set(count as i64);
+    }
+
+    pub fn set_fork_id_match_ratio(&self, ratio: f64) {
+        self.fork_id_match_ratio.set(ratio);
+    }
+
+    // Without admin_peers only the count is known
+    pub fn reset_peer_breakdown(&self) {
+        self.peers_by_direction.reset();
+        self.peers_by_client.reset();
+        self.peers_by_fork_id.reset();
+    }
+
+    pub fn increment_peer_connects(&self, count: u64) {
+        self.peer_connects.inc_by(count);
+    }
+
+    pub fn increment_peer_disconnects(&self, count: u64) {
+        self.peer_disconnects.inc_by(count);
+    }
+
+    pub fn set_sync_blocks(&self, syncing: bool, current: u64, highest: u64, progress_percent: f64) {
+        self.sync_syncing.set(syncing as i64);
+        self.sync_current_block.set(current as i64);
+        self.sync_highest_block.set(highest as i64);
+        self.sync_progress.set(progress_percent);
+    }
+
+    pub fn set_sync_states(&self, known: Option<u64>, pulled: Option<u64>) {
+        self.sync_known_states.set(known.unwrap_or(0) as i64);
+        self.sync_pulled_states.set(pulled.unwrap_or(0) as i64);
+    }
+
+    pub fn set_sync_rate(&self, blocks_per_second: f64, eta_seconds: Option<u64>) {
+        self.sync_blocks_per_second.set(blocks_per_second);
+        self.sync_eta.set(eta_seconds.map_or(-1, |eta| eta as i64));
+    }
+}
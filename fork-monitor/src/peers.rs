@@ -0,0 +1,278 @@
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ethers::providers::Provider;
+use ethers::types::U64;
+use ethers::utils::{hex, rlp::Rlp};
+use mordor_common::telemetry::TracedHttp;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+use tracing::{info, instrument, warn};
+
+use crate::metrics::Metrics;
+
+// Connects and disconnects are counted over this window
+const CHURN_WINDOW_SECS: u64 = 3_600;
+
+// The subset of core-geth's admin_peers entries we use
+#[derive(Debug, Deserialize, Serialize)]
+struct AdminPeer {
+    id: String,
+    name: String,
+    enr: Option<String>,
+    network: AdminPeerNetwork,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AdminPeerNetwork {
+    inbound: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct AdminNodeInfo {
+    enr: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ClientCount {
+    pub client: String,
+    pub peers: usize,
+}
+
+#[derive(Clone, Serialize)]
+pub struct PeerReport {
+    pub peer_count: u64,
+    pub min_peers: u64,
+    pub below_threshold: bool,
+    // False when the node does not expose the admin API; only the
+    // peer count is known then
+    pub admin_api: bool,
+    pub inbound: usize,
+    pub outbound: usize,
+    pub clients: Vec<ClientCount>,
+    pub fork_id: Option<String>,
+    pub fork_id_match: usize,
+    pub fork_id_mismatch: usize,
+    pub fork_id_unknown: usize,
+    // Share of peers with a known fork ID that advertise ours
+    pub fork_id_match_ratio: Option<f64>,
+    pub connects_1h: usize,
+    pub disconnects_1h: usize,
+}
+
+struct PeerState {
+    known: HashSet<String>,
+    // (timestamp, connected) per peer set change
+    churn: VecDeque<(u64, bool)>,
+    fork_id: Option<[u8; 4]>,
+    report: Option<PeerReport>,
+}
+
+/// Polls the node's p2p view and tracks peer count, client mix, direction,
+/// churn and how many peers advertise the Mordor fork ID.
+pub struct PeerMonitor {
+    provider: Provider<TracedHttp>,
+    metrics: Arc<Metrics>,
+    min_peers: u64,
+    state: RwLock<PeerState>,
+}
+
+impl PeerMonitor {
+    /// `fork_id` overrides the fork ID read from the node's own ENR.
+    pub fn new(provider: Provider<TracedHttp>, metrics: Arc<Metrics>, min_peers: u64, fork_id: Option<[u8; 4]>) -> Self {
+        Self {
+            provider,
+            metrics,
+            min_peers,
+            state: RwLock::new(PeerState {
+                known: HashSet::new(),
+                churn: VecDeque::new(),
+                fork_id,
+                report: None,
+            }),
+        }
+    }
+
+    #[instrument(name = "peers.poll", skip_all)]
+    pub async fn poll(&self) -> Result<()> {
+        let peer_count = self.provider.request::<_, U64>("net_peerCount", ()).await?.as_u64();
+        self.metrics.set_peer_count(peer_count);
+
+        let mut state = self.state.write().await;
+
+        let was_below = state.report.as_ref().is_some_and(|r| r.below_threshold);
+        let below_threshold = peer_count < self.min_peers;
+        self.metrics.set_peer_count_low(below_threshold);
+        if below_threshold && !was_below {
+            warn!("Peer count {} is below the minimum of {}", peer_count, self.min_peers);
+        } else if !below_threshold && was_below {
+            info!("Peer count recovered to {}", peer_count);
+        }
+
+        // The admin API is often not exposed; keep going with the count alone
+        let peers = match self.provider.request::<_, Vec<AdminPeer>>("admin_peers", ()).await {
+            Ok(peers) => Some(peers),
+            Err(e) => {
+                if state.report.as_ref().map_or(true, |r| r.admin_api) {
+                    warn!("admin_peers unavailable, reporting peer count only: {}", e);
+                    self.metrics.reset_peer_breakdown();
+                }
+                None
+            }
+        };
+
+        if state.fork_id.is_none() && peers.is_some() {
+            state.fork_id = self.node_fork_id().await;
+        }
+
+        let now = now();
+        let mut report = PeerReport {
+            peer_count,
+            min_peers: self.min_peers,
+            below_threshold,
+            admin_api: peers.is_some(),
+            inbound: 0,
+            outbound: 0,
+            clients: Vec::new(),
+            fork_id: state.fork_id.map(|id| format!("0x{}", hex::encode(id))),
+            fork_id_match: 0,
+            fork_id_mismatch: 0,
+            fork_id_unknown: 0,
+            fork_id_match_ratio: None,
+            connects_1h: 0,
+            disconnects_1h: 0,
+        };
+
+        if let Some(peers) = peers {
+            let mut clients: BTreeMap<String, usize> = BTreeMap::new();
+
+            for peer in &peers {
+                if peer.network.inbound {
+                    report.inbound += 1;
+                } else {
+                    report.outbound += 1;
+                }
+                *clients.entry(client_version(&peer.name)).or_default() += 1;
+
+                // Inbound peers often have no ENR, so their fork ID is unknown
+                match (state.fork_id, peer.enr.as_deref().and_then(|enr| enr_fork_id(enr).ok())) {
+                    (Some(ours), Some(theirs)) if ours == theirs => report.fork_id_match += 1,
+                    (Some(_), Some(_)) => report.fork_id_mismatch += 1,
+                    _ => report.fork_id_unknown += 1,
+                }
+            }
+
+            let known = report.fork_id_match + report.fork_id_mismatch;
+            if known > 0 {
+                report.fork_id_match_ratio = Some(report.fork_id_match as f64 / known as f64);
+            }
+
+            // Churn is only meaningful against a previous peer set
+            let current: HashSet<String> = peers.iter().map(|p| p.id.clone()).collect();
+            if state.report.as_ref().is_some_and(|r| r.admin_api) {
+                let connects = current.difference(&state.known).count();
+                let disconnects = state.known.difference(&current).count();
+                self.metrics.increment_peer_connects(connects as u64);
+                self.metrics.increment_peer_disconnects(disconnects as u64);
+                state.churn.extend(std::iter::repeat((now, true)).take(connects));
+                state.churn.extend(std::iter::repeat((now, false)).take(disconnects));
+            }
+            state.known = current;
+
+            self.metrics.set_peers_by_direction(report.inbound, report.outbound);
+            self.metrics.set_peers_by_client(&clients);
+            self.metrics.set_peers_by_fork_id("match", report.fork_id_match);
+            self.metrics.set_peers_by_fork_id("mismatch", report.fork_id_mismatch);
+            self.metrics.set_peers_by_fork_id("unknown", report.fork_id_unknown);
+            if let Some(ratio) = report.fork_id_match_ratio {
+                self.metrics.set_fork_id_match_ratio(ratio);
+            }
+
+            let mut clients: Vec<ClientCount> = clients
+                .into_iter()
+                .map(|(client, peers)| ClientCount { client, peers })
+                .collect();
+            clients.sort_by_key(|c| std::cmp::Reverse(c.peers));
+            report.clients = clients;
+        }
+
+        while state.churn.front().is_some_and(|(at, _)| at + CHURN_WINDOW_SECS < now) {
+            state.churn.pop_front();
+        }
+        report.connects_1h = state.churn.iter().filter(|(_, connected)| *connected).count();
+        report.disconnects_1h = state.churn.len() - report.connects_1h;
+
+        state.report = Some(report);
+        Ok(())
+    }
+
+    async fn node_fork_id(&self) -> Option<[u8; 4]> {
+        let info: AdminNodeInfo = self.provider.request("admin_nodeInfo", ()).await.ok()?;
+        match enr_fork_id(info.enr.as_deref()?) {
+            Ok(fork_id) => {
+                info!("Node fork ID: 0x{}", hex::encode(fork_id));
+                Some(fork_id)
+            }
+            Err(e) => {
+                warn!("Could not read the node's fork ID: {}", e);
+                None
+            }
+        }
+    }
+
+    pub async fn report(&self) -> Option<PeerReport> {
+        self.state.read().await.report.clone()
+    }
+}
+
+/// Parses a fork ID given as 4 hex bytes, e.g. `0x7a0e8e07`.
+pub fn parse_fork_id(value: &str) -> Result<[u8; 4]> {
+    let bytes = hex::decode(value.trim_start_matches("0x"))?;
+    bytes
+        .try_into()
+        .map_err(|_| anyhow!("Fork ID must be 4 bytes: {}", value))
+}
+
+// "CoreGeth/v1.12.20-stable-c2fb4412/linux-amd64/go1.21.10" -> "CoreGeth/v1.12.20"
+fn client_version(name: &str) -> String {
+    let mut parts = name.split('/');
+    let client = parts.next().unwrap_or_default();
+    match parts.next() {
+        Some(version) if version.starts_with('v') => {
+            format!("{}/{}", client, version.split('-').next().unwrap_or(version))
+        }
+        _ if client.is_empty() => "unknown".to_string(),
+        _ => client.to_string(),
+    }
+}
+
+// The fork hash from the `eth` entry of an EIP-778 node record:
+// [signature, seq, key, value, ...] with eth = [[fork hash, fork next]]
+fn enr_fork_id(enr: &str) -> Result<[u8; 4]> {
+    let encoded = enr.strip_prefix("enr:").ok_or_else(|| anyhow!("Not an ENR: {}", enr))?;
+    let bytes = URL_SAFE_NO_PAD.decode(encoded)?;
+    let record = Rlp::new(&bytes);
+
+    for i in (2..record.item_count()?).step_by(2) {
+        if record.at(i)?.data()? != b"eth" {
+            continue;
+        }
+        let hash: Vec<u8> = record.at(i + 1)?.at(0)?.at(0)?.as_val()?;
+        return hash
+            .try_into()
+            .map_err(|_| anyhow!("Malformed fork hash in ENR"));
+    }
+
+    Err(anyhow!("ENR has no eth entry"))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
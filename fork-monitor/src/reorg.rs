@@ -0,0 +1,419 @@
+use ethers::types::{Address, Block, Transaction, H256, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::blockchain::MAX_CATCH_UP_BLOCKS;
+
+// Canonical blocks kept to diff a reorg against, and how long dropped
+// transactions are watched for afterwards
+const MAX_HISTORY: usize = MAX_CATCH_UP_BLOCKS as usize;
+
+// Reorg events kept for the /reorgs API
+const MAX_REORG_EVENTS: usize = 1_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReorgedTxKind {
+    // In no canonical block yet; it may still be mined later
+    Dropped,
+    // Mined again at another height
+    Reincluded,
+    // Its sender's nonce was used by another transaction: a double spend
+    Replaced,
+}
+
+impl ReorgedTxKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReorgedTxKind::Dropped => "dropped",
+            ReorgedTxKind::Reincluded => "reincluded",
+            ReorgedTxKind::Replaced => "replaced",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReorgedTx {
+    pub hash: H256,
+    pub from: Address,
+    pub nonce: U256,
+    pub kind: ReorgedTxKind,
+    pub orphaned_block: u64,
+    pub canonical_block: Option<u64>,
+    pub replaced_by: Option<H256>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReorgEvent {
+    pub detected_at: u64,
+    pub common_ancestor: u64,
+    pub depth: u64,
+    pub orphaned_blocks: Vec<H256>,
+    pub canonical_blocks: Vec<H256>,
+    // Transactions from the orphaned blocks that are not in the same block
+    // height on the canonical branch; updated as dropped ones are mined
+    pub transactions: Vec<ReorgedTx>,
+    // Set once the chain is MAX_HISTORY blocks past the common ancestor;
+    // transactions still dropped then stay dropped
+    #[serde(default)]
+    pub settled: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct TxSummary {
+    hash: H256,
+    from: Address,
+    nonce: U256,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct BlockTxs {
+    number: u64,
+    hash: H256,
+    parent_hash: H256,
+    transactions: Vec<TxSummary>,
+}
+
+impl BlockTxs {
+    fn new(block: &Block<Transaction>) -> Self {
+        Self {
+            number: block.number.unwrap().as_u64(),
+            hash: block.hash.unwrap(),
+            parent_hash: block.parent_hash,
+            transactions: block
+                .transactions
+                .iter()
+                .map(|tx| TxSummary {
+                    hash: tx.hash,
+                    from: tx.from,
+                    nonce: tx.nonce,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Keeps the transactions of recent canonical blocks so that, when a reorg
+/// replaces them, the orphaned branch can be diffed against the new one.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ReorgTracker {
+    blocks: VecDeque<BlockTxs>,
+    events: VecDeque<ReorgEvent>,
+    // Canonical blocks seen, the denominator of the reorg rate
+    #[serde(default)]
+    blocks_seen: u64,
+}
+
+impl ReorgTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the block builds on the tracked tip or is already tracked,
+    /// so no branch has to be fetched for it.
+    pub fn extends(&self, block: &Block<Transaction>) -> bool {
+        match self.blocks.back() {
+            Some(tip) => tip.hash == block.parent_hash || self.contains(block.hash.unwrap()),
+            None => true,
+        }
+    }
+
+    pub fn contains(&self, hash: H256) -> bool {
+        self.blocks.iter().rev().any(|b| b.hash == hash)
+    }
+
+    pub fn oldest(&self) -> Option<u64> {
+        self.blocks.front().map(|b| b.number)
+    }
+
+    /// Adds a block that extends the tip; returns transactions from earlier
+    /// reorgs that it settles: dropped ones it mines or replaces, and those
+    /// still dropped when their reorg falls out of the history.
+    pub fn add_block(&mut self, block: &Block<Transaction>) -> Vec<ReorgedTx> {
+        if self.contains(block.hash.unwrap()) {
+            return Vec::new();
+        }
+        let block = BlockTxs::new(block);
+        let settled = self.settle(&block);
+        self.push(block);
+        settled
+    }
+
+    /// Replaces the tracked blocks after the branch's parent with the branch
+    /// (oldest first) and diffs the transactions of the two. A branch that
+    /// doesn't connect to the tracked blocks restarts the history instead.
+    pub fn reorg(&mut self, branch: &[Block<Transaction>]) -> (Option<ReorgEvent>, Vec<ReorgedTx>) {
+        let canonical: Vec<BlockTxs> = branch.iter().map(BlockTxs::new).collect();
+        let Some(first) = canonical.first() else {
+            return (None, Vec::new());
+        };
+        let Some(ancestor) = self.blocks.iter().position(|b| b.hash == first.parent_hash) else {
+            self.blocks.clear();
+            for block in canonical {
+                self.push(block);
+            }
+            return (None, Vec::new());
+        };
+
+        let common_ancestor = self.blocks[ancestor].number;
+        let orphaned: Vec<BlockTxs> = self.blocks.drain(ancestor + 1..).collect();
+        // Their replacements are counted instead
+        self.blocks_seen = self.blocks_seen.saturating_sub(orphaned.len() as u64);
+
+        let mut settled = Vec::new();
+        for block in &canonical {
+            settled.extend(self.settle(block));
+        }
+
+        let event = ReorgEvent {
+            detected_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            common_ancestor,
+            depth: orphaned.len() as u64,
+            orphaned_blocks: orphaned.iter().map(|b| b.hash).collect(),
+            canonical_blocks: canonical.iter().map(|b| b.hash).collect(),
+            transactions: Self::diff(&orphaned, &canonical),
+            settled: false,
+        };
+
+        for block in canonical {
+            self.push(block);
+        }
+        self.events.push_back(event.clone());
+        if self.events.len() > MAX_REORG_EVENTS {
+            self.events.pop_front();
+        }
+
+        (Some(event), settled)
+    }
+
+    pub fn events(&self) -> Vec<ReorgEvent> {
+        self.events.iter().cloned().collect()
+    }
+
+    pub fn blocks_seen(&self) -> u64 {
+        self.blocks_seen
+    }
+
+    /// Depths of the reorgs kept, oldest first.
+    pub fn depths(&self) -> Vec<u64> {
+        self.events.iter().map(|event| event.depth).collect()
+    }
+
+    fn push(&mut self, block: BlockTxs) {
+        self.blocks_seen += 1;
+        self.blocks.push_back(block);
+        while self.blocks.len() > MAX_HISTORY {
+            self.blocks.pop_front();
+        }
+    }
+
+    fn diff(orphaned: &[BlockTxs], canonical: &[BlockTxs]) -> Vec<ReorgedTx> {
+        let mut by_hash: HashMap<H256, u64> = HashMap::new();
+        let mut by_nonce: HashMap<(Address, U256), (H256, u64)> = HashMap::new();
+        for block in canonical {
+            for tx in &block.transactions {
+                by_hash.insert(tx.hash, block.number);
+                by_nonce.insert((tx.from, tx.nonce), (tx.hash, block.number));
+            }
+        }
+
+        let mut reorged = Vec::new();
+        for block in orphaned {
+            for tx in &block.transactions {
+                let (kind, canonical_block, replaced_by) = match by_hash.get(&tx.hash) {
+                    // Unaffected apart from the block hash
+                    Some(number) if *number == block.number => continue,
+                    Some(number) => (ReorgedTxKind::Reincluded, Some(*number), None),
+                    None => match by_nonce.get(&(tx.from, tx.nonce)) {
+                        Some((hash, number)) => (ReorgedTxKind::Replaced, Some(*number), Some(*hash)),
+                        None => (ReorgedTxKind::Dropped, None, None),
+                    },
+                };
+                reorged.push(ReorgedTx {
+                    hash: tx.hash,
+                    from: tx.from,
+                    nonce: tx.nonce,
+                    kind,
+                    orphaned_block: block.number,
+                    canonical_block,
+                    replaced_by,
+                });
+            }
+        }
+        reorged
+    }
+
+    // Dropped transactions of recent reorgs that the block mines, or whose
+    // nonce it uses, are updated in place
+    fn settle(&mut self, block: &BlockTxs) -> Vec<ReorgedTx> {
+        let mut settled = Vec::new();
+        for event in self.events.iter_mut().filter(|event| !event.settled) {
+            if event.common_ancestor + (MAX_HISTORY as u64) < block.number {
+                event.settled = true;
+                settled.extend(event.transactions.iter().filter(|t| t.kind == ReorgedTxKind::Dropped).cloned());
+                continue;
+            }
+            for reorged in event.transactions.iter_mut().filter(|t| t.kind == ReorgedTxKind::Dropped) {
+                let Some(tx) = block
+                    .transactions
+                    .iter()
+                    .find(|tx| tx.hash == reorged.hash || (tx.from == reorged.from && tx.nonce == reorged.nonce))
+                else {
+                    continue;
+                };
+                if tx.hash == reorged.hash {
+                    reorged.kind = ReorgedTxKind::Reincluded;
+                } else {
+                    reorged.kind = ReorgedTxKind::Replaced;
+                    reorged.replaced_by = Some(tx.hash);
+                }
+                reorged.canonical_block = Some(block.number);
+                settled.push(reorged.clone());
+            }
+        }
+        settled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(number: u64, branch: u64) -> H256 {
+        H256::from_low_u64_be(number << 8 | branch)
+    }
+
+    fn tx(id: u64, sender: u8, nonce: u64) -> Transaction {
+        Transaction {
+            hash: H256::from_low_u64_be(id),
+            from: Address::repeat_byte(sender),
+            nonce: nonce.into(),
+            ..Default::default()
+        }
+    }
+
+    // Block `number` of `branch`, whose parent is on `parent_branch`
+    fn block(number: u64, branch: u64, parent_branch: u64, transactions: Vec<Transaction>) -> Block<Transaction> {
+        Block {
+            number: Some(number.into()),
+            hash: Some(hash(number, branch)),
+            parent_hash: hash(number - 1, parent_branch),
+            transactions,
+            ..Default::default()
+        }
+    }
+
+    fn outcomes(txs: &[ReorgedTx]) -> Vec<(u64, ReorgedTxKind, Option<u64>)> {
+        txs.iter().map(|tx| (tx.hash.to_low_u64_be(), tx.kind, tx.canonical_block)).collect()
+    }
+
+    // Blocks 1-10; block 9 holds transactions 1-4 from senders 1-4 and
+    // block 10 transaction 5 from sender 5, all at nonce 0
+    fn tracker() -> ReorgTracker {
+        let mut tracker = ReorgTracker::new();
+        for number in 1..=10 {
+            let transactions = match number {
+                9 => (1..=4).map(|id| tx(id, id as u8, 0)).collect(),
+                10 => vec![tx(5, 5, 0)],
+                _ => Vec::new(),
+            };
+            let block = block(number, 0, 0, transactions);
+            assert!(tracker.extends(&block));
+            assert!(tracker.add_block(&block).is_empty());
+        }
+        tracker
+    }
+
+    // Orphans blocks 9-10 for 9'-11': transaction 4 stays at height 9,
+    // 1 is mined again at 10, sender 2's nonce goes to transaction 20, and
+    // 3 and 5 are dropped
+    fn reorg(tracker: &mut ReorgTracker) -> ReorgEvent {
+        let branch = [
+            block(9, 1, 0, vec![tx(4, 4, 0)]),
+            block(10, 1, 1, vec![tx(1, 1, 0)]),
+            block(11, 1, 1, vec![tx(20, 2, 0)]),
+        ];
+        assert!(!tracker.extends(&branch[2]));
+        let (event, settled) = tracker.reorg(&branch);
+        assert!(settled.is_empty());
+        event.unwrap()
+    }
+
+    #[test]
+    fn diffs_the_orphaned_branch_against_the_new_one() {
+        let mut tracker = tracker();
+        let event = reorg(&mut tracker);
+
+        assert_eq!(event.common_ancestor, 8);
+        assert_eq!(event.depth, 2);
+        assert_eq!(event.orphaned_blocks, [hash(9, 0), hash(10, 0)]);
+        assert_eq!(event.canonical_blocks, [hash(9, 1), hash(10, 1), hash(11, 1)]);
+        assert_eq!(
+            outcomes(&event.transactions),
+            [
+                (1, ReorgedTxKind::Reincluded, Some(10)),
+                (2, ReorgedTxKind::Replaced, Some(11)),
+                (3, ReorgedTxKind::Dropped, None),
+                (5, ReorgedTxKind::Dropped, None),
+            ]
+        );
+        assert_eq!(event.transactions[1].replaced_by, Some(H256::from_low_u64_be(20)));
+
+        // The orphaned blocks no longer count towards the reorg rate
+        assert_eq!(tracker.blocks_seen(), 11);
+        assert_eq!(tracker.depths(), [2]);
+    }
+
+    #[test]
+    fn settles_dropped_transactions_mined_later() {
+        let mut tracker = tracker();
+        reorg(&mut tracker);
+
+        // Transaction 3 mined as is, sender 5's nonce used by transaction 50
+        let settled = tracker.add_block(&block(12, 1, 1, vec![tx(3, 3, 0)]));
+        assert_eq!(outcomes(&settled), [(3, ReorgedTxKind::Reincluded, Some(12))]);
+        let settled = tracker.add_block(&block(13, 1, 1, vec![tx(50, 5, 0)]));
+        assert_eq!(outcomes(&settled), [(5, ReorgedTxKind::Replaced, Some(13))]);
+        assert_eq!(settled[0].replaced_by, Some(H256::from_low_u64_be(50)));
+
+        let event = &tracker.events()[0];
+        assert!(event.transactions.iter().all(|tx| tx.kind != ReorgedTxKind::Dropped));
+        // Settled transactions are not reported again
+        assert!(tracker.add_block(&block(14, 1, 1, vec![tx(3, 3, 0)])).is_empty());
+    }
+
+    #[test]
+    fn dropped_transactions_are_final_once_out_of_the_history() {
+        let mut tracker = tracker();
+        reorg(&mut tracker);
+
+        // Watched until the chain is MAX_HISTORY blocks past the ancestor
+        let last_watched = 8 + MAX_HISTORY as u64;
+        for number in 12..=last_watched {
+            assert!(tracker.add_block(&block(number, 1, 1, Vec::new())).is_empty());
+        }
+        assert!(!tracker.events()[0].settled);
+
+        let settled = tracker.add_block(&block(last_watched + 1, 1, 1, vec![tx(3, 3, 0)]));
+        assert_eq!(
+            outcomes(&settled),
+            [(3, ReorgedTxKind::Dropped, None), (5, ReorgedTxKind::Dropped, None)]
+        );
+        assert!(tracker.events()[0].settled);
+        assert!(tracker.add_block(&block(last_watched + 2, 1, 1, Vec::new())).is_empty());
+    }
+
+    #[test]
+    fn unconnected_branch_restarts_the_history() {
+        let mut tracker = tracker();
+        let (event, settled) = tracker.reorg(&[block(50, 2, 2, Vec::new())]);
+
+        assert!(event.is_none() && settled.is_empty());
+        assert_eq!(tracker.oldest(), Some(50));
+        assert!(tracker.events().is_empty());
+    }
+}
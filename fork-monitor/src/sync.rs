@@ -0,0 +1,146 @@
+use anyhow::Result;
+use ethers::providers::{Middleware, Provider};
+use ethers::types::SyncingStatus;
+use mordor_common::telemetry::TracedHttp;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+use tracing::{info, instrument};
+
+use crate::metrics::Metrics;
+
+// Moving window for the sync rate
+const RATE_WINDOW_SECS: f64 = 300.0;
+
+#[derive(Clone, Serialize)]
+pub struct SyncReport {
+    pub syncing: bool,
+    pub starting_block: u64,
+    pub current_block: u64,
+    pub highest_block: u64,
+    pub known_states: Option<u64>,
+    pub pulled_states: Option<u64>,
+    pub progress_percent: f64,
+    pub blocks_per_second: f64,
+    // None while syncing until a rate is known
+    pub eta_seconds: Option<u64>,
+}
+
+struct SyncState {
+    // (unix time, current block) while syncing
+    samples: VecDeque<(f64, u64)>,
+    report: Option<SyncReport>,
+}
+
+/// Follows eth_syncing and estimates how fast, and how soon, the node
+/// catches up with the highest known block.
+pub struct SyncMonitor {
+    provider: Provider<TracedHttp>,
+    metrics: Arc<Metrics>,
+    state: RwLock<SyncState>,
+}
+
+impl SyncMonitor {
+    pub fn new(provider: Provider<TracedHttp>, metrics: Arc<Metrics>) -> Self {
+        Self {
+            provider,
+            metrics,
+            state: RwLock::new(SyncState {
+                samples: VecDeque::new(),
+                report: None,
+            }),
+        }
+    }
+
+    #[instrument(name = "sync.poll", skip_all)]
+    pub async fn poll(&self) -> Result<()> {
+        let status = self.provider.syncing().await?;
+        let mut state = self.state.write().await;
+        let was_syncing = state.report.as_ref().is_some_and(|r| r.syncing);
+
+        let report = match status {
+            SyncingStatus::IsFalse => {
+                if was_syncing {
+                    info!("Sync complete");
+                }
+                state.samples.clear();
+
+                let head = self.provider.get_block_number().await?.as_u64();
+                SyncReport {
+                    syncing: false,
+                    starting_block: head,
+                    current_block: head,
+                    highest_block: head,
+                    known_states: None,
+                    pulled_states: None,
+                    progress_percent: 100.0,
+                    blocks_per_second: 0.0,
+                    eta_seconds: Some(0),
+                }
+            }
+            SyncingStatus::IsSyncing(progress) => {
+                let current = progress.current_block.as_u64();
+                let highest = progress.highest_block.as_u64();
+                let starting = progress.starting_block.as_u64();
+                if !was_syncing {
+                    info!("Node is syncing: block {} of {}", current, highest);
+                }
+
+                let now = now();
+                state.samples.push_back((now, current));
+                while state.samples.front().is_some_and(|(at, _)| at + RATE_WINDOW_SECS < now) {
+                    state.samples.pop_front();
+                }
+
+                let blocks_per_second = rate(&state.samples);
+                let remaining = highest.saturating_sub(current);
+
+                SyncReport {
+                    syncing: true,
+                    starting_block: starting,
+                    current_block: current,
+                    highest_block: highest,
+                    known_states: progress.known_states.map(|s| s.as_u64()),
+                    pulled_states: progress.pulled_states.map(|s| s.as_u64()),
+                    progress_percent: progress_percent(current, highest),
+                    blocks_per_second,
+                    eta_seconds: (blocks_per_second > 0.0)
+                        .then(|| (remaining as f64 / blocks_per_second) as u64),
+                }
+            }
+        };
+
+        self.metrics.set_sync_blocks(report.syncing, report.current_block, report.highest_block, report.progress_percent);
+        self.metrics.set_sync_states(report.known_states, report.pulled_states);
+        self.metrics.set_sync_rate(report.blocks_per_second, report.eta_seconds);
+        state.report = Some(report);
+        Ok(())
+    }
+
+    pub async fn report(&self) -> Option<SyncReport> {
+        self.state.read().await.report.clone()
+    }
+}
+
+fn progress_percent(current: u64, highest: u64) -> f64 {
+    if highest == 0 {
+        return 100.0;
+    }
+    (current as f64 / highest as f64 * 100.0).min(100.0)
+}
+
+fn rate(samples: &VecDeque<(f64, u64)>) -> f64 {
+    match (samples.front(), samples.back()) {
+        (Some((t0, b0)), Some((t1, b1))) if t1 > t0 => b1.saturating_sub(*b0) as f64 / (t1 - t0),
+        _ => 0.0,
+    }
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
@@ -0,0 +1,274 @@
+use ethers::types::{Address, Block, H256};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Blocks the per-miner breakdown covers (~3.5 hours at 13s)
+const WINDOW_BLOCKS: usize = 1_000;
+
+// The smallest gap consensus allows between a block and its parent
+const MIN_BLOCK_INTERVAL: u64 = 1;
+
+// ETC's target block time; honest gaps are exponential around it
+const TARGET_BLOCK_TIME: f64 = 13.0;
+
+// Violations kept for the /timestamps API
+const MAX_VIOLATIONS: usize = 1_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    Future,
+    NonMonotonic,
+    MinInterval,
+}
+
+impl ViolationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ViolationKind::Future => "future",
+            ViolationKind::NonMonotonic => "non_monotonic",
+            ViolationKind::MinInterval => "min_interval",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TimestampViolation {
+    pub detected_at: u64,
+    pub block: u64,
+    pub hash: H256,
+    pub miner: Address,
+    pub kind: ViolationKind,
+    pub timestamp: u64,
+    // Seconds ahead of the monitor's clock for future blocks, otherwise
+    // the gap from the parent (zero or negative when not increasing)
+    pub offset: i64,
+}
+
+#[derive(Serialize)]
+pub struct MinerTimestamps {
+    pub miner: Address,
+    pub blocks: u64,
+    pub future: u64,
+    pub non_monotonic: u64,
+    pub min_interval: u64,
+    pub min_interval_percent: f64,
+}
+
+#[derive(Serialize)]
+pub struct TimestampReport {
+    pub window_blocks: usize,
+    // Share of honest blocks expected at the minimum interval
+    pub expected_min_interval_percent: f64,
+    pub miners: Vec<MinerTimestamps>,
+    pub violations: Vec<TimestampViolation>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct TimestampSample {
+    number: u64,
+    hash: H256,
+    miner: Address,
+    violations: Vec<ViolationKind>,
+}
+
+/// Checks block timestamps against the parent and the monitor's clock.
+/// Future timestamps, timestamps that don't increase and runs of
+/// minimum-interval blocks are how selfish and timewarp miners game
+/// difficulty, so each violation is kept with the block's miner.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct TimestampChecker {
+    samples: VecDeque<TimestampSample>,
+    violations: VecDeque<TimestampViolation>,
+}
+
+impl TimestampChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks a block against its parent's timestamp; returns what it
+    /// violated, or nothing when the block was already checked.
+    pub fn check<TX>(&mut self, block: &Block<TX>, parent_timestamp: u64, future_tolerance: u64) -> Vec<TimestampViolation> {
+        let number = block.number.unwrap().as_u64();
+        let hash = block.hash.unwrap();
+        let miner = block.author.unwrap_or_default();
+        let timestamp = block.timestamp.as_u64();
+
+        if self.samples.iter().rev().any(|s| s.hash == hash) {
+            return Vec::new();
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let gap = timestamp as i64 - parent_timestamp as i64;
+
+        let mut found = Vec::new();
+        if timestamp > now + future_tolerance {
+            found.push((ViolationKind::Future, (timestamp - now) as i64));
+        }
+        if gap <= 0 {
+            found.push((ViolationKind::NonMonotonic, gap));
+        } else if gap as u64 <= MIN_BLOCK_INTERVAL {
+            found.push((ViolationKind::MinInterval, gap));
+        }
+
+        let violations: Vec<TimestampViolation> = found
+            .into_iter()
+            .map(|(kind, offset)| TimestampViolation {
+                detected_at: now,
+                block: number,
+                hash,
+                miner,
+                kind,
+                timestamp,
+                offset,
+            })
+            .collect();
+
+        // A block at an already seen height replaces the old branch
+        while self.samples.back().is_some_and(|s| s.number >= number) {
+            self.samples.pop_back();
+        }
+        self.samples.push_back(TimestampSample {
+            number,
+            hash,
+            miner,
+            violations: violations.iter().map(|v| v.kind).collect(),
+        });
+        while self.samples.len() > WINDOW_BLOCKS {
+            self.samples.pop_front();
+        }
+
+        self.violations.extend(violations.iter().cloned());
+        while self.violations.len() > MAX_VIOLATIONS {
+            self.violations.pop_front();
+        }
+
+        violations
+    }
+
+    pub fn report(&self) -> TimestampReport {
+        let mut by_miner: HashMap<Address, MinerTimestamps> = HashMap::new();
+        for sample in &self.samples {
+            let miner = by_miner.entry(sample.miner).or_insert(MinerTimestamps {
+                miner: sample.miner,
+                blocks: 0,
+                future: 0,
+                non_monotonic: 0,
+                min_interval: 0,
+                min_interval_percent: 0.0,
+            });
+            miner.blocks += 1;
+            for kind in &sample.violations {
+                match kind {
+                    ViolationKind::Future => miner.future += 1,
+                    ViolationKind::NonMonotonic => miner.non_monotonic += 1,
+                    ViolationKind::MinInterval => miner.min_interval += 1,
+                }
+            }
+        }
+
+        let mut miners: Vec<MinerTimestamps> = by_miner
+            .into_values()
+            .map(|mut miner| {
+                miner.min_interval_percent = miner.min_interval as f64 / miner.blocks as f64 * 100.0;
+                miner
+            })
+            .collect();
+        miners.sort_by_key(|m| std::cmp::Reverse(m.future + m.non_monotonic + m.min_interval));
+
+        TimestampReport {
+            window_blocks: self.samples.len(),
+            expected_min_interval_percent: (1.0 - (-(MIN_BLOCK_INTERVAL as f64) / TARGET_BLOCK_TIME).exp()) * 100.0,
+            miners,
+            violations: self.violations.iter().cloned().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(number: u64, fork: u8, miner: u8, timestamp: u64) -> Block<H256> {
+        Block {
+            number: Some(number.into()),
+            hash: Some(H256::from_low_u64_be(number << 8 | fork as u64)),
+            author: Some(Address::repeat_byte(miner)),
+            timestamp: timestamp.into(),
+            ..Default::default()
+        }
+    }
+
+    fn kinds(violations: &[TimestampViolation]) -> Vec<(ViolationKind, i64)> {
+        violations.iter().map(|v| (v.kind, v.offset)).collect()
+    }
+
+    #[test]
+    fn checks_the_gap_from_the_parent() {
+        let mut checker = TimestampChecker::new();
+
+        assert!(checker.check(&block(1, 0, 1, 1_013), 1_000, 15).is_empty());
+        assert_eq!(kinds(&checker.check(&block(2, 0, 1, 1_014), 1_013, 15)), [(ViolationKind::MinInterval, 1)]);
+        assert_eq!(kinds(&checker.check(&block(3, 0, 1, 1_014), 1_014, 15)), [(ViolationKind::NonMonotonic, 0)]);
+        assert_eq!(kinds(&checker.check(&block(4, 0, 1, 1_009), 1_014, 15)), [(ViolationKind::NonMonotonic, -5)]);
+    }
+
+    #[test]
+    fn flags_timestamps_beyond_the_future_tolerance() {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let mut checker = TimestampChecker::new();
+
+        assert!(checker.check(&block(1, 0, 1, now + 10), now - 3, 15).is_empty());
+        let found = checker.check(&block(2, 0, 1, now + 100), now + 10, 15);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, ViolationKind::Future);
+        // The clock may tick between the two readings
+        assert!((99..=100).contains(&found[0].offset), "{}", found[0].offset);
+    }
+
+    #[test]
+    fn checks_each_block_once_and_replaces_reorged_heights() {
+        let mut checker = TimestampChecker::new();
+        checker.check(&block(1, 0, 1, 1_000), 987, 15);
+        assert_eq!(checker.check(&block(2, 0, 1, 1_001), 1_000, 15).len(), 1);
+        // The tip is checked again on every poll
+        assert!(checker.check(&block(2, 0, 1, 1_001), 1_000, 15).is_empty());
+
+        // Another miner's block at height 2 replaces the first branch
+        assert!(checker.check(&block(2, 1, 2, 1_013), 1_000, 15).is_empty());
+
+        let report = checker.report();
+        assert_eq!(report.window_blocks, 2);
+        // The replaced block's violation stays in the event log
+        assert_eq!(report.violations.len(), 1);
+        assert!(report.miners.iter().all(|m| m.min_interval == 0));
+    }
+
+    #[test]
+    fn reports_per_miner_shares_against_the_poisson_expectation() {
+        let mut checker = TimestampChecker::new();
+        let mut timestamp = 1_000;
+        // Miner 1 mines every block at the minimum interval, miner 2 honestly
+        for number in 1..=8 {
+            let (miner, gap) = if number % 2 == 0 { (1, 1) } else { (2, 13) };
+            checker.check(&block(number, 0, miner, timestamp + gap), timestamp, 15);
+            timestamp += gap;
+        }
+
+        let report = checker.report();
+        // 1 - exp(-1/13) of honest blocks land within a second of the parent
+        assert!((report.expected_min_interval_percent - 7.4036).abs() < 1e-3);
+        assert_eq!(report.window_blocks, 8);
+
+        let first = &report.miners[0];
+        assert_eq!(first.miner, Address::repeat_byte(1));
+        assert_eq!((first.blocks, first.min_interval, first.min_interval_percent), (4, 4, 100.0));
+        let second = &report.miners[1];
+        assert_eq!((second.blocks, second.min_interval, second.non_monotonic), (4, 0, 0));
+    }
+}
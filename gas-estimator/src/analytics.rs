@@ -0,0 +1,349 @@
+use anyhow::Result;
+use ethers::providers::{Middleware, Provider};
+use ethers::types::{Address, H256};
+use mordor_common::telemetry::TracedHttp;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, instrument, warn};
+
+use crate::metrics::Metrics;
+
+// Rolling windows over which consumers are ranked, by block timestamp
+pub const WINDOWS: [(&str, u64); 2] = [("1h", 3_600), ("24h", 86_400)];
+
+// Never fetch more than this many blocks in one pass when catching up
+const MAX_BLOCKS_PER_PASS: u64 = 50;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TxKind {
+    Call,
+    Create,
+    Transfer,
+}
+
+impl TxKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TxKind::Call => "call",
+            TxKind::Create => "create",
+            TxKind::Transfer => "transfer",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct GasUsage {
+    kind: TxKind,
+    // Destination for calls and transfers, the new contract for creations
+    contract: Address,
+    selector: Option<[u8; 4]>,
+    gas_used: u64,
+}
+
+// (kind, contract, selector) a consumer is grouped by
+type ConsumerKey = (TxKind, Address, Option<[u8; 4]>);
+
+/// The gas attribution of one block, kept for the rolling windows.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BlockUsage {
+    // Zero in checkpoints from before hashes were kept
+    #[serde(default)]
+    hash: H256,
+    timestamp: u64,
+    usages: Vec<GasUsage>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct GasConsumer {
+    pub kind: TxKind,
+    pub contract: Address,
+    pub selector: Option<String>,
+    pub gas_used: u64,
+    pub tx_count: u64,
+    pub share_percent: f64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct WindowReport {
+    pub window: String,
+    pub blocks: usize,
+    pub total_gas_used: u64,
+    pub gas_by_kind: HashMap<TxKind, u64>,
+    pub top_consumers: Vec<GasConsumer>,
+}
+
+/// Attributes receipt `gas_used` to destination contracts and 4-byte method
+/// selectors, and ranks the top consumers over the rolling windows.
+pub struct GasAnalytics {
+    provider: Provider<TracedHttp>,
+    metrics: Arc<Metrics>,
+    top_n: usize,
+    history: RwLock<VecDeque<(u64, BlockUsage)>>,
+    reports: RwLock<Vec<WindowReport>>,
+}
+
+impl GasAnalytics {
+    pub fn new(provider: Provider<TracedHttp>, metrics: Arc<Metrics>, top_n: usize) -> Self {
+        Self {
+            provider,
+            metrics,
+            top_n,
+            history: RwLock::new(VecDeque::new()),
+            reports: RwLock::new(Vec::new()),
+        }
+    }
+
+    #[instrument(name = "analytics.analyze", skip_all)]
+    pub async fn analyze(&self) -> Result<()> {
+        let latest = self.provider.get_block_number().await?.as_u64();
+
+        // The RPC calls work on a copy, so metrics scrapes and checkpoints
+        // don't wait on them; the write lock is only taken to splice in the
+        // result
+        let mut kept = self.history().await;
+        self.drop_orphaned(&mut kept, latest).await?;
+
+        let next = match kept.last() {
+            Some((number, _)) => (number + 1).max(latest.saturating_sub(MAX_BLOCKS_PER_PASS - 1)),
+            None => latest,
+        };
+
+        let mut fetched: Vec<(u64, BlockUsage)> = Vec::new();
+        for number in next..=latest {
+            let (parent_hash, usage) = match self.fetch_block_usage(number).await? {
+                Some(block) => block,
+                None => break,
+            };
+            // Reorged again since the history was checked; the next pass refetches
+            let parent = fetched.last().or(kept.last()).filter(|(n, _)| n + 1 == number);
+            if parent.is_some_and(|(_, usage)| !usage.hash.is_zero() && usage.hash != parent_hash) {
+                break;
+            }
+            fetched.push((number, usage));
+        }
+
+        let mut history = self.history.write().await;
+        match kept.last() {
+            Some((tip, _)) => {
+                while history.back().is_some_and(|(n, _)| n > tip) {
+                    history.pop_back();
+                }
+            }
+            None => history.clear(),
+        }
+        history.extend(fetched);
+
+        // Drop blocks that fell out of the longest window
+        let longest = WINDOWS.iter().map(|(_, secs)| *secs).max().unwrap_or(0);
+        if let Some(tip_time) = history.back().map(|(_, usage)| usage.timestamp) {
+            while history
+                .front()
+                .is_some_and(|(_, usage)| usage.timestamp + longest < tip_time)
+            {
+                history.pop_front();
+            }
+        }
+
+        let reports = self.build_reports(&history);
+        drop(history);
+
+        self.update_metrics(&reports);
+        *self.reports.write().await = reports;
+
+        Ok(())
+    }
+
+    // Drops blocks from the tip down until one is still on the canonical
+    // chain, so gas from orphaned blocks isn't counted next to the blocks
+    // that replaced them
+    async fn drop_orphaned(&self, history: &mut Vec<(u64, BlockUsage)>, latest: u64) -> Result<()> {
+        let mut dropped = 0;
+        while let Some((number, usage)) = history.last() {
+            // Blocks from older checkpoints can't be checked, and dropping
+            // them would throw away the restored windows
+            if usage.hash.is_zero() {
+                break;
+            }
+            let canonical = match *number <= latest {
+                true => self.provider.get_block(*number).await?.and_then(|block| block.hash),
+                false => None,
+            };
+            if canonical == Some(usage.hash) {
+                break;
+            }
+            history.pop();
+            dropped += 1;
+        }
+        if dropped > 0 {
+            warn!("Dropped {} reorged block(s) from the gas analytics history", dropped);
+        }
+        Ok(())
+    }
+
+    // The block's attribution, with its parent hash to check it extends the history
+    #[instrument(name = "block.process", skip(self))]
+    async fn fetch_block_usage(&self, number: u64) -> Result<Option<(H256, BlockUsage)>> {
+        let block = match self.provider.get_block_with_txs(number).await? {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+
+        let mut usages = Vec::with_capacity(block.transactions.len());
+        for tx in &block.transactions {
+            let receipt = match self.provider.get_transaction_receipt(tx.hash).await? {
+                Some(receipt) => receipt,
+                None => {
+                    warn!("Missing receipt for {:?} in block {}", tx.hash, number);
+                    continue;
+                }
+            };
+            let gas_used = receipt.gas_used.unwrap_or_default().as_u64();
+
+            let usage = match (tx.to, receipt.contract_address) {
+                (None, created) => GasUsage {
+                    kind: TxKind::Create,
+                    contract: created.unwrap_or_default(),
+                    selector: None,
+                    gas_used,
+                },
+                (Some(to), _) if tx.input.len() >= 4 => GasUsage {
+                    kind: TxKind::Call,
+                    contract: to,
+                    selector: Some([tx.input[0], tx.input[1], tx.input[2], tx.input[3]]),
+                    gas_used,
+                },
+                (Some(to), _) => GasUsage {
+                    kind: TxKind::Transfer,
+                    contract: to,
+                    selector: None,
+                    gas_used,
+                },
+            };
+            usages.push(usage);
+        }
+
+        Ok(Some((
+            block.parent_hash,
+            BlockUsage {
+                hash: block.hash.unwrap_or_default(),
+                timestamp: block.timestamp.as_u64(),
+                usages,
+            },
+        )))
+    }
+
+    fn build_reports(&self, history: &VecDeque<(u64, BlockUsage)>) -> Vec<WindowReport> {
+        let tip_time = match history.back() {
+            Some((_, usage)) => usage.timestamp,
+            None => return Vec::new(),
+        };
+
+        WINDOWS
+            .iter()
+            .map(|(name, secs)| {
+                let blocks: Vec<&BlockUsage> = history
+                    .iter()
+                    .map(|(_, usage)| usage)
+                    .filter(|usage| usage.timestamp + secs >= tip_time)
+                    .collect();
+
+                let mut total_gas_used = 0;
+                let mut gas_by_kind: HashMap<TxKind, u64> = HashMap::new();
+                let mut consumers: HashMap<ConsumerKey, (u64, u64)> = HashMap::new();
+
+                for usage in blocks.iter().flat_map(|block| block.usages.iter()) {
+                    total_gas_used += usage.gas_used;
+                    *gas_by_kind.entry(usage.kind).or_default() += usage.gas_used;
+
+                    // Plain value transfers aren't contract consumption
+                    if usage.kind == TxKind::Transfer {
+                        continue;
+                    }
+                    let entry = consumers
+                        .entry((usage.kind, usage.contract, usage.selector))
+                        .or_default();
+                    entry.0 += usage.gas_used;
+                    entry.1 += 1;
+                }
+
+                let mut top_consumers: Vec<GasConsumer> = consumers
+                    .into_iter()
+                    .map(|((kind, contract, selector), (gas_used, tx_count))| GasConsumer {
+                        kind,
+                        contract,
+                        selector: selector.map(|s| format!("0x{:02x}{:02x}{:02x}{:02x}", s[0], s[1], s[2], s[3])),
+                        gas_used,
+                        tx_count,
+                        share_percent: if total_gas_used > 0 {
+                            gas_used as f64 / total_gas_used as f64 * 100.0
+                        } else {
+                            0.0
+                        },
+                    })
+                    .collect();
+                top_consumers.sort_by_key(|consumer| std::cmp::Reverse(consumer.gas_used));
+                top_consumers.truncate(self.top_n);
+
+                WindowReport {
+                    window: name.to_string(),
+                    blocks: blocks.len(),
+                    total_gas_used,
+                    gas_by_kind,
+                    top_consumers,
+                }
+            })
+            .collect()
+    }
+
+    fn update_metrics(&self, reports: &[WindowReport]) {
+        // Consumers drop out of the top N, so start from a clean slate
+        self.metrics.reset_top_gas_consumers();
+
+        for report in reports {
+            for kind in [TxKind::Call, TxKind::Create, TxKind::Transfer] {
+                let gas = report.gas_by_kind.get(&kind).copied().unwrap_or(0);
+                self.metrics.set_gas_used_by_kind(&report.window, kind.as_str(), gas);
+            }
+
+            for consumer in &report.top_consumers {
+                self.metrics.set_top_gas_consumer(
+                    &report.window,
+                    consumer.kind.as_str(),
+                    &format!("{:?}", consumer.contract),
+                    consumer.selector.as_deref().unwrap_or(""),
+                    consumer.gas_used,
+                );
+            }
+
+            info!(
+                "Gas analytics {}: {} blocks, {} gas, {} top consumers",
+                report.window,
+                report.blocks,
+                report.total_gas_used,
+                report.top_consumers.len()
+            );
+        }
+    }
+
+    pub async fn reports(&self) -> Vec<WindowReport> {
+        self.reports.read().await.clone()
+    }
+
+    /// Attributed blocks by number, oldest first.
+    pub async fn history(&self) -> Vec<(u64, BlockUsage)> {
+        self.history.read().await.iter().cloned().collect()
+    }
+
+    /// Restores checkpointed history; the next pass fetches only newer blocks.
+    pub async fn restore(&self, history: Vec<(u64, BlockUsage)>) {
+        let history = VecDeque::from(history);
+        let reports = self.build_reports(&history);
+        self.update_metrics(&reports);
+
+        *self.history.write().await = history;
+        *self.reports.write().await = reports;
+    }
+}
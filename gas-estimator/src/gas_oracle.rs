@@ -1,89 +1,230 @@
 use anyhow::Result;
-use ethers::providers::{Provider, Http, Middleware};
-use hyper::{
-    service::{make_service_fn, service_fn},
-        Body, Request, Response, Server,
+use ethers::providers::{Middleware, Provider};
+use ethers::types::H256;
+use mordor_common::telemetry::TracedHttp;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, instrument, warn};
+
+use crate::metrics::Metrics;
+
+// Number of recent blocks kept in the rolling window
+const WINDOW_SIZE: usize = 20;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BlockSample {
+    pub number: u64,
+    // Zero in checkpoints from before hashes were kept, so those samples are
+    // refetched
+    #[serde(default)]
+    pub hash: H256,
+    pub gas_used: u64,
+    pub gas_limit: u64,
+    pub tx_count: usize,
+    // Gas prices of the block's transactions, sorted ascending
+    pub gas_prices: Vec<u128>,
+    // Gas limits of the same transactions in the same order; empty in
+    // checkpoints from before they were kept
+    #[serde(default)]
+    pub gas_limits: Vec<u64>,
+}
+
+impl BlockSample {
+    pub fn gas_used_ratio(&self) -> f64 {
+        if self.gas_limit == 0 {
+            return 0.0;
+        }
+        self.gas_used as f64 / self.gas_limit as f64
+    }
+}
+
+pub struct GasOracle {
+    provider: Provider<TracedHttp>,
+    metrics: Arc<Metrics>,
+    window: Arc<RwLock<VecDeque<BlockSample>>>,
+    window_size: usize,
+}
+
+impl GasOracle {
+    pub fn new(provider: Provider<TracedHttp>, metrics: Arc<Metrics>) -> Self {
+        Self {
+            provider,
+            metrics,
+            window: Arc::new(RwLock::new(VecDeque::with_capacity(WINDOW_SIZE))),
+            window_size: WINDOW_SIZE,
+        }
+    }
+
+    #[instrument(name = "gas.analyze", skip_all)]
+    pub async fn analyze(&self) -> Result<()> {
+        let latest = self.provider.get_block_number().await?.as_u64();
+
+        // The RPC calls work on a copy, so proxied reads of the window don't
+        // wait on them; the write lock is only taken to splice in the result
+        let mut kept = self.samples().await;
+        self.drop_orphaned(&mut kept, latest).await?;
+
+        // Only fetch blocks we haven't seen, capped at the window size
+        let oldest_wanted = (latest + 1).saturating_sub(self.window_size as u64);
+        let next = kept
+            .last()
+            .map(|sample| sample.number + 1)
+            .unwrap_or(oldest_wanted)
+            .max(oldest_wanted);
+
+        let mut fetched: Vec<BlockSample> = Vec::new();
+        for number in next..=latest {
+            let block = match self.provider.get_block_with_txs(number).await? {
+                Some(block) => block,
+                None => break,
+            };
+            // Reorged again since the window was checked; the next pass refetches
+            let parent = fetched.last().or(kept.last()).filter(|sample| sample.number + 1 == number);
+            if parent.is_some_and(|sample| sample.hash != block.parent_hash) {
+                break;
+            }
+
+            let mut priced: Vec<(u128, u64)> = block
+                .transactions
+                .iter()
+                .filter_map(|tx| Some((tx.gas_price?.as_u128(), tx.gas.as_u64())))
+                .collect();
+            priced.sort_unstable();
+            let (gas_prices, gas_limits) = priced.into_iter().unzip();
+
+            fetched.push(BlockSample {
+                number,
+                hash: block.hash.unwrap_or_default(),
+                gas_used: block.gas_used.as_u64(),
+                gas_limit: block.gas_limit.as_u64(),
+                tx_count: block.transactions.len(),
+                gas_prices,
+                gas_limits,
+            });
+        }
+
+        let mut window = self.window.write().await;
+        match kept.last() {
+            Some(tip) => {
+                while window.back().is_some_and(|sample| sample.number > tip.number) {
+                    window.pop_back();
+                }
+            }
+            None => window.clear(),
+        }
+        window.extend(fetched);
+        while window.len() > self.window_size {
+            window.pop_front();
+        }
+
+        self.update_metrics(&window);
+        Ok(())
+    }
+
+    // Drops samples from the tip down until one is still on the canonical
+    // chain, so reorged heights are fetched again instead of serving prices
+    // from orphaned blocks
+    async fn drop_orphaned(&self, samples: &mut Vec<BlockSample>, latest: u64) -> Result<()> {
+        let mut dropped = 0;
+        while let Some(sample) = samples.last() {
+            let canonical = match sample.number <= latest {
+                true => self.provider.get_block(sample.number).await?.and_then(|block| block.hash),
+                false => None,
+            };
+            if canonical == Some(sample.hash) {
+                break;
+            }
+            samples.pop();
+            dropped += 1;
+        }
+        if dropped > 0 {
+            warn!("Dropped {} reorged block(s) from the gas price window", dropped);
+        }
+        Ok(())
+    }
+
+    fn update_metrics(&self, window: &VecDeque<BlockSample>) {
+        if window.is_empty() {
+            return;
+        }
+
+        let mut prices: Vec<u128> = window
+            .iter()
+            .flat_map(|sample| sample.gas_prices.iter().copied())
+            .collect();
+        prices.sort_unstable();
+
+        if !prices.is_empty() {
+            let mean = prices.iter().map(|&p| p as f64).sum::<f64>() / prices.len() as f64;
+
+            self.metrics.set_gas_price_min(prices[0] as f64);
+            self.metrics.set_gas_price_max(prices[prices.len() - 1] as f64);
+            self.metrics.set_gas_price_median(percentile(&prices, 50.0) as f64);
+            self.metrics.set_gas_price_p25(percentile(&prices, 25.0) as f64);
+            self.metrics.set_gas_price_p75(percentile(&prices, 75.0) as f64);
+            self.metrics.set_gas_price_mean(mean);
+        }
+
+        let gas_used: u64 = window.iter().map(|sample| sample.gas_used).sum();
+        let gas_limit: u64 = window.iter().map(|sample| sample.gas_limit).sum();
+        let tx_count: usize = window.iter().map(|sample| sample.tx_count).sum();
+
+        let utilization = if gas_limit > 0 {
+            gas_used as f64 / gas_limit as f64 * 100.0
+        } else {
+            0.0
         };
-        use prometheus::{Encoder, TextEncoder};
-        use std::sync::Arc;
-        use tokio::time::{interval, Duration};
-        use tracing::{info, error};
-
-        mod gas_oracle;
-        mod metrics;
-
-        use gas_oracle::GasOracle;
-        use metrics::Metrics;
-
-        #[tokio::main]
-        async fn main() -> Result<()> {
-            tracing_subscriber::fmt::init();
-
-                let rpc_url = std::env::var("RPC_URL")
-                        .unwrap_or_else(|_| "http://mordor-node:8545".to_string());
-                            
-                                let poll_interval = std::env::var("POLL_INTERVAL_SECS")
-                                        .unwrap_or_else(|_| "12".to_string())
-                                                .parse::<u64>()?;
-
-                                                    info!("Starting Mordor Gas Estimator");
-                                                        info!("RPC URL: {}", rpc_url);
-                                                            info!("Poll interval: {}s", poll_interval);
-
-                                                                let provider = Provider::<Http>::try_from(&rpc_url)?;
-                                                                    let metrics = Arc::new(Metrics::new());
-                                                                        let oracle = Arc::new(GasOracle::new(provider, metrics.clone()));
-
-                                                                            // Start analysis loop
-                                                                                let oracle_clone = oracle.clone();
-                                                                                    tokio::spawn(async move {
-                                                                                            let mut interval = interval(Duration::from_secs(poll_interval));
-                                                                                                    loop {
-                                                                                                                interval.tick().await;
-                                                                                                                            if let Err(e) = oracle_clone.analyze().await {
-                                                                                                                                            error!("Gas analysis error: {}", e);
-                                                                                                                                                        }
-                                                                                                                                                                }
-                                                                                                                                                                    });
-
-                                                                                                                                                                        // Start metrics HTTP server
-                                                                                                                                                                            let metrics_clone = metrics.clone();
-                                                                                                                                                                                let make_svc = make_service_fn(move |_| {
-                                                                                                                                                                                        let metrics = metrics_clone.clone();
-                                                                                                                                                                                                async move {
-                                                                                                                                                                                                            Ok::<_, hyper::Error>(service_fn(move |req| {
-                                                                                                                                                                                                                            serve_metrics(req, metrics.clone())
-                                                                                                                                                                                                                                        }))
-                                                                                                                                                                                                                                                }
-                                                                                                                                                                                                                                                    });
-
-                                                                                                                                                                                                                                                        let addr = ([0, 0, 0, 0], 9091).into();
-                                                                                                                                                                                                                                                            let server = Server::bind(&addr).serve(make_svc);
-                                                                                                                                                                                                                                                                
-                                                                                                                                                                                                                                                                    info!("Metrics server listening on http://{}", addr);
-                                                                                                                                                                                                                                                                        server.await?;
-
-                                                                                                                                                                                                                                                                            Ok(())
-                                                                                                                                                                                                                                                                            }
-
-                                                                                                                                                                                                                                                                            async fn serve_metrics(
-                                                                                                                                                                                                                                                                                req: Request<Body>,
-                                                                                                                                                                                                                                                                                    metrics: Arc<Metrics>,
-                                                                                                                                                                                                                                                                                    ) -> Result<Response<Body>, hyper::Error> {
-                                                                                                                                                                                                                                                                                        if req.uri().path() == "/metrics" {
-                                                                                                                                                                                                                                                                                                let encoder = TextEncoder::new();
-                                                                                                                                                                                                                                                                                                        let metric_families = metrics.registry.gather();
-                                                                                                                                                                                                                                                                                                                let mut buffer = vec![];
-                                                                                                                                                                                                                                                                                                                        encoder.encode(&metric_families, &mut buffer).unwrap();
-                                                                                                                                                                                                                                                                                                                                
-                                                                                                                                                                                                                                                                                                                                        Ok(Response::new(Body::from(buffer)))
-                                                                                                                                                                                                                                                                                                                                            } else if req.uri().path() == "/health" {
-                                                                                                                                                                                                                                                                                                                                                    Ok(Response::new(Body::from("OK")))
-                                                                                                                                                                                                                                                                                                                                                        } else {
-                                                                                                                                                                                                                                                                                                                                                                Ok(Response::builder()
-                                                                                                                                                                                                                                                                                                                                                                            .status(404)
-                                                                                                                                                                                                                                                                                                                                                                                        .body(Body::from("Not Found"))
-                                                                                                                                                                                                                                                                                                                                                                                                    .unwrap())
-                                                                                                                                                                                                                                                                                                                                                                                                        }
-                                                                                                                                                                                                                                                                                                                                                                                                        }
-                                                                                                                                                                                                                                                                                                                                                                                                        
\ No newline at end of file
+
+        self.metrics.set_gas_utilization(utilization);
+        self.metrics.set_avg_tx_per_block(tx_count as f64 / window.len() as f64);
+
+        info!(
+            "Analyzed {} blocks: {} txs, {:.2}% utilization",
+            window.len(),
+            tx_count,
+            utilization
+        );
+    }
+
+    /// The "standard" recommendation: median gas price over the window.
+    pub async fn recommended_gas_price(&self) -> Option<u128> {
+        let window = self.window.read().await;
+
+        let mut prices: Vec<u128> = window
+            .iter()
+            .flat_map(|sample| sample.gas_prices.iter().copied())
+            .collect();
+        if prices.is_empty() {
+            return None;
+        }
+        prices.sort_unstable();
+
+        Some(percentile(&prices, 50.0))
+    }
+
+    /// Snapshot of the rolling window, oldest block first.
+    pub async fn samples(&self) -> Vec<BlockSample> {
+        self.window.read().await.iter().cloned().collect()
+    }
+
+    /// Restores a checkpointed window; the next pass fetches only newer blocks.
+    pub async fn restore(&self, samples: Vec<BlockSample>) {
+        let mut window = self.window.write().await;
+        *window = samples.into();
+        while window.len() > self.window_size {
+            window.pop_front();
+        }
+        self.update_metrics(&window);
+    }
+}
+
+/// Nearest-rank percentile of an ascending slice; 0 when empty.
+pub fn percentile(sorted: &[u128], p: f64) -> u128 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
@@ -1,89 +1,242 @@
 use anyhow::Result;
-use ethers::providers::{Provider, Http, Middleware};
+use ethers::providers::Provider;
 use hyper::{
     service::{make_service_fn, service_fn},
-        Body, Request, Response, Server,
-        };
-        use prometheus::{Encoder, TextEncoder};
-        use std::sync::Arc;
-        use tokio::time::{interval, Duration};
-        use tracing::{info, error};
-
-        mod gas_oracle;
-        mod metrics;
-
-        use gas_oracle::GasOracle;
-        use metrics::Metrics;
-
-        #[tokio::main]
-        async fn main() -> Result<()> {
-            tracing_subscriber::fmt::init();
-
-                let rpc_url = std::env::var("RPC_URL")
-                        .unwrap_or_else(|_| "http://mordor-node:8545".to_string());
-                            
-                                let poll_interval = std::env::var("POLL_INTERVAL_SECS")
-                                        .unwrap_or_else(|_| "12".to_string())
-                                                .parse::<u64>()?;
-
-                                                    info!("Starting Mordor Gas Estimator");
-                                                        info!("RPC URL: {}", rpc_url);
-                                                            info!("Poll interval: {}s", poll_interval);
-
-                                                                let provider = Provider::<Http>::try_from(&rpc_url)?;
-                                                                    let metrics = Arc::new(Metrics::new());
-                                                                        let oracle = Arc::new(GasOracle::new(provider, metrics.clone()));
-
-                                                                            // Start analysis loop
-                                                                                let oracle_clone = oracle.clone();
-                                                                                    tokio::spawn(async move {
-                                                                                            let mut interval = interval(Duration::from_secs(poll_interval));
-                                                                                                    loop {
-                                                                                                                interval.tick().await;
-                                                                                                                            if let Err(e) = oracle_clone.analyze().await {
-                                                                                                                                            error!("Gas analysis error: {}", e);
-                                                                                                                                                        }
-                                                                                                                                                                }
-                                                                                                                                                                    });
-
-                                                                                                                                                                        // Start metrics HTTP server
-                                                                                                                                                                            let metrics_clone = metrics.clone();
-                                                                                                                                                                                let make_svc = make_service_fn(move |_| {
-                                                                                                                                                                                        let metrics = metrics_clone.clone();
-                                                                                                                                                                                                async move {
-                                                                                                                                                                                                            Ok::<_, hyper::Error>(service_fn(move |req| {
-                                                                                                                                                                                                                            serve_metrics(req, metrics.clone())
-                                                                                                                                                                                                                                        }))
-                                                                                                                                                                                                                                                }
-                                                                                                                                                                                                                                                    });
-
-                                                                                                                                                                                                                                                        let addr = ([0, 0, 0, 0], 9091).into();
-                                                                                                                                                                                                                                                            let server = Server::bind(&addr).serve(make_svc);
-                                                                                                                                                                                                                                                                
-                                                                                                                                                                                                                                                                    info!("Metrics server listening on http://{}", addr);
-                                                                                                                                                                                                                                                                        server.await?;
-
-                                                                                                                                                                                                                                                                            Ok(())
-                                                                                                                                                                                                                                                                            }
-
-                                                                                                                                                                                                                                                                            async fn serve_metrics(
-                                                                                                                                                                                                                                                                                req: Request<Body>,
-                                                                                                                                                                                                                                                                                    metrics: Arc<Metrics>,
-                                                                                                                                                                                                                                                                                    ) -> Result<Response<Body>, hyper::Error> {
-                                                                                                                                                                                                                                                                                        if req.uri().path() == "/metrics" {
-                                                                                                                                                                                                                                                                                                let encoder = TextEncoder::new();
-                                                                                                                                                                                                                                                                                                        let metric_families = metrics.registry.gather();
-                                                                                                                                                                                                                                                                                                                let mut buffer = vec![];
-                                                                                                                                                                                                                                                                                                                        encoder.encode(&metric_families, &mut buffer).unwrap();
-                                                                                                                                                                                                                                                                                                                                
-                                                                                                                                                                                                                                                                                                                                        Ok(Response::new(Body::from(buffer)))
-                                                                                                                                                                                                                                                                                                                                            } else if req.uri().path() == "/health" {
-                                                                                                                                                                                                                                                                                                                                                    Ok(Response::new(Body::from("OK")))
-                                                                                                                                                                                                                                                                                                                                                        } else {
-                                                                                                                                                                                                                                                                                                                                                                Ok(Response::builder()
-                                                                                                                                                                                                                                                                                                                                                                            .status(404)
-                                                                                                                                                                                                                                                                                                                                                                                        .body(Body::from("Not Found"))
-                                                                                                                                                                                                                                                                                                                                                                                                    .unwrap())
-                                                                                                                                                                                                                                                                                                                                                                                                        }
-                                                                                                                                                                                                                                                                                                                                                                                                        }
-                                                                                                                                                                                                                                                                                                                                                                                                        
\ No newline at end of file
+    Body, Request, Response, Server,
+};
+use mordor_common::checkpoint::Checkpoint;
+use mordor_common::shutdown::Shutdown;
+use mordor_common::telemetry::{self, TracedHttp};
+use prometheus::{Encoder, TextEncoder};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::time::{interval, interval_at, Duration, Instant};
+use tracing::{error, info};
+
+mod analytics;
+mod gas_oracle;
+mod metrics;
+mod proxy;
+
+use analytics::{BlockUsage, GasAnalytics};
+use gas_oracle::{BlockSample, GasOracle};
+use metrics::Metrics;
+use proxy::RpcProxy;
+
+// The rolling windows a restart resumes from
+#[derive(Serialize, Deserialize)]
+struct GasState {
+    oracle: Vec<BlockSample>,
+    analytics: Vec<(u64, BlockUsage)>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let telemetry = telemetry::init("gas-estimator")?;
+
+    let rpc_url =
+        std::env::var("RPC_URL").unwrap_or_else(|_| "http://mordor-node:8545".to_string());
+
+    let poll_interval = std::env::var("POLL_INTERVAL_SECS")
+        .unwrap_or_else(|_| "12".to_string())
+        .parse::<u64>()?;
+
+    let top_n = std::env::var("ANALYTICS_TOP_N")
+        .unwrap_or_else(|_| "10".to_string())
+        .parse::<usize>()?;
+
+    let proxy_enabled = std::env::var("PROXY_ENABLED")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+
+    let proxy_port = std::env::var("PROXY_PORT")
+        .unwrap_or_else(|_| "8547".to_string())
+        .parse::<u16>()?;
+
+    let checkpoint_interval = std::env::var("CHECKPOINT_INTERVAL_SECS")
+        .unwrap_or_else(|_| "60".to_string())
+        .parse::<u64>()?
+        .max(1);
+
+    let shutdown_timeout = std::env::var("SHUTDOWN_TIMEOUT_SECS")
+        .unwrap_or_else(|_| "8".to_string())
+        .parse::<u64>()?;
+
+    info!("Starting Mordor Gas Estimator");
+    info!("RPC URL: {}", rpc_url);
+    info!("Poll interval: {}s", poll_interval);
+
+    let provider = Provider::new(TracedHttp::new(&rpc_url)?);
+    let metrics = Arc::new(Metrics::new());
+    telemetry.export_metrics(metrics.registry.clone());
+    let oracle = Arc::new(GasOracle::new(provider.clone(), metrics.clone()));
+    let analytics = Arc::new(GasAnalytics::new(provider, metrics.clone(), top_n));
+
+    let checkpoint = Checkpoint::from_env("/data/gas-estimator-state.json").map(Arc::new);
+    if let Some(state) = checkpoint.as_ref().and_then(|c| c.load::<GasState>()) {
+        info!(
+            "Resuming from checkpointed windows: {} oracle blocks, {} analytics blocks",
+            state.oracle.len(),
+            state.analytics.len()
+        );
+        oracle.restore(state.oracle).await;
+        analytics.restore(state.analytics).await;
+    }
+
+    // Docker sends SIGKILL 10s after SIGTERM, so finish before that
+    let shutdown = Shutdown::listen(Duration::from_secs(shutdown_timeout))?;
+
+    // Start analysis loop
+    let oracle_clone = oracle.clone();
+    let shutdown_clone = shutdown.clone();
+    let oracle_task = tokio::spawn(async move {
+        let mut interval = interval(Duration::from_secs(poll_interval));
+        loop {
+            tokio::select! {
+                biased;
+                _ = shutdown_clone.clone().requested() => break,
+                _ = interval.tick() => {}
+            }
+            if let Err(e) = oracle_clone.analyze().await {
+                error!("Gas analysis error: {}", e);
+            }
+        }
+    });
+
+    // Start contract analytics loop
+    let analytics_clone = analytics.clone();
+    let shutdown_clone = shutdown.clone();
+    let analytics_task = tokio::spawn(async move {
+        let mut interval = interval(Duration::from_secs(poll_interval));
+        loop {
+            tokio::select! {
+                biased;
+                _ = shutdown_clone.clone().requested() => break,
+                _ = interval.tick() => {}
+            }
+            if let Err(e) = analytics_clone.analyze().await {
+                error!("Gas analytics error: {}", e);
+            }
+        }
+    });
+
+    // Both windows are saved from this one task, so checkpoints are written
+    // in order and the 24h history isn't rewritten after every poll
+    let checkpoint_task = checkpoint.clone().map(|checkpoint| {
+        let (oracle, analytics) = (oracle.clone(), analytics.clone());
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            let period = Duration::from_secs(checkpoint_interval);
+            let mut interval = interval_at(Instant::now() + period, period);
+            loop {
+                tokio::select! {
+                    biased;
+                    _ = shutdown.clone().requested() => break,
+                    _ = interval.tick() => {}
+                }
+                save_state(&checkpoint, &oracle, &analytics).await;
+            }
+        })
+    });
+
+    // Start JSON-RPC proxy
+    let mut proxy_task = None;
+    if proxy_enabled {
+        let proxy = Arc::new(RpcProxy::new(&rpc_url, oracle.clone())?);
+        let make_proxy_svc = make_service_fn(move |_| {
+            let proxy = proxy.clone();
+            async move {
+                Ok::<_, hyper::Error>(service_fn(move |req| {
+                    let proxy = proxy.clone();
+                    async move { proxy.handle(req).await }
+                }))
+            }
+        });
+
+        let proxy_addr = ([0, 0, 0, 0], proxy_port).into();
+        let proxy_server = Server::bind(&proxy_addr)
+            .serve(make_proxy_svc)
+            .with_graceful_shutdown(shutdown.clone().requested());
+
+        info!("JSON-RPC proxy listening on http://{}", proxy_addr);
+        proxy_task = Some(tokio::spawn(async move {
+            if let Err(e) = proxy_server.await {
+                error!("Proxy server error: {}", e);
+            }
+        }));
+    }
+
+    // Start metrics HTTP server
+    let metrics_clone = metrics.clone();
+    let analytics_clone = analytics.clone();
+    let make_svc = make_service_fn(move |_| {
+        let metrics = metrics_clone.clone();
+        let analytics = analytics_clone.clone();
+        async move {
+            Ok::<_, hyper::Error>(service_fn(move |req| {
+                serve_metrics(req, metrics.clone(), analytics.clone())
+            }))
+        }
+    });
+
+    let addr = ([0, 0, 0, 0], 9091).into();
+    let server = Server::bind(&addr)
+        .serve(make_svc)
+        .with_graceful_shutdown(shutdown.requested());
+
+    info!("Metrics server listening on http://{}", addr);
+    // Returns once shutdown is requested and in-flight responses are sent
+    server.await?;
+
+    for task in [Some(oracle_task), Some(analytics_task), checkpoint_task, proxy_task].into_iter().flatten() {
+        task.await?;
+    }
+    if let Some(checkpoint) = &checkpoint {
+        save_state(checkpoint, &oracle, &analytics).await;
+    }
+
+    telemetry.shutdown().await;
+    info!("Shutdown complete");
+    Ok(())
+}
+
+async fn save_state(checkpoint: &Checkpoint, oracle: &GasOracle, analytics: &GasAnalytics) {
+    checkpoint
+        .save(|| async {
+            GasState {
+                oracle: oracle.samples().await,
+                analytics: analytics.history().await,
+            }
+        })
+        .await;
+}
+
+async fn serve_metrics(
+    req: Request<Body>,
+    metrics: Arc<Metrics>,
+    analytics: Arc<GasAnalytics>,
+) -> Result<Response<Body>, hyper::Error> {
+    if req.uri().path() == "/metrics" {
+        let encoder = TextEncoder::new();
+        let metric_families = metrics.registry.gather();
+        let mut buffer = vec![];
+        encoder.encode(&metric_families, &mut buffer).unwrap();
+
+        Ok(Response::new(Body::from(buffer)))
+    } else if req.uri().path() == "/gas/top" {
+        let reports = analytics.reports().await;
+        let body = serde_json::to_vec(&reports).unwrap();
+
+        Ok(Response::builder()
+            .header("Content-Type", "application/json")
+            .body(Body::from(body))
+            .unwrap())
+    } else if req.uri().path() == "/health" {
+        Ok(Response::new(Body::from("OK")))
+    } else {
+        Ok(Response::builder()
+            .status(404)
+            .body(Body::from("Not Found"))
+            .unwrap())
+    }
+}
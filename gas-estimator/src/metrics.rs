@@ -0,0 +1,153 @@
+use prometheus::{Gauge, IntGaugeVec, Opts, Registry};
+
+pub struct Metrics {
+    pub registry: Registry,
+
+    // Gas price metrics
+    gas_price_min: Gauge,
+    gas_price_max: Gauge,
+    gas_price_median: Gauge,
+    gas_price_p25: Gauge,
+    gas_price_p75: Gauge,
+    gas_price_mean: Gauge,
+
+    // Network metrics
+    gas_utilization: Gauge,
+    avg_tx_per_block: Gauge,
+
+    // Contract analytics metrics
+    gas_used_by_kind: IntGaugeVec,
+    top_gas_consumers: IntGaugeVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let gas_price_min = Gauge::with_opts(
+            Opts::new("mordor_gas_price_min_wei", "Minimum gas price")
+                .namespace("etc"),
+        ).unwrap();
+
+        let gas_price_max = Gauge::with_opts(
+            Opts::new("mordor_gas_price_max_wei", "Maximum gas price")
+                .namespace("etc"),
+        ).unwrap();
+
+        let gas_price_median = Gauge::with_opts(
+            Opts::new("mordor_gas_price_median_wei", "Median gas price")
+                .namespace("etc"),
+        ).unwrap();
+
+        let gas_price_p25 = Gauge::with_opts(
+            Opts::new("mordor_gas_price_p25_wei", "25th percentile gas price")
+                .namespace("etc"),
+        ).unwrap();
+
+        let gas_price_p75 = Gauge::with_opts(
+            Opts::new("mordor_gas_price_p75_wei", "75th percentile gas price")
+                .namespace("etc"),
+        ).unwrap();
+
+        let gas_price_mean = Gauge::with_opts(
+            Opts::new("mordor_gas_price_mean_wei", "Mean gas price")
+                .namespace("etc"),
+        ).unwrap();
+
+        let gas_utilization = Gauge::with_opts(
+            Opts::new("mordor_gas_utilization_percent", "Gas utilization percentage")
+                .namespace("etc"),
+        ).unwrap();
+
+        let avg_tx_per_block = Gauge::with_opts(
+            Opts::new("mordor_avg_tx_per_block", "Average transactions per block")
+                .namespace("etc"),
+        ).unwrap();
+
+        let gas_used_by_kind = IntGaugeVec::new(
+            Opts::new("mordor_gas_used_by_kind", "Gas used per transaction kind over a rolling window")
+                .namespace("etc"),
+            &["window", "kind"],
+        ).unwrap();
+
+        let top_gas_consumers = IntGaugeVec::new(
+            Opts::new("mordor_top_gas_consumer_gas_used", "Gas used by the top contract/method consumers over a rolling window")
+                .namespace("etc"),
+            &["window", "kind", "contract", "selector"],
+        ).unwrap();
+
+        // Register all metrics
+        registry.register(Box::new(gas_price_min.clone())).unwrap();
+        registry.register(Box::new(gas_price_max.clone())).unwrap();
+        registry.register(Box::new(gas_price_median.clone())).unwrap();
+        registry.register(Box::new(gas_price_p25.clone())).unwrap();
+        registry.register(Box::new(gas_price_p75.clone())).unwrap();
+        registry.register(Box::new(gas_price_mean.clone())).unwrap();
+        registry.register(Box::new(gas_utilization.clone())).unwrap();
+        registry.register(Box::new(avg_tx_per_block.clone())).unwrap();
+        registry.register(Box::new(gas_used_by_kind.clone())).unwrap();
+        registry.register(Box::new(top_gas_consumers.clone())).unwrap();
+
+        Self {
+            registry,
+            gas_price_min,
+            gas_price_max,
+            gas_price_median,
+            gas_price_p25,
+            gas_price_p75,
+            gas_price_mean,
+            gas_utilization,
+            avg_tx_per_block,
+            gas_used_by_kind,
+            top_gas_consumers,
+        }
+    }
+
+    pub fn set_gas_price_min(&self, wei: f64) {
+        self.gas_price_min.set(wei);
+    }
+
+    pub fn set_gas_price_max(&self, wei: f64) {
+        self.gas_price_max.set(wei);
+    }
+
+    pub fn set_gas_price_median(&self, wei: f64) {
+        self.gas_price_median.set(wei);
+    }
+
+    pub fn set_gas_price_p25(&self, wei: f64) {
+        self.gas_price_p25.set(wei);
+    }
+
+    pub fn set_gas_price_p75(&self, wei: f64) {
+        self.gas_price_p75.set(wei);
+    }
+
+    pub fn set_gas_price_mean(&self, wei: f64) {
+        self.gas_price_mean.set(wei);
+    }
+
+    pub fn set_gas_utilization(&self, percent: f64) {
+        self.gas_utilization.set(percent);
+    }
+
+    pub fn set_avg_tx_per_block(&self, count: f64) {
+        self.avg_tx_per_block.set(count);
+    }
+
+    pub fn set_gas_used_by_kind(&self, window: &str, kind: &str, gas: u64) {
+        self.gas_used_by_kind
+            .with_label_values(&[window, kind])
+            .set(gas as i64);
+    }
+
+    pub fn reset_top_gas_consumers(&self) {
+        self.top_gas_consumers.reset();
+    }
+
+    pub fn set_top_gas_consumer(&self, window: &str, kind: &str, contract: &str, selector: &str, gas: u64) {
+        self.top_gas_consumers
+            .with_label_values(&[window, kind, contract, selector])
+            .set(gas as i64);
+    }
+}
@@ -0,0 +1,351 @@
+use anyhow::Result;
+use hyper::client::HttpConnector;
+use hyper::header::{CONTENT_TYPE, HOST};
+use hyper::{Body, Client, Method, Request, Response, StatusCode, Uri};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing::{debug, field, instrument, warn, Span};
+
+use crate::gas_oracle::{BlockSample, GasOracle};
+
+// JSON-RPC "invalid params" error code
+const INVALID_PARAMS: i64 = -32602;
+
+/// JSON-RPC pass-through in front of the node. `eth_gasPrice` and
+/// `eth_feeHistory` are answered from the oracle's rolling window; every
+/// other call is forwarded to the upstream node as-is.
+pub struct RpcProxy {
+    upstream: Uri,
+    client: Client<HttpConnector>,
+    oracle: Arc<GasOracle>,
+}
+
+impl RpcProxy {
+    pub fn new(upstream: &str, oracle: Arc<GasOracle>) -> Result<Self> {
+        Ok(Self {
+            upstream: upstream.parse()?,
+            client: Client::new(),
+            oracle,
+        })
+    }
+
+    #[instrument(name = "proxy.request", skip_all, fields(otel.kind = "server"))]
+    pub async fn handle(&self, req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
+        let (parts, body) = req.into_parts();
+        let bytes = hyper::body::to_bytes(body).await?;
+
+        if parts.method == Method::POST {
+            match serde_json::from_slice::<Value>(&bytes) {
+                Ok(Value::Object(call)) => {
+                    if let Some(response) = self.intercept(&Value::Object(call)).await {
+                        return Ok(json_response(&response));
+                    }
+                }
+                Ok(Value::Array(calls)) => {
+                    if let Some(response) = self.handle_batch(calls).await {
+                        return Ok(json_response(&response));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(self.forward(parts.method, parts.headers, bytes.to_vec()).await)
+    }
+
+    // Answers the intercepted calls of a batch locally and forwards the rest
+    // as a smaller batch. Returns None when nothing in the batch is ours, so
+    // the original body is forwarded untouched.
+    async fn handle_batch(&self, calls: Vec<Value>) -> Option<Value> {
+        let mut answered = Vec::new();
+        let mut forwarded = Vec::new();
+
+        for call in calls {
+            match self.intercept(&call).await {
+                Some(response) => answered.push(response),
+                None => forwarded.push(call),
+            }
+        }
+
+        if answered.is_empty() {
+            return None;
+        }
+
+        if !forwarded.is_empty() {
+            let body = serde_json::to_vec(&Value::Array(forwarded)).unwrap_or_default();
+            let mut headers = hyper::HeaderMap::new();
+            headers.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+            let response = self.forward(Method::POST, headers, body).await;
+            let bytes = hyper::body::to_bytes(response.into_body()).await.ok()?;
+
+            match serde_json::from_slice::<Value>(&bytes) {
+                Ok(Value::Array(responses)) => answered.extend(responses),
+                Ok(other) => answered.push(other),
+                Err(e) => warn!("Unparseable upstream batch response: {}", e),
+            }
+        }
+
+        Some(Value::Array(answered))
+    }
+
+    async fn intercept(&self, call: &Value) -> Option<Value> {
+        let method = call.get("method")?.as_str()?;
+        let id = call.get("id").cloned().unwrap_or(Value::Null);
+        let params = call.get("params").cloned().unwrap_or(Value::Null);
+
+        match method {
+            "eth_gasPrice" => {
+                let price = self.oracle.recommended_gas_price().await?;
+                debug!("Answering eth_gasPrice with {} wei", price);
+                Some(rpc_result(id, json!(format!("{:#x}", price))))
+            }
+            "eth_feeHistory" => match fee_history(&params, &self.oracle.samples().await) {
+                Ok(Some(history)) => Some(rpc_result(id, history)),
+                Ok(None) => None,
+                Err(message) => Some(rpc_error(id, INVALID_PARAMS, &message)),
+            },
+            _ => None,
+        }
+    }
+
+    #[instrument(name = "proxy.forward", skip_all, fields(otel.kind = "client", otel.status_message = field::Empty))]
+    async fn forward(&self, method: Method, headers: hyper::HeaderMap, body: Vec<u8>) -> Response<Body> {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(self.upstream.clone())
+            .body(Body::from(body))
+            .unwrap();
+
+        for (name, value) in headers.iter() {
+            if name != HOST {
+                request.headers_mut().append(name, value.clone());
+            }
+        }
+
+        match self.client.request(request).await {
+            Ok(response) => response,
+            Err(e) => {
+                Span::current().record("otel.status_message", field::display(&e));
+                warn!("Upstream RPC request failed: {}", e);
+                Response::builder()
+                    .status(StatusCode::BAD_GATEWAY)
+                    .body(Body::from("Bad Gateway"))
+                    .unwrap()
+            }
+        }
+    }
+}
+
+// Builds an eth_feeHistory result from the rolling window's samples,
+// oldest first. ETC has no base fee, so baseFeePerGas is all zeros and the
+// reward percentiles are taken over the full gas price of each block's
+// transactions. Returns Ok(None) when the requested range isn't covered by
+// the window, so the call is forwarded.
+fn fee_history(params: &Value, samples: &[BlockSample]) -> Result<Option<Value>, String> {
+    let block_count = params
+        .get(0)
+        .and_then(parse_quantity)
+        .ok_or("blockCount must be a quantity")?;
+
+    let newest_block = match params.get(1).and_then(Value::as_str) {
+        Some("latest") | Some("pending") | None => None,
+        // Valid tags the window can't tell apart from the tip
+        Some("earliest") | Some("safe") | Some("finalized") => return Ok(None),
+        Some(tag) => Some(
+            parse_quantity(&Value::String(tag.to_string()))
+                .ok_or("newestBlock must be a block number or tag")?,
+        ),
+    };
+
+    let percentiles = match params.get(2) {
+        Some(Value::Array(values)) => {
+            let mut percentiles = Vec::with_capacity(values.len());
+            for value in values {
+                let p = value.as_f64().ok_or("rewardPercentiles must be numbers")?;
+                if !(0.0..=100.0).contains(&p) {
+                    return Err(format!("reward percentile {} out of range", p));
+                }
+                if percentiles.last().is_some_and(|&last| p < last) {
+                    return Err("rewardPercentiles must be monotonically increasing".into());
+                }
+                percentiles.push(p);
+            }
+            Some(percentiles)
+        }
+        Some(Value::Null) | None => None,
+        Some(_) => return Err("rewardPercentiles must be an array".into()),
+    };
+
+    if block_count == 0 {
+        return Ok(None);
+    }
+
+    let newest_index = match newest_block {
+        None if !samples.is_empty() => samples.len() - 1,
+        None => return Ok(None),
+        Some(number) => match samples.iter().position(|sample| sample.number == number) {
+            Some(index) => index,
+            None => return Ok(None),
+        },
+    };
+
+    let count = (block_count as usize).min(newest_index + 1);
+    let range = &samples[newest_index + 1 - count..=newest_index];
+
+    let mut history = json!({
+        "oldestBlock": format!("{:#x}", range[0].number),
+        "baseFeePerGas": vec!["0x0"; count + 1],
+        "gasUsedRatio": range.iter().map(|sample| sample.gas_used_ratio()).collect::<Vec<_>>(),
+    });
+
+    if let Some(percentiles) = percentiles {
+        let reward: Vec<Vec<String>> = range
+            .iter()
+            .map(|sample| {
+                percentiles
+                    .iter()
+                    .map(|&p| format!("{:#x}", reward(sample, p)))
+                    .collect()
+            })
+            .collect();
+        history["reward"] = json!(reward);
+    }
+
+    Ok(Some(history))
+}
+
+// As in eth_feeHistory, each transaction counts by its gas: the reward is
+// the price at which the cheaper transactions cover `p` percent of the
+// block's gas. Receipts aren't fetched, so gas limits stand in for gas used;
+// samples checkpointed without them count every transaction once.
+fn reward(sample: &BlockSample, p: f64) -> u128 {
+    let prices = &sample.gas_prices;
+    if prices.is_empty() {
+        return 0;
+    }
+    let gas = |index: usize| match sample.gas_limits.len() == prices.len() {
+        true => sample.gas_limits[index],
+        false => 1,
+    };
+
+    let total: u64 = (0..prices.len()).map(gas).sum();
+    let threshold = (total as f64 * p / 100.0) as u64;
+    let mut index = 0;
+    let mut covered = gas(0);
+    while covered < threshold && index < prices.len() - 1 {
+        index += 1;
+        covered += gas(index);
+    }
+    prices[index]
+}
+
+fn parse_quantity(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok(),
+            None => s.parse().ok(),
+        },
+        _ => None,
+    }
+}
+
+fn rpc_result(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn rpc_error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn json_response(value: &Value) -> Response<Body> {
+    Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(value.to_string()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::H256;
+
+    const GWEI: u128 = 1_000_000_000;
+
+    // Blocks 100-104; block n has half its gas used and prices 1..=n-99 gwei,
+    // all plain transfers
+    fn window() -> Vec<BlockSample> {
+        (100..105)
+            .map(|number| BlockSample {
+                number,
+                hash: H256::from_low_u64_be(number),
+                gas_used: 4_000_000,
+                gas_limit: 8_000_000,
+                tx_count: (number - 99) as usize,
+                gas_prices: (1..=(number - 99) as u128).map(|gwei| gwei * GWEI).collect(),
+                gas_limits: vec![21_000; (number - 99) as usize],
+            })
+            .collect()
+    }
+
+    fn gwei(value: u128) -> String {
+        format!("{:#x}", value * GWEI)
+    }
+
+    #[test]
+    fn synthesizes_latest_blocks_with_rewards() {
+        let history = fee_history(&json!(["0x2", "latest", [25, 75]]), &window()).unwrap().unwrap();
+
+        assert_eq!(history["oldestBlock"], "0x67");
+        assert_eq!(history["baseFeePerGas"], json!(["0x0", "0x0", "0x0"]));
+        assert_eq!(history["gasUsedRatio"], json!([0.5, 0.5]));
+        // Equal gas: the first price covering 25% and 75% of 4 and 5 transfers
+        assert_eq!(history["reward"], json!([[gwei(1), gwei(3)], [gwei(2), gwei(4)]]));
+    }
+
+    #[test]
+    fn rewards_are_weighted_by_gas() {
+        let mut samples = window();
+        // The 3 gwei transaction carries most of block 102's gas
+        samples[2].gas_limits = vec![21_000, 21_000, 100_000];
+        let history = fee_history(&json!([1, "0x66", [10, 50, 100]]), &samples).unwrap().unwrap();
+
+        assert_eq!(history["reward"], json!([[gwei(1), gwei(3), gwei(3)]]));
+
+        // Samples checkpointed without gas limits count each transaction once
+        samples[2].gas_limits.clear();
+        let history = fee_history(&json!([1, "0x66", [10, 50, 100]]), &samples).unwrap().unwrap();
+
+        assert_eq!(history["reward"], json!([[gwei(1), gwei(1), gwei(3)]]));
+    }
+
+    #[test]
+    fn clips_block_count_to_the_window() {
+        let history = fee_history(&json!([10, "0x65"]), &window()).unwrap().unwrap();
+
+        assert_eq!(history["oldestBlock"], "0x64");
+        assert_eq!(history["gasUsedRatio"].as_array().unwrap().len(), 2);
+        assert!(history.get("reward").is_none());
+    }
+
+    #[test]
+    fn forwards_ranges_outside_the_window() {
+        assert_eq!(fee_history(&json!([1, "0x63"]), &window()), Ok(None));
+        assert_eq!(fee_history(&json!([0, "latest"]), &window()), Ok(None));
+        assert_eq!(fee_history(&json!([1, "latest"]), &[]), Ok(None));
+        assert_eq!(fee_history(&json!([1, "earliest"]), &window()), Ok(None));
+        assert_eq!(fee_history(&json!([1, "finalized", [50]]), &window()), Ok(None));
+    }
+
+    #[test]
+    fn rejects_invalid_params() {
+        let error = |params: Value| fee_history(&params, &window()).unwrap_err();
+
+        assert_eq!(error(json!([])), "blockCount must be a quantity");
+        assert_eq!(error(json!([1, "tip"])), "newestBlock must be a block number or tag");
+        assert_eq!(error(json!([1, "latest", [50, 10]])), "rewardPercentiles must be monotonically increasing");
+        assert_eq!(error(json!([1, "latest", [101]])), "reward percentile 101 out of range");
+        assert_eq!(error(json!([1, "latest", "50"])), "rewardPercentiles must be an array");
+    }
+}
@@ -0,0 +1,265 @@
+use anyhow::{anyhow, bail, Result};
+use ethers::providers::{Middleware, Provider};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use mordor_common::telemetry::TracedHttp;
+use std::sync::Arc;
+use tracing::{info, instrument, warn};
+
+use crate::metrics::Metrics;
+use crate::store::{IndexedBlock, Store};
+
+// Give up rather than unwind further than this on a single reorg
+const MAX_REORG_DEPTH: u64 = 1_000;
+
+pub struct IndexerConfig {
+    pub start_block: u64,
+    pub end_block: Option<u64>,
+    pub concurrency: usize,
+    pub batch_size: u64,
+}
+
+/// Backfills blocks into the store in batches fetched with bounded
+/// parallelism, then follows the tip. Each batch must link to the stored
+/// chain by parent hash; when it doesn't, the orphaned heights are rolled
+/// back to the common ancestor and indexing resumes from there.
+pub struct Indexer {
+    provider: Provider<TracedHttp>,
+    store: Arc<Store>,
+    metrics: Arc<Metrics>,
+    config: IndexerConfig,
+}
+
+impl Indexer {
+    pub fn new(
+        provider: Provider<TracedHttp>,
+        store: Arc<Store>,
+        metrics: Arc<Metrics>,
+        config: IndexerConfig,
+    ) -> Self {
+        Self {
+            provider,
+            store,
+            metrics,
+            config,
+        }
+    }
+
+    // Indexes at most one batch. Returns true once the index has caught up
+    // with the tip, or with END_BLOCK when one is set.
+    #[instrument(name = "indexer.sync", skip_all)]
+    pub async fn sync(&self) -> Result<bool> {
+        let tip = self.provider.get_block_number().await?.as_u64();
+        let target = match self.config.end_block {
+            Some(end) => end.min(tip),
+            None => tip,
+        };
+
+        let checkpoint = self.store.checkpoint()?;
+        let next = match checkpoint {
+            Some((number, _)) => number + 1,
+            None => self.config.start_block,
+        };
+
+        self.metrics.set_chain_height(tip);
+        if let Some((number, _)) = checkpoint {
+            self.metrics.set_indexed_height(number);
+        }
+        self.metrics.set_lag_blocks(tip.saturating_sub(next.saturating_sub(1)));
+
+        if next > target {
+            return Ok(self.config.end_block.map_or(true, |end| next > end));
+        }
+
+        let last = target.min(next + self.config.batch_size - 1);
+        let blocks: Vec<Option<IndexedBlock>> = stream::iter(next..=last)
+            .map(|number| self.fetch_block(number))
+            .buffered(self.config.concurrency)
+            .try_collect()
+            .await?;
+
+        // The node may not serve every block up to the tip it just reported
+        let blocks: Vec<IndexedBlock> = blocks.into_iter().map_while(|block| block).collect();
+        let first = match blocks.first() {
+            Some(first) => first,
+            None => return Ok(false),
+        };
+
+        if let Some((number, hash)) = checkpoint {
+            if first.block.parent_hash != hash {
+                warn!(
+                    "Block {} does not extend indexed block {} ({:?}), rolling back",
+                    first.number(),
+                    number,
+                    hash
+                );
+                self.rollback(number).await?;
+                return Ok(false);
+            }
+        }
+
+        // A reorg while the batch was being fetched breaks the linkage inside
+        // it; keep the linked prefix and pick the rest up on the next pass
+        let mut linked = 1;
+        while linked < blocks.len() && blocks[linked].block.parent_hash == blocks[linked - 1].hash() {
+            linked += 1;
+        }
+        let blocks = &blocks[..linked];
+
+        self.store.insert_blocks(blocks)?;
+
+        let indexed = blocks.last().map(|block| block.number()).unwrap_or(next);
+        let tx_count: usize = blocks.iter().map(|block| block.block.transactions.len()).sum();
+        self.metrics.inc_blocks_indexed(blocks.len() as u64);
+        self.metrics.inc_transactions_indexed(tx_count as u64);
+        self.metrics.set_indexed_height(indexed);
+        self.metrics.set_lag_blocks(tip.saturating_sub(indexed));
+
+        info!(
+            "Indexed blocks {}-{} ({} txs), {} behind tip",
+            next,
+            indexed,
+            tx_count,
+            tip.saturating_sub(indexed)
+        );
+
+        Ok(indexed >= target && self.config.end_block.map_or(true, |end| indexed >= end))
+    }
+
+    #[instrument(name = "block.process", skip(self))]
+    async fn fetch_block(&self, number: u64) -> Result<Option<IndexedBlock>> {
+        let block = match self.provider.get_block_with_txs(number).await? {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+
+        let mut receipts = Vec::with_capacity(block.transactions.len());
+        for tx in &block.transactions {
+            let receipt = self
+                .provider
+                .get_transaction_receipt(tx.hash)
+                .await?
+                .ok_or_else(|| anyhow!("Missing receipt for {:?} in block {}", tx.hash, number))?;
+            receipts.push(receipt);
+        }
+
+        Ok(Some(IndexedBlock { block, receipts }))
+    }
+
+    // Walks back from `from` until the stored hash matches the canonical one
+    // and drops everything above that common ancestor
+    #[instrument(name = "indexer.rollback", skip(self))]
+    async fn rollback(&self, from: u64) -> Result<()> {
+        let mut number = from;
+
+        loop {
+            let stored = match self.store.hash_at(number)? {
+                Some(hash) => hash,
+                None => break,
+            };
+
+            let canonical = self.provider.get_block(number).await?.and_then(|block| block.hash);
+            if canonical == Some(stored) {
+                break;
+            }
+
+            if from - number >= MAX_REORG_DEPTH {
+                bail!("Reorg below block {} is deeper than {} blocks", from, MAX_REORG_DEPTH);
+            }
+            if number == 0 {
+                bail!("Indexed genesis block does not match the node");
+            }
+            number -= 1;
+        }
+
+        let removed = self.store.rollback_to(number)?;
+        self.metrics.inc_reorg_rollbacks();
+        self.metrics.inc_rolled_back_blocks(removed as u64);
+        self.metrics.set_indexed_height(number);
+
+        warn!("Rolled back {} orphaned blocks above {}", removed, number);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{Block, Transaction, H256};
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server};
+    use serde_json::{json, Value};
+
+    fn hash(number: u64, branch: u64) -> H256 {
+        H256::from_low_u64_be((branch << 32) + number + 1)
+    }
+
+    fn block(number: u64) -> IndexedBlock {
+        IndexedBlock {
+            block: Block::<Transaction> {
+                number: Some(number.into()),
+                hash: Some(hash(number, 0)),
+                parent_hash: number.checked_sub(1).map(|parent| hash(parent, 0)).unwrap_or_default(),
+                ..Default::default()
+            },
+            receipts: Vec::new(),
+        }
+    }
+
+    // Serves eth_getBlockByNumber for a chain that forked away from the
+    // indexed one at `fork`
+    fn node(fork: u64) -> String {
+        let make_svc = make_service_fn(move |_| async move {
+            Ok::<_, hyper::Error>(service_fn(move |req: Request<Body>| async move {
+                let body = hyper::body::to_bytes(req.into_body()).await?;
+                let request: Value = serde_json::from_slice(&body).unwrap();
+                let number = request["params"][0].as_str().unwrap().trim_start_matches("0x");
+                let number = u64::from_str_radix(number, 16).unwrap();
+                let branch = if number >= fork { 1 } else { 0 };
+                let response = json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": {"number": format!("{:#x}", number), "hash": hash(number, branch), "transactions": []},
+                });
+                Ok::<_, hyper::Error>(Response::new(Body::from(response.to_string())))
+            }))
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        url
+    }
+
+    fn indexer(node: &str, store: Arc<Store>) -> Indexer {
+        let provider = Provider::new(TracedHttp::new(node).unwrap());
+        let config = IndexerConfig {
+            start_block: 0,
+            end_block: None,
+            concurrency: 1,
+            batch_size: 10,
+        };
+        Indexer::new(provider, store, Arc::new(Metrics::new()), config)
+    }
+
+    #[tokio::test]
+    async fn rollback_stops_at_common_ancestor() {
+        let store = Arc::new(Store::open(":memory:").unwrap());
+        store.insert_blocks(&(0..10).map(block).collect::<Vec<_>>()).unwrap();
+
+        indexer(&node(7), store.clone()).rollback(9).await.unwrap();
+
+        assert_eq!(store.checkpoint().unwrap(), Some((6, hash(6, 0))));
+        assert_eq!(store.hash_at(7).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn rollback_gives_up_on_a_different_genesis() {
+        let store = Arc::new(Store::open(":memory:").unwrap());
+        store.insert_blocks(&(0..3).map(block).collect::<Vec<_>>()).unwrap();
+
+        let error = indexer(&node(0), store.clone()).rollback(2).await.unwrap_err();
+
+        assert!(error.to_string().contains("genesis"), "{}", error);
+        assert_eq!(store.checkpoint().unwrap(), Some((2, hash(2, 0))));
+    }
+}
@@ -0,0 +1,202 @@
+use anyhow::Result;
+use ethers::providers::Provider;
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server,
+};
+use mordor_common::shutdown::Shutdown;
+use mordor_common::telemetry::{self, TracedHttp};
+use prometheus::{Encoder, TextEncoder};
+use std::sync::Arc;
+use tokio::sync::oneshot;
+use tokio::time::{sleep, Duration};
+use tracing::{error, info};
+
+mod indexer;
+mod metrics;
+mod store;
+
+use indexer::{Indexer, IndexerConfig};
+use metrics::Metrics;
+use store::Store;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let telemetry = telemetry::init("mordor-indexer")?;
+
+    let rpc_url =
+        std::env::var("RPC_URL").unwrap_or_else(|_| "http://mordor-node:8545".to_string());
+
+    let db_path =
+        std::env::var("INDEXER_DB").unwrap_or_else(|_| "/data/mordor-index.db".to_string());
+
+    let poll_interval = std::env::var("POLL_INTERVAL_SECS")
+        .unwrap_or_else(|_| "5".to_string())
+        .parse::<u64>()?;
+
+    let start_block = std::env::var("START_BLOCK")
+        .unwrap_or_else(|_| "0".to_string())
+        .parse::<u64>()?;
+
+    let end_block = match std::env::var("END_BLOCK") {
+        Ok(value) => Some(value.parse::<u64>()?),
+        Err(_) => None,
+    };
+
+    let concurrency = std::env::var("INDEXER_CONCURRENCY")
+        .unwrap_or_else(|_| "8".to_string())
+        .parse::<usize>()?
+        .max(1);
+
+    let batch_size = std::env::var("BATCH_SIZE")
+        .unwrap_or_else(|_| "100".to_string())
+        .parse::<u64>()?
+        .max(1);
+
+    let shutdown_timeout = std::env::var("SHUTDOWN_TIMEOUT_SECS")
+        .unwrap_or_else(|_| "8".to_string())
+        .parse::<u64>()?;
+
+    info!("Starting Mordor Indexer");
+    info!("RPC URL: {}", rpc_url);
+    info!("Database: {}", db_path);
+
+    let provider = Provider::new(TracedHttp::new(&rpc_url)?);
+    let store = Arc::new(Store::open(&db_path)?);
+    let metrics = Arc::new(Metrics::new());
+    telemetry.export_metrics(metrics.registry.clone());
+
+    match store.checkpoint()? {
+        Some((number, hash)) => info!("Resuming after checkpoint {} ({:?})", number, hash),
+        None => info!("No checkpoint, starting at block {}", start_block),
+    }
+
+    let indexer = Indexer::new(
+        provider,
+        store.clone(),
+        metrics.clone(),
+        IndexerConfig {
+            start_block,
+            end_block,
+            concurrency,
+            batch_size,
+        },
+    );
+
+    // Docker sends SIGKILL 10s after SIGTERM, so finish before that
+    let shutdown = Shutdown::listen(Duration::from_secs(shutdown_timeout))?;
+
+    // Start metrics HTTP server
+    let metrics_clone = metrics.clone();
+    let store_clone = store.clone();
+    let make_svc = make_service_fn(move |_| {
+        let metrics = metrics_clone.clone();
+        let store = store_clone.clone();
+        async move {
+            Ok::<_, hyper::Error>(service_fn(move |req| {
+                serve_metrics(req, metrics.clone(), store.clone())
+            }))
+        }
+    });
+
+    // The server also stops once a bounded backfill reaches END_BLOCK
+    let (backfilled, backfill_done) = oneshot::channel::<()>();
+    let server_shutdown = shutdown.clone();
+    let addr = ([0, 0, 0, 0], 9093).into();
+    let server = Server::bind(&addr)
+        .serve(make_svc)
+        .with_graceful_shutdown(async move {
+            tokio::select! {
+                _ = server_shutdown.requested() => {}
+                _ = backfill_done => {}
+            }
+        });
+
+    info!("Metrics server listening on http://{}", addr);
+    let server_task = tokio::spawn(async move {
+        if let Err(e) = server.await {
+            error!("Metrics server error: {}", e);
+        }
+    });
+
+    // Backfill as fast as the batches allow, then poll for new blocks. Each
+    // batch commits with its checkpoint, so stopping between batches loses
+    // nothing.
+    let mut idle = Duration::ZERO;
+    loop {
+        tokio::select! {
+            biased;
+            _ = shutdown.clone().requested() => break,
+            _ = sleep(idle) => {}
+        }
+
+        idle = match indexer.sync().await {
+            Ok(true) if end_block.is_some() => {
+                info!("Reached END_BLOCK {}, backfill complete", end_block.unwrap());
+                let _ = backfilled.send(());
+                break;
+            }
+            Ok(true) => Duration::from_secs(poll_interval),
+            Ok(false) => Duration::ZERO,
+            Err(e) => {
+                error!("Indexing error: {}", e);
+                Duration::from_secs(poll_interval)
+            }
+        };
+    }
+
+    server_task.await?;
+
+    telemetry.shutdown().await;
+    info!("Shutdown complete");
+    Ok(())
+}
+
+// Most recent transactions returned by /accounts/<address>/transactions
+const DEFAULT_ACCOUNT_TX_LIMIT: usize = 50;
+
+async fn serve_metrics(
+    req: Request<Body>,
+    metrics: Arc<Metrics>,
+    store: Arc<Store>,
+) -> Result<Response<Body>, hyper::Error> {
+    let account = req
+        .uri()
+        .path()
+        .strip_prefix("/accounts/")
+        .and_then(|rest| rest.strip_suffix("/transactions"));
+
+    if req.uri().path() == "/metrics" {
+        let encoder = TextEncoder::new();
+        let metric_families = metrics.registry.gather();
+        let mut buffer = vec![];
+        encoder.encode(&metric_families, &mut buffer).unwrap();
+
+        Ok(Response::new(Body::from(buffer)))
+    } else if let Some(address) = account {
+        let limit = req
+            .uri()
+            .query()
+            .and_then(|query| query.split('&').find_map(|pair| pair.strip_prefix("limit=")))
+            .and_then(|limit| limit.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_ACCOUNT_TX_LIMIT);
+
+        match store.account_transactions(address, limit) {
+            Ok(transactions) => Ok(Response::builder()
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_vec(&transactions).unwrap()))
+                .unwrap()),
+            Err(e) => Ok(Response::builder()
+                .status(500)
+                .body(Body::from(e.to_string()))
+                .unwrap()),
+        }
+    } else if req.uri().path() == "/health" {
+        Ok(Response::new(Body::from("OK")))
+    } else {
+        Ok(Response::builder()
+            .status(404)
+            .body(Body::from("Not Found"))
+            .unwrap())
+    }
+}
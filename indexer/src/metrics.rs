@@ -0,0 +1,105 @@
+use prometheus::{IntCounter, IntGauge, Opts, Registry};
+
+pub struct Metrics {
+    pub registry: Registry,
+
+    // Progress metrics
+    indexed_height: IntGauge,
+    chain_height: IntGauge,
+    lag_blocks: IntGauge,
+    blocks_indexed: IntCounter,
+    transactions_indexed: IntCounter,
+
+    // Reorg metrics
+    reorg_rollbacks: IntCounter,
+    rolled_back_blocks: IntCounter,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let indexed_height = IntGauge::with_opts(
+            Opts::new("mordor_indexer_indexed_height", "Highest contiguous indexed block")
+                .namespace("etc"),
+        ).unwrap();
+
+        let chain_height = IntGauge::with_opts(
+            Opts::new("mordor_indexer_chain_height", "Chain tip reported by the node")
+                .namespace("etc"),
+        ).unwrap();
+
+        let lag_blocks = IntGauge::with_opts(
+            Opts::new("mordor_indexer_lag_blocks", "Blocks between the chain tip and the index")
+                .namespace("etc"),
+        ).unwrap();
+
+        let blocks_indexed = IntCounter::with_opts(
+            Opts::new("mordor_indexer_blocks_indexed_total", "Total blocks written to the index")
+                .namespace("etc"),
+        ).unwrap();
+
+        let transactions_indexed = IntCounter::with_opts(
+            Opts::new("mordor_indexer_transactions_indexed_total", "Total transactions written to the index")
+                .namespace("etc"),
+        ).unwrap();
+
+        let reorg_rollbacks = IntCounter::with_opts(
+            Opts::new("mordor_indexer_reorg_rollbacks_total", "Total reorgs rolled back in the index")
+                .namespace("etc"),
+        ).unwrap();
+
+        let rolled_back_blocks = IntCounter::with_opts(
+            Opts::new("mordor_indexer_rolled_back_blocks_total", "Total orphaned blocks removed from the index")
+                .namespace("etc"),
+        ).unwrap();
+
+        // Register all metrics
+        registry.register(Box::new(indexed_height.clone())).unwrap();
+        registry.register(Box::new(chain_height.clone())).unwrap();
+        registry.register(Box::new(lag_blocks.clone())).unwrap();
+        registry.register(Box::new(blocks_indexed.clone())).unwrap();
+        registry.register(Box::new(transactions_indexed.clone())).unwrap();
+        registry.register(Box::new(reorg_rollbacks.clone())).unwrap();
+        registry.register(Box::new(rolled_back_blocks.clone())).unwrap();
+
+        Self {
+            registry,
+            indexed_height,
+            chain_height,
+            lag_blocks,
+            blocks_indexed,
+            transactions_indexed,
+            reorg_rollbacks,
+            rolled_back_blocks,
+        }
+    }
+
+    pub fn set_indexed_height(&self, height: u64) {
+        self.indexed_height.set(height as i64);
+    }
+
+    pub fn set_chain_height(&self, height: u64) {
+        self.chain_height.set(height as i64);
+    }
+
+    pub fn set_lag_blocks(&self, blocks: u64) {
+        self.lag_blocks.set(blocks as i64);
+    }
+
+    pub fn inc_blocks_indexed(&self, count: u64) {
+        self.blocks_indexed.inc_by(count);
+    }
+
+    pub fn inc_transactions_indexed(&self, count: u64) {
+        self.transactions_indexed.inc_by(count);
+    }
+
+    pub fn inc_reorg_rollbacks(&self) {
+        self.reorg_rollbacks.inc();
+    }
+
+    pub fn inc_rolled_back_blocks(&self, count: u64) {
+        self.rolled_back_blocks.inc_by(count);
+    }
+}
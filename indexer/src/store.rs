@@ -0,0 +1,411 @@
+use anyhow::Result;
+use ethers::types::{Block, Transaction, TransactionReceipt, H256};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Mutex;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS blocks (
+    number       INTEGER PRIMARY KEY,
+    hash         TEXT NOT NULL,
+    parent_hash  TEXT NOT NULL,
+    timestamp    INTEGER NOT NULL,
+    miner        TEXT NOT NULL,
+    difficulty   TEXT NOT NULL,
+    gas_used     INTEGER NOT NULL,
+    gas_limit    INTEGER NOT NULL,
+    tx_count     INTEGER NOT NULL,
+    uncle_count  INTEGER NOT NULL,
+    size         INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS transactions (
+    hash          TEXT PRIMARY KEY,
+    block_number  INTEGER NOT NULL,
+    tx_index      INTEGER NOT NULL,
+    from_address  TEXT NOT NULL,
+    to_address    TEXT,
+    value         TEXT NOT NULL,
+    nonce         INTEGER NOT NULL,
+    gas           INTEGER NOT NULL,
+    gas_price     TEXT NOT NULL,
+    selector      TEXT
+);
+
+CREATE TABLE IF NOT EXISTS receipts (
+    tx_hash              TEXT PRIMARY KEY,
+    block_number         INTEGER NOT NULL,
+    status               INTEGER,
+    gas_used             INTEGER NOT NULL,
+    cumulative_gas_used  INTEGER NOT NULL,
+    contract_address     TEXT,
+    log_count            INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS checkpoint (
+    id      INTEGER PRIMARY KEY CHECK (id = 0),
+    number  INTEGER NOT NULL,
+    hash    TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_transactions_block ON transactions (block_number);
+CREATE INDEX IF NOT EXISTS idx_transactions_from ON transactions (from_address);
+CREATE INDEX IF NOT EXISTS idx_transactions_to ON transactions (to_address);
+CREATE INDEX IF NOT EXISTS idx_receipts_block ON receipts (block_number);
+";
+
+/// A block with its transactions and their receipts, as written in one go.
+pub struct IndexedBlock {
+    pub block: Block<Transaction>,
+    pub receipts: Vec<TransactionReceipt>,
+}
+
+impl IndexedBlock {
+    pub fn number(&self) -> u64 {
+        self.block.number.unwrap_or_default().as_u64()
+    }
+
+    pub fn hash(&self) -> H256 {
+        self.block.hash.unwrap_or_default()
+    }
+}
+
+/// An indexed transaction sent from or to an account, newest first.
+#[derive(Serialize)]
+pub struct AccountTransaction {
+    pub hash: String,
+    pub block_number: u64,
+    pub timestamp: u64,
+    pub from: String,
+    pub to: Option<String>,
+    // Wei, as a decimal string
+    pub value: String,
+    pub nonce: u64,
+    pub status: Option<u64>,
+    pub gas_used: Option<u64>,
+}
+
+/// SQLite-backed history of headers, transaction and receipt summaries.
+/// The checkpoint always points at the highest contiguous indexed block.
+pub struct Store {
+    conn: Mutex<Connection>,
+}
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        // WAL lets other tools read the index while we write
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        conn.execute_batch(SCHEMA)?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn checkpoint(&self) -> Result<Option<(u64, H256)>> {
+        let conn = self.conn.lock().unwrap();
+        let row = conn
+            .query_row("SELECT number, hash FROM checkpoint WHERE id = 0", [], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })
+            .optional()?;
+
+        match row {
+            Some((number, hash)) => Ok(Some((number as u64, hash.parse()?))),
+            None => Ok(None),
+        }
+    }
+
+    pub fn hash_at(&self, number: u64) -> Result<Option<H256>> {
+        let conn = self.conn.lock().unwrap();
+        let hash = conn
+            .query_row(
+                "SELECT hash FROM blocks WHERE number = ?1",
+                [number as i64],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+
+        match hash {
+            Some(hash) => Ok(Some(hash.parse()?)),
+            None => Ok(None),
+        }
+    }
+
+    // Addresses are matched in the lowercase form they are stored in
+    pub fn account_transactions(&self, address: &str, limit: usize) -> Result<Vec<AccountTransaction>> {
+        let conn = self.conn.lock().unwrap();
+        let mut query = conn.prepare_cached(
+            "SELECT t.hash, t.block_number, b.timestamp, t.from_address, t.to_address, t.value, t.nonce,
+                    r.status, r.gas_used
+             FROM transactions t
+             JOIN blocks b ON b.number = t.block_number
+             LEFT JOIN receipts r ON r.tx_hash = t.hash
+             WHERE t.from_address = ?1 OR t.to_address = ?1
+             ORDER BY t.block_number DESC, t.tx_index DESC
+             LIMIT ?2",
+        )?;
+
+        let rows = query.query_map(params![address.to_lowercase(), limit as i64], |row| {
+            Ok(AccountTransaction {
+                hash: row.get(0)?,
+                block_number: row.get::<_, i64>(1)? as u64,
+                timestamp: row.get::<_, i64>(2)? as u64,
+                from: row.get(3)?,
+                to: row.get(4)?,
+                value: row.get(5)?,
+                nonce: row.get::<_, i64>(6)? as u64,
+                status: row.get::<_, Option<i64>>(7)?.map(|status| status as u64),
+                gas_used: row.get::<_, Option<i64>>(8)?.map(|gas| gas as u64),
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    // Writes the blocks and advances the checkpoint in a single transaction,
+    // so an interrupted run never leaves a partially indexed block behind
+    pub fn insert_blocks(&self, blocks: &[IndexedBlock]) -> Result<()> {
+        let last = match blocks.last() {
+            Some(last) => last,
+            None => return Ok(()),
+        };
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        {
+            let mut insert_block = tx.prepare_cached(
+                "INSERT OR REPLACE INTO blocks
+                 (number, hash, parent_hash, timestamp, miner, difficulty, gas_used, gas_limit, tx_count, uncle_count, size)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            )?;
+            let mut insert_tx = tx.prepare_cached(
+                "INSERT OR REPLACE INTO transactions
+                 (hash, block_number, tx_index, from_address, to_address, value, nonce, gas, gas_price, selector)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            )?;
+            let mut insert_receipt = tx.prepare_cached(
+                "INSERT OR REPLACE INTO receipts
+                 (tx_hash, block_number, status, gas_used, cumulative_gas_used, contract_address, log_count)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+
+            for indexed in blocks {
+                let block = &indexed.block;
+                let number = indexed.number() as i64;
+
+                insert_block.execute(params![
+                    number,
+                    format!("{:?}", indexed.hash()),
+                    format!("{:?}", block.parent_hash),
+                    block.timestamp.as_u64() as i64,
+                    format!("{:?}", block.author.unwrap_or_default()),
+                    block.difficulty.to_string(),
+                    block.gas_used.as_u64() as i64,
+                    block.gas_limit.as_u64() as i64,
+                    block.transactions.len() as i64,
+                    block.uncles.len() as i64,
+                    block.size.map(|size| size.as_u64() as i64),
+                ])?;
+
+                for transaction in &block.transactions {
+                    let selector = match (transaction.to, transaction.input.len() >= 4) {
+                        (Some(_), true) => Some(format!("0x{}", hex(&transaction.input[..4]))),
+                        _ => None,
+                    };
+
+                    insert_tx.execute(params![
+                        format!("{:?}", transaction.hash),
+                        number,
+                        transaction.transaction_index.unwrap_or_default().as_u64() as i64,
+                        format!("{:?}", transaction.from),
+                        transaction.to.map(|to| format!("{:?}", to)),
+                        transaction.value.to_string(),
+                        transaction.nonce.as_u64() as i64,
+                        transaction.gas.as_u64() as i64,
+                        transaction.gas_price.unwrap_or_default().to_string(),
+                        selector,
+                    ])?;
+                }
+
+                for receipt in &indexed.receipts {
+                    insert_receipt.execute(params![
+                        format!("{:?}", receipt.transaction_hash),
+                        number,
+                        receipt.status.map(|status| status.as_u64() as i64),
+                        receipt.gas_used.unwrap_or_default().as_u64() as i64,
+                        receipt.cumulative_gas_used.as_u64() as i64,
+                        receipt.contract_address.map(|address| format!("{:?}", address)),
+                        receipt.logs.len() as i64,
+                    ])?;
+                }
+            }
+
+            tx.execute(
+                "INSERT OR REPLACE INTO checkpoint (id, number, hash) VALUES (0, ?1, ?2)",
+                params![last.number() as i64, format!("{:?}", last.hash())],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    // Deletes everything above `number` and moves the checkpoint back to the
+    // highest block left. Returns how many blocks were removed.
+    pub fn rollback_to(&self, number: u64) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let above = number as i64;
+
+        let removed = tx.execute("DELETE FROM blocks WHERE number > ?1", [above])?;
+        tx.execute("DELETE FROM transactions WHERE block_number > ?1", [above])?;
+        tx.execute("DELETE FROM receipts WHERE block_number > ?1", [above])?;
+
+        let remaining = tx
+            .query_row(
+                "SELECT number, hash FROM blocks ORDER BY number DESC LIMIT 1",
+                [],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()?;
+
+        match remaining {
+            Some((number, hash)) => {
+                tx.execute(
+                    "INSERT OR REPLACE INTO checkpoint (id, number, hash) VALUES (0, ?1, ?2)",
+                    params![number, hash],
+                )?;
+            }
+            None => {
+                tx.execute("DELETE FROM checkpoint", [])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(removed)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{Address, Transaction, U64};
+
+    fn hash(number: u64) -> H256 {
+        H256::from_low_u64_be(number + 1)
+    }
+
+    fn block(number: u64, transactions: Vec<Transaction>) -> IndexedBlock {
+        let receipts = transactions
+            .iter()
+            .map(|tx| TransactionReceipt {
+                transaction_hash: tx.hash,
+                status: Some(U64::one()),
+                gas_used: Some(21_000.into()),
+                ..Default::default()
+            })
+            .collect();
+        IndexedBlock {
+            block: Block {
+                number: Some(number.into()),
+                hash: Some(hash(number)),
+                parent_hash: number.checked_sub(1).map(hash).unwrap_or_default(),
+                timestamp: (1_700_000_000 + number * 13).into(),
+                transactions,
+                ..Default::default()
+            },
+            receipts,
+        }
+    }
+
+    fn transfer(number: u64, index: u64, from: Address, to: Address) -> Transaction {
+        Transaction {
+            hash: H256::from_low_u64_be(number * 100 + index + 1),
+            block_number: Some(number.into()),
+            transaction_index: Some(index.into()),
+            from,
+            to: Some(to),
+            value: 1_000.into(),
+            ..Default::default()
+        }
+    }
+
+    fn blocks(store: &Store) -> i64 {
+        let conn = store.conn.lock().unwrap();
+        conn.query_row("SELECT COUNT(*) FROM blocks", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn insert_advances_checkpoint_to_last_block() {
+        let store = Store::open(":memory:").unwrap();
+        assert_eq!(store.checkpoint().unwrap(), None);
+
+        let (alice, bob) = (Address::repeat_byte(0xaa), Address::repeat_byte(0xbb));
+        let batch: Vec<IndexedBlock> = (0..5)
+            .map(|number| block(number, vec![transfer(number, 0, alice, bob)]))
+            .collect();
+        store.insert_blocks(&batch).unwrap();
+
+        assert_eq!(blocks(&store), 5);
+        assert_eq!(store.checkpoint().unwrap(), Some((4, hash(4))));
+        assert_eq!(store.hash_at(2).unwrap(), Some(hash(2)));
+        assert_eq!(store.hash_at(5).unwrap(), None);
+    }
+
+    #[test]
+    fn rollback_drops_blocks_above_height() {
+        let store = Store::open(":memory:").unwrap();
+        let (alice, bob) = (Address::repeat_byte(0xaa), Address::repeat_byte(0xbb));
+        let batch: Vec<IndexedBlock> = (0..6)
+            .map(|number| block(number, vec![transfer(number, 0, alice, bob)]))
+            .collect();
+        store.insert_blocks(&batch).unwrap();
+
+        assert_eq!(store.rollback_to(3).unwrap(), 2);
+        assert_eq!(blocks(&store), 4);
+        assert_eq!(store.checkpoint().unwrap(), Some((3, hash(3))));
+        assert_eq!(store.hash_at(4).unwrap(), None);
+
+        // Transactions and receipts of the dropped blocks go with them
+        let transactions = store.account_transactions(&format!("{:?}", alice), 10).unwrap();
+        let numbers: Vec<u64> = transactions.iter().map(|tx| tx.block_number).collect();
+        assert_eq!(numbers, [3, 2, 1, 0]);
+
+        assert_eq!(store.rollback_to(0).unwrap(), 3);
+        assert_eq!(store.checkpoint().unwrap(), Some((0, hash(0))));
+    }
+
+    // The /accounts/<address>/transactions route passes the address as given
+    #[test]
+    fn account_lookup_ignores_address_case() {
+        let store = Store::open(":memory:").unwrap();
+        let sender: Address = "0x52908400098527886E0F7030069857D2E4169EE7".parse().unwrap();
+        let other = Address::repeat_byte(0xbb);
+        store
+            .insert_blocks(&[
+                block(0, vec![transfer(0, 0, sender, other)]),
+                block(1, vec![transfer(1, 0, other, other), transfer(1, 1, other, sender)]),
+            ])
+            .unwrap();
+
+        for address in [
+            "0x52908400098527886E0F7030069857D2E4169EE7",
+            "0x52908400098527886e0f7030069857d2e4169ee7",
+        ] {
+            let transactions = store.account_transactions(address, 10).unwrap();
+            let hashes: Vec<String> = transactions.iter().map(|tx| tx.hash.clone()).collect();
+            assert_eq!(hashes, [format!("{:?}", H256::from_low_u64_be(102)), format!("{:?}", H256::from_low_u64_be(1))]);
+            assert_eq!(transactions[0].status, Some(1));
+            assert_eq!(transactions[0].gas_used, Some(21_000));
+        }
+    }
+}